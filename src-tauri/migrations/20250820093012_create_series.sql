@@ -0,0 +1,65 @@
+-- Add migration script here
+
+--------------------------------------------------------------------------------
+-- 1. 番剧（系列）表：同一部番剧在不同平台、不同写法的标题都归到同一条 series
+--------------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS series (
+      id               INTEGER PRIMARY KEY AUTOINCREMENT,
+      title            TEXT    NOT NULL,           -- 展示用标题（首次出现时的原始标题）
+      normalized_title TEXT    NOT NULL UNIQUE,    -- 归一化后的标题，用于匹配
+      image_url        TEXT    NOT NULL DEFAULT '',
+      created_time     INTEGER NOT NULL
+);
+
+-- 别名：归一化后的其它标题写法
+CREATE TABLE IF NOT EXISTS series_alias (
+      id               INTEGER PRIMARY KEY AUTOINCREMENT,
+      series_id        INTEGER NOT NULL,
+      alias            TEXT    NOT NULL,
+      normalized_alias TEXT    NOT NULL UNIQUE,
+      FOREIGN KEY (series_id)
+          REFERENCES series(id)
+          ON DELETE CASCADE
+);
+
+-- 平台外部 ID：如 B 站 season_id、腾讯 cid
+CREATE TABLE IF NOT EXISTS series_platform (
+      id          INTEGER PRIMARY KEY AUTOINCREMENT,
+      series_id   INTEGER NOT NULL,
+      platform    TEXT    NOT NULL,
+      external_id TEXT    NOT NULL,
+      UNIQUE(platform, external_id),
+      FOREIGN KEY (series_id)
+          REFERENCES series(id)
+          ON DELETE CASCADE
+);
+
+--------------------------------------------------------------------------------
+-- 2. 更新记录、关注记录关联到 series（历史数据在启动时由程序回填）
+--------------------------------------------------------------------------------
+ALTER TABLE ani_info ADD COLUMN series_id INTEGER REFERENCES series(id) ON DELETE SET NULL;
+ALTER TABLE ani_collect ADD COLUMN series_id INTEGER REFERENCES series(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_series_alias_series
+    ON series_alias(series_id);
+CREATE INDEX IF NOT EXISTS idx_series_platform_series
+    ON series_platform(series_id);
+CREATE INDEX IF NOT EXISTS idx_ani_info_series
+    ON ani_info(series_id, update_count);
+CREATE INDEX IF NOT EXISTS idx_ani_collect_series
+    ON ani_collect(series_id);
+
+--------------------------------------------------------------------------------
+-- 3. 观看后把同一 series 的关注记录都标记为已看
+--------------------------------------------------------------------------------
+DROP TRIGGER IF EXISTS trg_after_insert_watch;
+CREATE TRIGGER trg_after_insert_watch
+    AFTER INSERT ON ani_watch_history
+    FOR EACH ROW
+BEGIN
+    UPDATE ani_collect
+    SET is_watched = 1
+    WHERE user_id = NEW.user_id
+      AND (ani_item_id = NEW.ani_item_id
+        OR series_id = (SELECT series_id FROM ani_info WHERE id = NEW.ani_item_id));
+END;
@@ -0,0 +1,7 @@
+-- Add migration script here
+
+-- 关注记录保存归一化后的标题，用于在标题写法不同时匹配更新（历史数据在启动时由程序回填）
+ALTER TABLE ani_collect ADD COLUMN normalized_title TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_ani_collect_normalized_title
+    ON ani_collect(normalized_title);
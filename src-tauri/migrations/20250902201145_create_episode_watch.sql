@@ -0,0 +1,35 @@
+-- Add migration script here
+
+--------------------------------------------------------------------------------
+-- 1. 分集观看记录：按番剧记录看过的每一集
+--------------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS episode_watch (
+      id           INTEGER PRIMARY KEY AUTOINCREMENT,
+      user_id      TEXT    DEFAULT '',
+      series_id    INTEGER NOT NULL,
+      episode      INTEGER NOT NULL,
+      watched_time INTEGER NOT NULL,
+      UNIQUE(user_id, series_id, episode),
+      FOREIGN KEY (series_id)
+          REFERENCES series(id)
+          ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_episode_watch_series
+    ON episode_watch(series_id, episode);
+
+--------------------------------------------------------------------------------
+-- 2. 观看某条更新时，同时记录对应番剧的这一集（历史数据在启动时由程序回填）
+--------------------------------------------------------------------------------
+DROP TRIGGER IF EXISTS trg_after_insert_watch_episode;
+CREATE TRIGGER trg_after_insert_watch_episode
+    AFTER INSERT ON ani_watch_history
+    FOR EACH ROW
+BEGIN
+    INSERT OR IGNORE INTO episode_watch (user_id, series_id, episode, watched_time)
+    SELECT NEW.user_id, series_id, CAST(update_count AS INTEGER), NEW.watched_time
+    FROM ani_info
+    WHERE id = NEW.ani_item_id
+      AND series_id IS NOT NULL
+      AND update_count GLOB '[0-9]*';
+END;
@@ -0,0 +1,20 @@
+-- Add migration script here
+
+--------------------------------------------------------------------------------
+-- 1. 已发送通知的剧集：同一番剧同一集只通知一次
+--------------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS ani_notification (
+      id            INTEGER PRIMARY KEY AUTOINCREMENT,
+      series_id     INTEGER NOT NULL,
+      update_count  TEXT    NOT NULL,
+      notified_time INTEGER NOT NULL,
+      UNIQUE(series_id, update_count),
+      FOREIGN KEY (series_id)
+          REFERENCES series(id)
+          ON DELETE CASCADE
+);
+
+--------------------------------------------------------------------------------
+-- 2. 按番剧关闭通知
+--------------------------------------------------------------------------------
+ALTER TABLE series ADD COLUMN notify_muted BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,20 @@
+-- Add migration script here
+
+--------------------------------------------------------------------------------
+-- 通知投递记录：每个渠道每条通知的最终结果
+--------------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS notify_delivery (
+      id             INTEGER PRIMARY KEY AUTOINCREMENT,
+      channel        TEXT    NOT NULL,
+      ani_item_id    INTEGER,              -- 合并通知时为空
+      title          TEXT    NOT NULL,
+      status         TEXT    NOT NULL,     -- ok / failed
+      attempts       INTEGER NOT NULL,
+      error          TEXT,
+      delivered_time INTEGER NOT NULL,
+      FOREIGN KEY (ani_item_id)
+          REFERENCES ani_info(id)
+          ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_notify_delivery_time ON notify_delivery(delivered_time DESC);
@@ -0,0 +1,19 @@
+-- Add migration script here
+
+--------------------------------------------------------------------------------
+-- 任务执行记录：每个数据源任务每次执行的结果，用于判断数据源是否正常
+--------------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS task_run (
+      id            INTEGER PRIMARY KEY AUTOINCREMENT,
+      task_name     TEXT    NOT NULL,
+      manual        INTEGER NOT NULL DEFAULT 0,   -- 是否手动触发
+      status        TEXT    NOT NULL,             -- success / failed
+      attempts      INTEGER NOT NULL,
+      item_count    INTEGER,                      -- 失败时为空
+      error         TEXT,
+      warnings      TEXT,                         -- 解析告警，每行一条
+      started_time  INTEGER NOT NULL,
+      finished_time INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_run_name_time ON task_run(task_name, started_time DESC);
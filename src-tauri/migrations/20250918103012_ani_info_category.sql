@@ -0,0 +1,8 @@
+-- Add migration script here
+
+--------------------------------------------------------------------------------
+-- 更新记录所属的数据源分类（anime / drama 等），已有数据均来自动漫数据源
+--------------------------------------------------------------------------------
+ALTER TABLE ani_info ADD COLUMN category TEXT NOT NULL DEFAULT 'anime';
+
+CREATE INDEX IF NOT EXISTS idx_ani_info_category ON ani_info(category, update_time);
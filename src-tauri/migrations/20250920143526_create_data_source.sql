@@ -0,0 +1,20 @@
+-- Add migration script here
+
+--------------------------------------------------------------------------------
+-- 数据源：启动时由 config.yaml 中的 datasource 初始化，之后可在应用中增删改
+--------------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS data_source (
+      id            INTEGER PRIMARY KEY AUTOINCREMENT,
+      name          TEXT    NOT NULL UNIQUE,      -- 同时也是定时任务名
+      category      TEXT    NOT NULL DEFAULT 'anime',
+      url           TEXT    NOT NULL,
+      cmd           TEXT    NOT NULL,
+      cron_expr     TEXT    NOT NULL,
+      retry_times   INTEGER NOT NULL DEFAULT 0,
+      retry_policy  TEXT,                         -- 重试策略（JSON），为空时使用默认策略
+      enabled       INTEGER NOT NULL DEFAULT 1,
+      managed       INTEGER NOT NULL DEFAULT 1,   -- 是否跟随配置文件更新，在应用中修改过后为 0
+      deleted       INTEGER NOT NULL DEFAULT 0,   -- 已在应用中删除（保留记录，避免配置文件再次添加）
+      created_time  INTEGER NOT NULL,
+      updated_time  INTEGER NOT NULL
+);
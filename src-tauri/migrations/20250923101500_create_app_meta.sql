@@ -0,0 +1,9 @@
+-- Add migration script here
+
+--------------------------------------------------------------------------------
+-- 应用元数据：记录上次重建番剧匹配键时使用的标题规则等
+--------------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS app_meta (
+      key   TEXT PRIMARY KEY,
+      value TEXT NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Add migration script here
+
+-- 宽松匹配键：去掉季度后缀的标题，关注某一季后用于匹配同一部番剧的其它季（历史数据在启动时由程序回填）
+ALTER TABLE series ADD COLUMN base_title TEXT;
+ALTER TABLE ani_collect ADD COLUMN base_title TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_series_base_title
+    ON series(base_title);
+CREATE INDEX IF NOT EXISTS idx_ani_collect_base_title
+    ON ani_collect(base_title);
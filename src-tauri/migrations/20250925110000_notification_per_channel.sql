@@ -0,0 +1,32 @@
+-- Add migration script here
+PRAGMA foreign_keys = OFF;
+
+--------------------------------------------------------------------------------
+-- 1. 按渠道记录已发送的剧集：发送成功才记录，失败的剧集下一轮检查时重发
+--    channel 为空的是旧版本的记录，视为所有渠道都已发送
+--------------------------------------------------------------------------------
+CREATE TABLE ani_notification_new (
+      id            INTEGER PRIMARY KEY AUTOINCREMENT,
+      channel       TEXT    NOT NULL DEFAULT '',
+      series_id     INTEGER NOT NULL,
+      update_count  TEXT    NOT NULL,      -- 剧集键：集数，没有集数时为更新记录和更新时间
+      notified_time INTEGER NOT NULL,
+      UNIQUE(channel, series_id, update_count),
+      FOREIGN KEY (series_id)
+          REFERENCES series(id)
+          ON DELETE CASCADE
+);
+
+-- 2. 拷贝旧数据
+INSERT INTO ani_notification_new (id, channel, series_id, update_count, notified_time)
+SELECT id, '', series_id, update_count, notified_time
+FROM ani_notification;
+
+-- 3. 删除旧表，重命名新表
+DROP TABLE ani_notification;
+ALTER TABLE ani_notification_new RENAME TO ani_notification;
+
+CREATE INDEX IF NOT EXISTS idx_ani_notification_series
+    ON ani_notification(series_id, update_count);
+
+PRAGMA foreign_keys = ON;
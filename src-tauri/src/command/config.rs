@@ -0,0 +1,28 @@
+use crate::configuration::{app_config_dir, ConfigStatus};
+use crate::types::ApiResponse;
+use crate::validation::{load_checked_configuration, ConfigDiagnostic};
+use serde_json::json;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+/// 查询配置目录和最近一次加载配置的检查结果（为空表示配置正常）
+#[tauri::command]
+pub async fn query_config_status(
+    app: AppHandle,
+    status: State<'_, Arc<ConfigStatus>>,
+) -> Result<ApiResponse, String> {
+    Ok(ApiResponse::ok(json!({
+        "configDir": app_config_dir(&app),
+        "diagnostics": status.diagnostics(),
+    })))
+}
+
+/// 检查配置，content 为准备保存的 config.yaml 内容，为空时检查当前的配置文件
+#[tauri::command]
+pub async fn check_config(
+    app: AppHandle,
+    content: Option<String>,
+) -> Result<ApiResponse<Vec<ConfigDiagnostic>>, String> {
+    let (_, diagnostics) = load_checked_configuration(&app_config_dir(&app), content.as_deref());
+    Ok(ApiResponse::ok(diagnostics))
+}
@@ -0,0 +1,153 @@
+use crate::command::platforms::cmd_names;
+use crate::configuration::DataSource;
+use crate::db::common::ge_db_pool;
+use crate::db::data_source::{
+    delete_data_source as delete_data_source_db, insert_data_source, list_data_source,
+    set_data_source_enabled as set_data_source_enabled_db,
+    update_data_source as update_data_source_db,
+};
+use crate::state::AppState;
+use crate::tasks::commands::build_cmd_map;
+use crate::tasks::config_watcher::apply_data_sources;
+use crate::tasks::scheduler::Scheduler;
+use crate::types::ApiResponse;
+use crate::validation::validate_data_source;
+use log::{info, warn};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::State;
+
+/// 查询所有数据源（已删除的除外）
+#[tauri::command]
+pub async fn query_data_source_list(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ApiResponse, String> {
+    match list_data_source(ge_db_pool(&state.db)).await {
+        Ok(list) => Ok(ApiResponse::ok(json!(list))),
+        Err(e) => Ok(ApiResponse::err(format!("查询失败：{e}"))),
+    }
+}
+
+/// 新增数据源，立即创建定时任务并执行一次
+#[tauri::command]
+pub async fn add_data_source(
+    state: State<'_, Arc<AppState>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+    category: String,
+    source: DataSource,
+) -> Result<ApiResponse, String> {
+    if let Err(e) = check_data_source(&category, &source) {
+        return Ok(ApiResponse::err(e));
+    }
+    match insert_data_source(
+        ge_db_pool(&state.db),
+        &category,
+        &source,
+        state.clock.now_millis(),
+    )
+    .await
+    {
+        Ok(id) => {
+            apply_changes(&state, &scheduler).await;
+            Ok(ApiResponse::ok(json!({ "id": id })))
+        }
+        Err(e) => Ok(ApiResponse::err(format!("新增数据源失败：{e}"))),
+    }
+}
+
+/// 修改数据源，修改后不再跟随配置文件更新，定时任务按新配置重新排期
+#[tauri::command]
+pub async fn update_data_source(
+    state: State<'_, Arc<AppState>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+    id: i64,
+    category: String,
+    source: DataSource,
+) -> Result<ApiResponse, String> {
+    if let Err(e) = check_data_source(&category, &source) {
+        return Ok(ApiResponse::err(e));
+    }
+    match update_data_source_db(
+        ge_db_pool(&state.db),
+        id,
+        &category,
+        &source,
+        state.clock.now_millis(),
+    )
+    .await
+    {
+        Ok(()) => {
+            apply_changes(&state, &scheduler).await;
+            Ok(ApiResponse::ok(json!({ "id": id })))
+        }
+        Err(e) => Ok(ApiResponse::err(format!("修改数据源失败：{e}"))),
+    }
+}
+
+/// 删除数据源，配置文件中的同名数据源也不会再添加
+#[tauri::command]
+pub async fn delete_data_source(
+    state: State<'_, Arc<AppState>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+    id: i64,
+) -> Result<ApiResponse, String> {
+    match delete_data_source_db(ge_db_pool(&state.db), id, state.clock.now_millis()).await {
+        Ok(()) => {
+            apply_changes(&state, &scheduler).await;
+            Ok(ApiResponse::ok(json!({ "id": id })))
+        }
+        Err(e) => Ok(ApiResponse::err(format!("删除数据源失败：{e}"))),
+    }
+}
+
+/// 启用或停用数据源
+#[tauri::command]
+pub async fn set_data_source_enabled(
+    state: State<'_, Arc<AppState>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+    id: i64,
+    enabled: bool,
+) -> Result<ApiResponse, String> {
+    match set_data_source_enabled_db(ge_db_pool(&state.db), id, enabled, state.clock.now_millis())
+        .await
+    {
+        Ok(()) => {
+            apply_changes(&state, &scheduler).await;
+            Ok(ApiResponse::ok(json!({ "id": id, "enabled": enabled })))
+        }
+        Err(e) => Ok(ApiResponse::err(format!("修改数据源失败：{e}"))),
+    }
+}
+
+/// 检查提交的数据源，只拒绝错误，告警照常保存
+fn check_data_source(category: &str, source: &DataSource) -> Result<(), String> {
+    if category.trim().is_empty() {
+        return Err("分类不能为空".to_string());
+    }
+    let cmds: HashSet<String> = cmd_names().into_iter().collect();
+    let errors: Vec<String> = validate_data_source("", source, &cmds)
+        .iter()
+        .filter(|d| d.is_error())
+        .map(|d| d.to_string())
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("；"))
+    }
+}
+
+/// 数据源保存后同步到调度器
+pub(crate) async fn apply_changes(state: &AppState, scheduler: &Scheduler) {
+    let cmd_map = build_cmd_map(state.clock.clone(), state.http.clone());
+    match apply_data_sources(ge_db_pool(&state.db), scheduler, &cmd_map).await {
+        Ok(diff) => info!(
+            "数据源已更新：新增 {} 个、修改 {} 个、移除 {} 个任务",
+            diff.added.len(),
+            diff.changed.len(),
+            diff.removed.len()
+        ),
+        Err(e) => warn!("数据源已保存，更新定时任务失败：{e:#}"),
+    }
+}
@@ -1,2 +1,5 @@
+pub mod config;
+pub mod data_source;
 pub mod platforms;
+pub mod scheduler;
 pub mod service;
@@ -1,93 +1,114 @@
+use crate::command::platforms::error::FetchError;
+use crate::command::platforms::Platform;
 use crate::types::{AniItem, AniItemResult, ApiResponse};
-use crate::utils::date_utils::{get_today_slash, get_today_weekday};
+use crate::utils::date_utils::{format_date, Clock, DateFormat};
 use crate::utils::extract_number;
-use crate::utils::http_client::http_client;
-use base64::{engine::general_purpose, Engine as _};
+use crate::utils::http_client::HttpClient;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
 use log::{debug, info};
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
 use std::collections::HashMap;
 
-#[tauri::command]
-pub async fn fetch_agedm_image(url: String) -> Result<String, String> {
-    // 新建异步 Reqwest 客户端
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(&url)
-        .header("Referer", "https://www.agedm.vip/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 先把 Content-Type 拷贝到一个拥有 String
-    let ct: String = resp
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "image/jpeg".to_string());
-
-    // 这时 resp 不再被借用，可以放心移动
-    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
-
-    // 转 base64，并拼成 Data URL
-    let b64 = general_purpose::STANDARD.encode(&bytes);
-    Ok(format!("data:{ct};base64,{b64}"))
-}
+/// AGE 动漫
+pub struct Agedm;
 
-#[tauri::command]
-pub async fn fetch_agedm_ani_data(url: String) -> Result<ApiResponse<AniItemResult>, String> {
-    // 1. 发请求拿响应
-    let client = http_client()?; // 若失败会 early-return Err(String)
-    let response = client
-        .get(&url)
-        .header("Referer", "https://www.agedm.tv")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 2. 解析成 HTML 文本
-    let body = response.text().await.map_err(|e| e.to_string())?;
-    debug!(
-        "解析从 AGE 动漫获取到的 HTML，前 200 字符：\n{}",
-        &body[..200.min(body.len())]
-    );
-    info!("成功获取 AGE 动漫今日更新数据");
+#[async_trait]
+impl Platform for Agedm {
+    fn id(&self) -> &'static str {
+        "agedm"
+    }
 
-    // 3. 解析 HTML，找「今天」区块
-    let document = Html::parse_document(&body);
-    // 1. 找到那个包含“今天 (土曜日)”按钮的 <div class="video_list_box recent_update ...">
+    fn name(&self) -> &'static str {
+        "AGE动漫"
+    }
+
+    fn referer(&self) -> &'static str {
+        "https://www.agedm.tv"
+    }
+
+    async fn fetch_schedule(
+        &self,
+        http: &HttpClient,
+        url: &str,
+        clock: &dyn Clock,
+    ) -> Result<ApiResponse<AniItemResult>, FetchError> {
+        // 1. 发请求拿响应
+        let response = self.request(http, self.header_profile(), url).await?;
+
+        // 2. 解析成 HTML 文本
+        let body = response.text().await?;
+        debug!(
+            "解析从 AGE 动漫获取到的 HTML，前 200 字符：\n{}",
+            &body[..200.min(body.len())]
+        );
+        info!("成功获取 AGE 动漫今日更新数据");
+
+        let result = parse_agedm_html(&body, clock.today());
+        Ok(ApiResponse::ok(result))
+    }
+}
+
+/// 解析 AGE 动漫更新页 HTML，按区块标题还原日期，提取所有可识别日期的番剧
+pub fn parse_agedm_html(body: &str, today: NaiveDate) -> AniItemResult {
+    // 1. 解析 HTML，找到所有 <div class="video_list_box recent_update ...">
+    let document = Html::parse_document(body);
     let list_box_sel = Selector::parse("div.video_list_box.recent_update").unwrap();
     let button_sel = Selector::parse("button.btn-danger").unwrap();
 
-    // 遍历所有最近更新块，选第一个按钮文本以“今天”开头的那个
-    // 先尝试找 “今天” 对应的列表节点
-    let maybe_today_box = document.select(&list_box_sel).find(|bx| {
-        bx.select(&button_sel)
-            .any(|btn| btn.text().any(|t| t.trim().starts_with("今天")))
-    });
-
-    // 4. 如果没找到「今天」区块，返回空结果
-    let today_box = if let Some(bx) = maybe_today_box {
-        bx
-    } else {
-        let empty: AniItemResult = HashMap::new();
-        return Ok(ApiResponse::ok(empty));
-    };
-
-    // 2. 在这个块里，选出所有的视频单元
+    // 2. 根据区块按钮文本（如“今天 (日曜日)”）确定日期，无法识别的区块跳过
+    let mut result: AniItemResult = HashMap::new();
+    for bx in document.select(&list_box_sel) {
+        let Some(date) = bx
+            .select(&button_sel)
+            .find_map(|btn| parse_box_date(&btn.text().collect::<String>(), today))
+        else {
+            continue;
+        };
+        let comics = parse_box_items(&bx, date);
+        result
+            .entry(format_date(date, DateFormat::Slash))
+            .or_default()
+            .extend(comics);
+    }
+
+    info!(
+        "成功提取到 {} 部今日更新的动漫",
+        result
+            .get(&format_date(today, DateFormat::Slash))
+            .map_or(0, Vec::len)
+    );
+    result
+}
+
+/// 解析区块按钮文本对应的日期，支持“今天/昨天/前天”和“2025-07-11”形式
+fn parse_box_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = text.trim();
+    let head = text.split_whitespace().next()?;
+    match head {
+        "今天" => Some(today),
+        "昨天" => Some(today - Duration::days(1)),
+        "前天" => Some(today - Duration::days(2)),
+        _ => NaiveDate::parse_from_str(head, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(head, "%Y/%m/%d"))
+            .ok(),
+    }
+}
+
+/// 解析单个区块中的视频单元
+fn parse_box_items(bx: &ElementRef, date: NaiveDate) -> Vec<AniItem> {
+    // 选出所有的视频单元
     let col_sel = Selector::parse("div.row > div.col").unwrap();
     let img_sel = Selector::parse("img.video_thumbs").unwrap();
     let span_sel = Selector::parse("span.video_item--info").unwrap();
     let a_sel = Selector::parse("div.video_item-title a").unwrap();
 
-    // 3. 初始化一个空的 result
-    let weekday_str = get_today_weekday().name_cn.to_string();
-    // 今天的日期，比如 "2025/07/13"
-    let today_date = get_today_slash();
+    // 更新日期，比如 "2025/07/13"
+    let update_time = format_date(date, DateFormat::Slash);
     // 动漫aniitem的列表
     let mut comics: Vec<AniItem> = Vec::new();
     // 过滤出符合条件的 <div class="col g-2 position-relative">
-    for col in today_box.select(&col_sel) {
+    for col in bx.select(&col_sel) {
         // 封面
         let image_url = col
             .select(&img_sel)
@@ -113,40 +134,42 @@ pub async fn fetch_agedm_ani_data(url: String) -> Result<ApiResponse<AniItemResu
             .unwrap_or_default();
 
         // 标题和详情链接
-        let (title, detail_url) = col
+        let (title, detail_url, external_id) = col
             .select(&a_sel)
             .next()
             .map(|a| {
-                let href = a
-                    .value()
-                    .attr("href")
-                    .unwrap_or_default() // &str
+                let raw_href = a.value().attr("href").unwrap_or_default();
+                // 番剧 ID 为详情页链接最后一段，如 /detail/20250096
+                let external_id = raw_href
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                let href = raw_href
                     .replacen("http://", "https://", 1) // 先把协议换好
                     .replacen("/detail/", "/play/", 1) // 再把路径段换好
                     .trim_end_matches('/') // 去掉末尾多余斜杠（可选）
                     .to_string(); // 拷贝成 String
                 let href = format!("{href}/1/{update_count}");
                 let txt = a.text().collect::<String>().trim().to_string();
-                (txt, href)
+                (txt, href, external_id)
             })
             .unwrap_or_default();
 
-        info!("识别到更新：{title} {update_info}");
+        info!("识别到更新：{update_time} {title} {update_info}");
         comics.push(AniItem {
             title,
             detail_url,
-            update_time: today_date.clone(),
+            update_time: update_time.clone(),
             platform: "agedm".to_string(),
             image_url,
             update_count,
             update_info,
+            external_id,
+            category: String::new(),
         });
     }
 
-    info!("成功提取到 {} 部今日更新的动漫", comics.len());
-
-    // 6. 构建并返回结果
-    let mut result: AniItemResult = HashMap::new();
-    result.insert(weekday_str, comics);
-    Ok(ApiResponse::ok(result))
+    comics
 }
@@ -1,56 +1,55 @@
+use crate::command::platforms::error::FetchError;
+use crate::command::platforms::Platform;
 use crate::types::{AniItem, AniItemResult, ApiResponse};
-use crate::utils::date_utils::{get_today_slash, get_today_weekday};
+use crate::utils::date_utils::{format_date, Clock, DateFormat};
+use crate::utils::http_client::{HeaderProfile, HttpClient};
 use crate::utils::{clean_text, extract_number};
-use base64::{engine::general_purpose, Engine as _};
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
 use log::{error, info};
 use serde_json::Value;
 use std::collections::HashMap;
 
-#[tauri::command]
-pub async fn fetch_bilibili_image(url: String) -> Result<String, String> {
-    // 新建异步 Reqwest 客户端
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(&url)
-        .header("Referer", "https://www.bilibili.com/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 先把 Content-Type 拷贝到一个拥有 String
-    let ct: String = resp
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "image/jpeg".to_string());
-
-    // 这时 resp 不再被借用，可以放心移动
-    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
-
-    // 转 base64，并拼成 Data URL
-    let b64 = general_purpose::STANDARD.encode(&bytes);
-    Ok(format!("data:{ct};base64,{b64}"))
-}
+/// 哔哩哔哩
+pub struct Bilibili;
+
+#[async_trait]
+impl Platform for Bilibili {
+    fn id(&self) -> &'static str {
+        "bilibili"
+    }
+
+    fn name(&self) -> &'static str {
+        "哔哩哔哩"
+    }
+
+    fn referer(&self) -> &'static str {
+        "https://www.bilibili.com/"
+    }
+
+    fn header_profile(&self) -> HeaderProfile {
+        HeaderProfile::Api
+    }
 
-#[tauri::command]
-pub async fn fetch_bilibili_ani_data(url: String) -> Result<ApiResponse<AniItemResult>, String> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("Referer", "https://www.bilibili.com/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    async fn fetch_schedule(
+        &self,
+        http: &HttpClient,
+        url: &str,
+        clock: &dyn Clock,
+    ) -> Result<ApiResponse<AniItemResult>, FetchError> {
+        let response = self.request(http, self.header_profile(), url).await?;
 
-    let json_value: Value = response.json().await.map_err(|e| e.to_string())?;
+        let json_value: Value = response.json().await?;
 
-    let result: AniItemResult = process_json_value(&json_value);
-    Ok(ApiResponse::ok(result))
+        let result: AniItemResult = process_json_value(&json_value, clock.today());
+        Ok(ApiResponse::ok(result))
+    }
 }
 
-/// 解析原始 JSON，往 `result` 中填充当天已发布的番剧更新
-fn process_json_value(json_value: &Value) -> AniItemResult {
+/// 解析原始 JSON，按真实更新日期返回时间线上所有天的番剧
+///
+/// 今天及以前只保留已发布的剧集，今天之后的剧集视为即将更新
+pub fn process_json_value(json_value: &Value, today: NaiveDate) -> AniItemResult {
     // 1. 验证响应状态和数据结构
     let code = json_value.get("code").and_then(Value::as_i64).unwrap_or(-1);
     if code != 0 || !json_value.get("result").is_some_and(Value::is_array) {
@@ -68,51 +67,62 @@ fn process_json_value(json_value: &Value) -> AniItemResult {
         }
     };
 
-    // 3. 找到今天的数据 (is_today == 1)
-    let today = match days
+    // 3. 找到今天的数据索引 (is_today == 1)，其余日期按与今天的偏移推算
+    let today_idx = match days
         .iter()
-        .find(|day| day.get("is_today").and_then(Value::as_i64) == Some(1))
+        .position(|day| day.get("is_today").and_then(Value::as_i64) == Some(1))
     {
-        Some(day) => day,
+        Some(idx) => idx,
         None => {
             info!("今日没有更新");
             // 即使没有更新，也返回包含空列表的结果
-            return create_empty_result();
+            return create_empty_result(today);
         }
     };
 
-    // 4. 处理剧集数据
-    let weekday = get_today_weekday().name_cn.to_string();
-    let mut comics: Vec<AniItem> = Vec::new();
-
-    if let Some(eps) = today.get("episodes").and_then(Value::as_array) {
-        for ep in eps
-            .iter()
-            .filter(|e| e.get("published").and_then(Value::as_i64) == Some(1))
-        {
-            let item = parse_item(ep);
-            info!("识别到更新：{} {}", item.title, item.update_info);
-            comics.push(item);
+    // 4. 处理每一天的剧集数据
+    let mut result = HashMap::new();
+    for (idx, day) in days.iter().enumerate() {
+        let date = today + Duration::days(idx as i64 - today_idx as i64);
+        let upcoming = date > today;
+        let mut comics: Vec<AniItem> = Vec::new();
+
+        if let Some(eps) = day.get("episodes").and_then(Value::as_array) {
+            for ep in eps
+                .iter()
+                .filter(|e| upcoming || e.get("published").and_then(Value::as_i64) == Some(1))
+            {
+                let item = parse_item(ep, date);
+                info!(
+                    "识别到更新：{} {} {}",
+                    item.update_time, item.title, item.update_info
+                );
+                comics.push(item);
+            }
         }
+        result.insert(format_date(date, DateFormat::Slash), comics);
     }
 
-    info!("成功提取到 {} 部今日更新的动漫", comics.len());
+    let today_key = format_date(today, DateFormat::Slash);
+    info!(
+        "成功提取到 {} 天的动漫，其中今日更新 {} 部",
+        result.len(),
+        result.get(&today_key).map_or(0, Vec::len)
+    );
 
     // 5. 构建结果
-    let mut result = HashMap::new();
-    result.insert(weekday, comics);
     result
 }
 
 // 辅助函数：创建空结果
-fn create_empty_result() -> AniItemResult {
-    let weekday = get_today_weekday().name_cn.to_string();
+fn create_empty_result(today: NaiveDate) -> AniItemResult {
     let mut result = HashMap::new();
-    result.insert(weekday, Vec::new());
+    result.insert(format_date(today, DateFormat::Slash), Vec::new());
     result
 }
+
 /// 根据单个 episode JSON 构建 AniItem
-fn parse_item(ep: &Value) -> AniItem {
+fn parse_item(ep: &Value, date: NaiveDate) -> AniItem {
     // pub_index
     let pub_index = ep
         .get("pub_index")
@@ -143,6 +153,13 @@ fn parse_item(ep: &Value) -> AniItem {
         .unwrap_or_default();
     let detail_url = format!("https://www.bilibili.com/bangumi/play/ep{episode_id}");
 
+    // external_id 取番剧的 season_id
+    let external_id = ep
+        .get("season_id")
+        .and_then(Value::as_i64)
+        .map(|id| id.to_string())
+        .unwrap_or_default();
+
     // title 清理
     let raw_title = ep.get("title").and_then(Value::as_str).unwrap_or("");
     let title = clean_text(raw_title);
@@ -155,6 +172,8 @@ fn parse_item(ep: &Value) -> AniItem {
         update_info,
         image_url,
         detail_url,
-        update_time: get_today_slash(),
+        update_time: format_date(date, DateFormat::Slash),
+        external_id,
+        category: String::new(),
     }
 }
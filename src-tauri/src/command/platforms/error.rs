@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use std::time::Duration;
+use thiserror::Error;
+
+/// 抓取平台数据时的错误，按是否值得重试分类
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum FetchError {
+    /// 网络错误：DNS 解析、连接失败、超时等
+    #[error("网络错误：{0}")]
+    Network(String),
+    /// 服务器返回了非 2xx 状态码
+    #[error("HTTP {status}：{message}")]
+    Status { status: u16, message: String },
+    /// 被限流（HTTP 429），retry_after 为服务器要求的等待时间
+    #[error("请求过于频繁，被限流")]
+    RateLimited { retry_after: Option<Duration> },
+    /// 解析失败，通常是页面结构发生了变化
+    #[error("解析失败：{0}")]
+    Parse(String),
+    /// 其他不可重试的错误，如配置有误
+    #[error("{0}")]
+    Other(String),
+}
+
+impl FetchError {
+    /// 是否为临时性错误：网络错误、限流和 5xx 值得重试，4xx 和解析失败重试也无济于事
+    pub fn is_transient(&self) -> bool {
+        match self {
+            FetchError::Network(_) | FetchError::RateLimited { .. } => true,
+            FetchError::Status { status, .. } => *status >= 500 || *status == 408,
+            FetchError::Parse(_) | FetchError::Other(_) => false,
+        }
+    }
+
+    /// 服务器要求的重试等待时间
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            FetchError::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(e: reqwest::Error) -> Self {
+        if let Some(status) = e.status() {
+            FetchError::Status {
+                status: status.as_u16(),
+                message: e.to_string(),
+            }
+        } else if e.is_decode() {
+            FetchError::Parse(e.to_string())
+        } else if e.is_builder() {
+            FetchError::Other(e.to_string())
+        } else {
+            FetchError::Network(e.to_string())
+        }
+    }
+}
+
+/// 检查响应的状态码，429 时读取 Retry-After
+pub fn check_response(resp: Response) -> Result<Response, FetchError> {
+    let status = resp.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(FetchError::RateLimited {
+            retry_after: response_retry_after(&resp),
+        });
+    }
+    if !status.is_success() {
+        return Err(FetchError::Status {
+            status: status.as_u16(),
+            message: status.canonical_reason().unwrap_or("未知错误").to_string(),
+        });
+    }
+    Ok(resp)
+}
+
+/// 响应中服务器要求的等待时间（Retry-After）
+pub fn response_retry_after(resp: &Response) -> Option<Duration> {
+    resp.headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_retry_after(v, Utc::now()))
+}
+
+/// 解析 Retry-After：秒数或 HTTP 日期
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = Utc.with_ymd_and_hms(2025, 7, 13, 8, 0, 0).unwrap();
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 13 Jul 2025 08:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
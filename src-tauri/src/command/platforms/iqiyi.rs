@@ -1,61 +1,63 @@
+use crate::command::platforms::error::FetchError;
+use crate::command::platforms::Platform;
 use crate::types::{AniItem, AniItemResult, ApiResponse};
-use crate::utils::date_utils::{get_today_slash, get_today_weekday};
+use crate::utils::date_utils::{
+    format_date, get_weekday, latest_weekday_on_or_before, Clock, DateFormat,
+};
+use crate::utils::http_client::{HeaderProfile, HttpClient};
 use crate::utils::{clean_text, extract_number};
-use base64::{engine::general_purpose, Engine as _};
-use chrono::{Datelike, Local};
+use async_trait::async_trait;
+use chrono::NaiveDate;
 use log::{error, info};
 use serde_json::Value;
 use std::collections::HashMap;
 
-#[tauri::command]
-pub async fn fetch_iqiyi_image(url: String) -> Result<String, String> {
-    // 新建异步 Reqwest 客户端
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(&url)
-        .header("Referer", "https://www.iqiyi.com/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 先把 Content-Type 拷贝到一个拥有 String
-    let ct: String = resp
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "image/jpeg".to_string());
-
-    // 这时 resp 不再被借用，可以放心移动
-    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
-
-    // 转 base64，并拼成 Data URL
-    let b64 = general_purpose::STANDARD.encode(&bytes);
-    Ok(format!("data:{ct};base64,{b64}"))
-}
+/// 爱奇艺
+pub struct Iqiyi;
+
+#[async_trait]
+impl Platform for Iqiyi {
+    fn id(&self) -> &'static str {
+        "iqiyi"
+    }
+
+    fn name(&self) -> &'static str {
+        "爱奇艺"
+    }
+
+    fn referer(&self) -> &'static str {
+        "https://www.iqiyi.com/"
+    }
 
-#[tauri::command]
-pub async fn fetch_iqiyi_ani_data(url: String) -> Result<ApiResponse<AniItemResult>, String> {
-    // 1. 发请求拿 JSON
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("Referer", "https://www.iqiyi.com/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 2. 反序列化成 serde_json::Value
-    let json_value: Value = response.json().await.map_err(|e| e.to_string())?;
-
-    // 3. 处理解析成 AniItemResult
-    let result: AniItemResult = process_json_value(&json_value);
-
-    // 4. 返回统一包装
-    Ok(ApiResponse::ok(result))
+    fn header_profile(&self) -> HeaderProfile {
+        HeaderProfile::Api
+    }
+
+    async fn fetch_schedule(
+        &self,
+        http: &HttpClient,
+        url: &str,
+        clock: &dyn Clock,
+    ) -> Result<ApiResponse<AniItemResult>, FetchError> {
+        // 1. 发请求拿 JSON
+        let response = self.request(http, self.header_profile(), url).await?;
+
+        // 2. 反序列化成 serde_json::Value
+        let json_value: Value = response.json().await?;
+
+        // 3. 处理解析成 AniItemResult
+        let result: AniItemResult = process_json_value(&json_value, clock.today());
+
+        // 4. 返回统一包装
+        Ok(ApiResponse::ok(result))
+    }
 }
 
-fn process_json_value(json_value: &Value) -> AniItemResult {
+/// 解析爱奇艺频道接口 JSON，提取“追番表”中全部七天的更新
+///
+/// 追番表按星期一到星期日分页，每页展示该星期最近一次的更新，
+/// 因此日期取 `today` 当天或之前最近的同一星期
+pub fn process_json_value(json_value: &Value, today: NaiveDate) -> AniItemResult {
     // 验证响应格式
     if json_value.get("code") != Some(&Value::from(0)) {
         error!("接口返回错误状态: {json_value}");
@@ -72,49 +74,56 @@ fn process_json_value(json_value: &Value) -> AniItemResult {
 
     info!("成功获取爱奇艺追番表数据");
 
-    // 提前计算周信息
-    let current_weekday = Local::now().weekday().num_days_from_monday() as usize;
-    let weekday_str = get_today_weekday().name_cn.to_string();
-
     // 查找追番表数据
-    let today_data = items
+    let week_data = match items
         .iter()
         .find(|item| item.get("title") == Some(&Value::from("追番表")))
         .and_then(|item| item.get("video"))
         .and_then(|video| video.as_array())
-        .and_then(|video_list| video_list.get(current_weekday))
-        .and_then(|day_data| day_data.get("data"))
-        .and_then(|data| data.as_array());
+    {
+        Some(list) => list,
+        None => {
+            error!("未找到追番表数据");
+            return HashMap::new();
+        }
+    };
 
     // 处理追番数据
     let mut result = HashMap::new();
 
-    match today_data {
-        Some(list) if !list.is_empty() => {
-            let items: Vec<AniItem> = list
-                .iter()
-                .filter_map(parse_item)
-                .inspect(|res| {
-                    info!("识别到更新：{} {}", res.title, res.update_info);
-                })
-                .collect();
-            info!("成功提取到 {} 部今日更新的动漫", items.len());
-            result.insert(weekday_str, items);
-        }
-        Some(_) => {
-            // 空数组
-            info!("今日没有更新");
-            result.insert(weekday_str, vec![]);
-        }
-        None => {
-            error!("未找到今日追番数据，当前星期索引: {current_weekday}");
-        }
+    for (idx, day_data) in week_data.iter().take(7).enumerate() {
+        let date = latest_weekday_on_or_before(today, idx as u32);
+        let list = day_data
+            .get("data")
+            .and_then(|data| data.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let items: Vec<AniItem> = list
+            .iter()
+            .filter_map(|ep| parse_item(ep, date))
+            .inspect(|res| {
+                info!(
+                    "识别到更新：{} {} {}",
+                    res.update_time, res.title, res.update_info
+                );
+            })
+            .collect();
+        result.insert(format_date(date, DateFormat::Slash), items);
+    }
+
+    let today_key = format_date(today, DateFormat::Slash);
+    match result.get(&today_key) {
+        Some(items) => info!("成功提取到 {} 部今日更新的动漫", items.len()),
+        None => error!(
+            "未找到今日追番数据，当前星期索引: {}",
+            get_weekday(today).num_from_mon
+        ),
     }
 
     result
 }
 
-fn parse_item(ep: &Value) -> Option<AniItem> {
+fn parse_item(ep: &Value, date: NaiveDate) -> Option<AniItem> {
     let title = ep.get("display_name")?.as_str().unwrap_or("").to_string();
     let raw_update_info = ep
         .get("dq_updatestatus")?
@@ -138,6 +147,13 @@ fn parse_item(ep: &Value) -> Option<AniItem> {
         .unwrap_or("")
         .to_string();
 
+    // 专辑的 qipu_id
+    let external_id = ep
+        .get("qipu_id")
+        .and_then(Value::as_i64)
+        .map(|id| id.to_string())
+        .unwrap_or_default();
+
     Some(AniItem {
         platform: "iqiyi".to_string(), // 平台名可以写死或传参
         title: clean_text(&title),
@@ -145,6 +161,8 @@ fn parse_item(ep: &Value) -> Option<AniItem> {
         update_info: raw_update_info,
         image_url,
         detail_url,
-        update_time: get_today_slash(),
+        update_time: format_date(date, DateFormat::Slash),
+        external_id,
+        category: String::new(),
     })
 }
@@ -1,70 +1,75 @@
+use crate::command::platforms::error::FetchError;
+use crate::command::platforms::Platform;
 use crate::types::{AniItem, AniItemResult, ApiResponse};
-use crate::utils::date_utils::{get_today_slash, get_today_weekday};
-use base64::{engine::general_purpose, Engine as _};
+use crate::utils::date_utils::{format_date, Clock, DateFormat};
+use crate::utils::http_client::{HeaderProfile, HttpClient};
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
 use log::{debug, info};
 use reqwest::Url;
 use scraper::{Html, Selector};
 use std::collections::HashMap;
 
-#[tauri::command]
-pub async fn fetch_mikanani_image(url: String) -> Result<String, String> {
-    // 新建异步 Reqwest 客户端
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(&url)
-        .header("Referer", "https://mikanani.me/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 先把 Content-Type 拷贝到一个拥有 String
-    let ct: String = resp
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "image/jpeg".to_string());
-
-    // 这时 resp 不再被借用，可以放心移动
-    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
-
-    // 转 base64，并拼成 Data URL
-    let b64 = general_purpose::STANDARD.encode(&bytes);
-    Ok(format!("data:{ct};base64,{b64}"))
+/// 蜜柑计划
+pub struct Mikanani;
+
+#[async_trait]
+impl Platform for Mikanani {
+    fn id(&self) -> &'static str {
+        "mikanani"
+    }
+
+    fn name(&self) -> &'static str {
+        "蜜柑计划"
+    }
+
+    fn referer(&self) -> &'static str {
+        "https://mikanani.me/"
+    }
+
+    fn header_profile(&self) -> HeaderProfile {
+        HeaderProfile::Minimal
+    }
+
+    async fn fetch_schedule(
+        &self,
+        http: &HttpClient,
+        url: &str,
+        clock: &dyn Clock,
+    ) -> Result<ApiResponse<AniItemResult>, FetchError> {
+        // 1. 发请求拿响应
+        let response = self.request(http, self.header_profile(), url).await?;
+
+        // 2. 解析成 HTML 文本
+        let body = response.text().await?;
+        debug!(
+            "解析从 Mikanani 获取到的 HTML，前 200 字符：\n{}",
+            &body[..200.min(body.len())]
+        );
+        info!("成功获取蜜柑计划追番表数据");
+        // base_url 用于拼接相对链接
+        let base_url = Url::parse(url).map_err(|e| FetchError::Other(e.to_string()))?;
+        let result = parse_mikanani_html(&body, &base_url, clock.today());
+
+        // 返回包装后的结果
+        Ok(ApiResponse::ok(result))
+    }
 }
 
-#[tauri::command]
-pub async fn fetch_mikanani_ani_data(url: String) -> Result<ApiResponse<AniItemResult>, String> {
-    // 1. 发请求拿响应
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("Referer", "https://mikanani.me/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 2. 解析成 HTML 文本
-    let body = response.text().await.map_err(|e| e.to_string())?;
-    debug!(
-        "解析从 Mikanani 获取到的 HTML，前 200 字符：\n{}",
-        &body[..200.min(body.len())]
-    );
-    info!("成功获取蜜柑计划追番表数据");
+/// 解析蜜柑计划首页 HTML，提取最近七天（含 `today`）内更新的番剧
+///
+/// 首页每个条目都带有最近一次的更新日期，直接以它作为真实的更新日期
+pub fn parse_mikanani_html(body: &str, base_url: &Url, today: NaiveDate) -> AniItemResult {
     // 解析 HTML
-    let document = Html::parse_document(&body);
+    let document = Html::parse_document(body);
     // 找到所有 <li> 节点
     let li_sel = Selector::parse("li").unwrap();
-    // base_url 用于拼接相对链接
-    let base_url = Url::parse(&url).map_err(|e| e.to_string())?;
+    // 最早保留到 6 天前
+    let earliest = today - Duration::days(6);
 
-    // 3. 初始化一个空的 result
+    // 初始化结果，今天即使没有更新也保留空列表
     let mut result: AniItemResult = HashMap::new();
-    let weekday_str = get_today_weekday().name_cn.to_string();
-    // 今天的日期，比如 "2025/07/13"
-    let today_date = get_today_slash();
-    // 动漫aniitem的列表
-    let mut comics: Vec<AniItem> = Vec::new();
+    result.insert(format_date(today, DateFormat::Slash), Vec::new());
     // 过滤出符合条件的 <li>
     for li in document.select(&li_sel) {
         // 必须有 <div class="num-node text-center">
@@ -75,30 +80,38 @@ pub async fn fetch_mikanani_ani_data(url: String) -> Result<ApiResponse<AniItemR
         {
             continue;
         }
-        // 且 <div class="date-text"> 包含 today_date
-        if let Some(div) = li.select(&Selector::parse("div.date-text").unwrap()).next() {
-            let text = div.text().collect::<String>();
-            if !text.contains(&today_date) {
-                continue;
-            }
-        } else {
+
+        // 构建 Ani，且 <div class="date-text"> 中的日期需在最近七天内
+        let Some(item) = build_mikanani_item(base_url, &li) else {
+            continue;
+        };
+        let in_range = NaiveDate::parse_from_str(&item.update_time, "%Y/%m/%d")
+            .is_ok_and(|date| date >= earliest && date <= today);
+        if !in_range {
             continue;
         }
 
-        // 构建 Ani 并加入结果
-        if let Some(item) = build_mikanani_item(&base_url, &li) {
-            info!("识别到更新：{} {}", item.title, item.update_info);
-            comics.push(item);
-        }
+        info!(
+            "识别到更新：{} {} {}",
+            item.update_time, item.title, item.update_info
+        );
+        result
+            .entry(item.update_time.clone())
+            .or_default()
+            .push(item);
     }
-    info!("成功提取到 {} 部今日更新的动漫", comics.len());
-    result.insert(weekday_str, comics);
-
-    // 7. 返回包装后的结果
-    Ok(ApiResponse::ok(result))
+    info!(
+        "成功提取到 {} 部今日更新的动漫",
+        result[&format_date(today, DateFormat::Slash)].len()
+    );
+    result
 }
 
-fn build_mikanani_item(base_url: &Url, li: &scraper::element_ref::ElementRef) -> Option<AniItem> {
+/// 从单个 `<li>` 节点构建 AniItem
+pub fn build_mikanani_item(
+    base_url: &Url,
+    li: &scraper::element_ref::ElementRef,
+) -> Option<AniItem> {
     // <a class="an-text" title="..." href="...">
     let a_sel = Selector::parse("a.an-text").unwrap();
     let a = li.select(&a_sel).next()?;
@@ -130,6 +143,8 @@ fn build_mikanani_item(base_url: &Url, li: &scraper::element_ref::ElementRef) ->
     // 详情 URL 来自 <a>.href
     let href = a.value().attr("href").unwrap_or("");
     let detail_url = base_url.join(href).ok()?.to_string();
+    // 番组 ID 为链接最后一段，如 /Home/Bangumi/3663
+    let external_id = href.rsplit('/').next().unwrap_or_default().to_string();
 
     Some(AniItem {
         platform: "mikanani".to_string(),
@@ -139,5 +154,7 @@ fn build_mikanani_item(base_url: &Url, li: &scraper::element_ref::ElementRef) ->
         image_url,
         detail_url,
         update_time,
+        external_id,
+        category: String::new(),
     })
 }
@@ -1,6 +1,180 @@
 pub mod agedm;
 pub mod bilibili;
+pub mod error;
 pub mod iqiyi;
 pub mod mikanani;
 pub mod tencent;
 pub mod youku;
+
+use crate::command::platforms::error::FetchError;
+use crate::state::AppState;
+use crate::types::{AniItemResult, ApiResponse};
+use crate::utils::date_utils::Clock;
+use crate::utils::http_client::{HeaderProfile, HttpClient};
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use reqwest::header::REFERER;
+use reqwest::Response;
+use std::sync::Arc;
+use tauri::State;
+
+/// 视频平台抽象：每个站点实现一次，注册到 [`registry`] 后即可同时被调度器和前端使用
+#[async_trait]
+pub trait Platform: Send + Sync {
+    /// 平台标识，与 `AniItem.platform` 保持一致
+    fn id(&self) -> &'static str;
+
+    /// 平台显示名称
+    fn name(&self) -> &'static str;
+
+    /// 请求该平台资源时默认携带的 Referer
+    fn referer(&self) -> &'static str;
+
+    /// 配置文件中 `cmd` 字段对应的命令名
+    fn cmd(&self) -> String {
+        format!("fetch_{}_ani_data", self.id())
+    }
+
+    /// 兼容旧配置的其它命令名
+    fn cmd_aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// 请求追番表时使用的请求头模板
+    fn header_profile(&self) -> HeaderProfile {
+        HeaderProfile::Browser
+    }
+
+    /// 请求该平台的资源：按平台的请求头模板和配置携带请求头及 Referer，经过该站点的限流后发送
+    async fn request(
+        &self,
+        http: &HttpClient,
+        profile: HeaderProfile,
+        url: &str,
+    ) -> Result<Response, FetchError> {
+        let request = http
+            .get(self.id(), profile, url)
+            .header(REFERER, self.referer());
+        http.send(self.id(), request).await
+    }
+
+    /// 抓取追番表数据，“今天”由 `clock` 决定
+    async fn fetch_schedule(
+        &self,
+        http: &HttpClient,
+        url: &str,
+        clock: &dyn Clock,
+    ) -> Result<ApiResponse<AniItemResult>, FetchError>;
+
+    /// 抓取图片并转为 Data URL
+    async fn fetch_image(&self, http: &HttpClient, url: &str) -> Result<String, String> {
+        let resp = self
+            .request(http, HeaderProfile::Image, url)
+            .await
+            .map_err(|e| e.to_string())?;
+        image_data_url(resp).await
+    }
+}
+
+/// 所有已注册的平台
+static PLATFORMS: Lazy<Vec<Arc<dyn Platform>>> = Lazy::new(|| {
+    vec![
+        Arc::new(bilibili::Bilibili),
+        Arc::new(iqiyi::Iqiyi),
+        Arc::new(mikanani::Mikanani),
+        Arc::new(tencent::Tencent),
+        Arc::new(youku::Youku),
+        Arc::new(agedm::Agedm),
+    ]
+});
+
+/// 获取平台注册表
+pub fn registry() -> &'static [Arc<dyn Platform>] {
+    &PLATFORMS
+}
+
+/// 根据平台标识查找平台
+pub fn find_platform(id: &str) -> Option<Arc<dyn Platform>> {
+    registry().iter().find(|p| p.id() == id).cloned()
+}
+
+/// 配置文件中可用的所有命令名（含兼容旧配置的别名）
+pub fn cmd_names() -> Vec<String> {
+    registry()
+        .iter()
+        .flat_map(|p| {
+            let aliases = p.cmd_aliases().iter().map(|a| a.to_string());
+            std::iter::once(p.cmd()).chain(aliases)
+        })
+        .collect()
+}
+
+/// 读取图片响应，并拼成 base64 Data URL
+pub async fn image_data_url(resp: Response) -> Result<String, String> {
+    // 先把 Content-Type 拷贝到一个拥有 String
+    let ct: String = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "image/jpeg".to_string());
+
+    // 这时 resp 不再被借用，可以放心移动
+    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
+
+    // 转 base64，并拼成 Data URL
+    let b64 = general_purpose::STANDARD.encode(&bytes);
+    Ok(format!("data:{ct};base64,{b64}"))
+}
+
+/// 抓取指定平台的追番表数据
+#[tauri::command]
+pub async fn fetch_ani_data(
+    state: State<'_, Arc<AppState>>,
+    platform: String,
+    url: String,
+) -> Result<ApiResponse<AniItemResult>, String> {
+    let platform = find_platform(&platform).ok_or(format!("未知的平台：{platform}"))?;
+    platform
+        .fetch_schedule(&state.http, &url, state.clock.as_ref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 通过指定平台获取图片（携带该平台的 Referer）
+#[tauri::command]
+pub async fn fetch_ani_image(
+    state: State<'_, Arc<AppState>>,
+    platform: String,
+    url: String,
+) -> Result<String, String> {
+    let platform = find_platform(&platform).ok_or(format!("未知的平台：{platform}"))?;
+    platform.fetch_image(&state.http, &url).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_registry_ids_unique() {
+        let ids: HashSet<&str> = registry().iter().map(|p| p.id()).collect();
+        assert_eq!(ids.len(), registry().len());
+    }
+
+    #[test]
+    fn test_registry_cmd_names() {
+        let cmds: Vec<String> = registry().iter().map(|p| p.cmd()).collect();
+        // 与 config.yaml 中已有的 cmd 名保持兼容
+        assert!(cmds.contains(&"fetch_bilibili_ani_data".to_string()));
+        assert!(cmds.contains(&"fetch_qq_ani_data".to_string()));
+        // 电视剧数据源使用的命令名
+        assert!(registry()
+            .iter()
+            .any(|p| p.cmd_aliases().contains(&"tencent_parser")));
+        assert!(find_platform("tencent").is_some());
+        assert!(find_platform("unknown").is_none());
+    }
+}
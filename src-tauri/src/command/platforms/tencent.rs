@@ -1,60 +1,71 @@
+use crate::command::platforms::error::FetchError;
+use crate::command::platforms::Platform;
 use crate::types::{AniItem, AniItemResult, ApiResponse};
-use crate::utils::date_utils::{get_today_slash, get_today_weekday};
+use crate::utils::date_utils::{format_date, latest_weekday_on_or_before, Clock, DateFormat};
 use crate::utils::extract_number;
-use crate::utils::http_client::http_client;
-use base64::engine::general_purpose;
-use base64::Engine;
+use crate::utils::http_client::{HeaderProfile, HttpClient};
+use async_trait::async_trait;
+use chrono::NaiveDate;
 use log::{debug, info, warn};
-use reqwest::Client;
 use scraper::{Html, Selector};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::error::Error;
 
-#[tauri::command]
-pub async fn fetch_qq_image(url: String) -> Result<String, String> {
-    // 新建异步 Reqwest 客户端
-    let client = http_client()?;
-    let resp = client
-        .get(&url)
-        .header("Referer", "https://v.qq.com/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // 先把 Content-Type 拷贝到一个拥有 String
-    let ct: String = resp
-        .headers()
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "image/jpeg".to_string());
-
-    // 这时 resp 不再被借用，可以放心移动
-    let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
-
-    // 转 base64，并拼成 Data URL
-    let b64 = general_purpose::STANDARD.encode(&bytes);
-    Ok(format!("data:{ct};base64,{b64}"))
+/// 腾讯视频
+pub struct Tencent;
+
+#[async_trait]
+impl Platform for Tencent {
+    fn id(&self) -> &'static str {
+        "tencent"
+    }
+
+    fn name(&self) -> &'static str {
+        "腾讯视频"
+    }
+
+    fn referer(&self) -> &'static str {
+        "https://v.qq.com/"
+    }
+
+    fn cmd(&self) -> String {
+        "fetch_qq_ani_data".to_string()
+    }
+
+    /// 电视剧数据源配置中使用的命令名
+    fn cmd_aliases(&self) -> &'static [&'static str] {
+        &["tencent_parser"]
+    }
+
+    fn header_profile(&self) -> HeaderProfile {
+        HeaderProfile::Minimal
+    }
+
+    /// 获取腾讯视频频道（动漫、电视剧）今日更新数据
+    async fn fetch_schedule(
+        &self,
+        http: &HttpClient,
+        url: &str,
+        clock: &dyn Clock,
+    ) -> Result<ApiResponse<AniItemResult>, FetchError> {
+        let resp = self.request(http, self.header_profile(), url).await?;
+        let text = resp.text().await?;
+        debug!(
+            "解析从 腾讯视频 获取到的 HTML，前 200 字符：\n{}",
+            &text[..200.min(text.len())]
+        );
+        let result = parse_tencent_html(&text, clock.today()).map_err(FetchError::Parse)?;
+        Ok(ApiResponse::ok(result))
+    }
 }
 
-/// 获取腾讯视频动漫频道今日更新数据
-#[tauri::command]
-pub async fn fetch_qq_ani_data(url: String) -> Result<ApiResponse<AniItemResult>, String> {
-    let client = Client::new();
-    let resp = client
-        .get(&url)
-        .header("Referer", "https://v.qq.com/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    let text = resp.text().await.map_err(|e| e.to_string())?;
-    debug!(
-        "解析从 腾讯视频 获取到的 HTML，前 200 字符：\n{}",
-        &text[..200.min(text.len())]
-    );
+/// 解析腾讯视频频道页 HTML，提取“每日更新”模块中页面已加载的各标签页视频列表
+///
+/// 当前选中的标签页对应今天，其余标签页按“周一”~“周日”还原为最近一次的更新日期
+pub fn parse_tencent_html(html: &str, today: NaiveDate) -> Result<AniItemResult, String> {
     // 1. 从 HTML 中提取嵌入的 JSON 数据
-    let data: Value = extract_vikor_json(text).map_err(|e| e.to_string())?;
+    let data: Value = extract_vikor_json(html).map_err(|e| e.to_string())?;
     let pinia = data
         .get("_piniaState")
         .and_then(Value::as_object)
@@ -62,49 +73,82 @@ pub async fn fetch_qq_ani_data(url: String) -> Result<ApiResponse<AniItemResult>
         .unwrap_or_default();
 
     // 2. 找到“每日更新”模块
-    let daily = find_daily_card(&pinia);
-    if daily.is_none() {
-        warn!("未找到“每日更新”模块，返回空结果。");
-        let empty: AniItemResult = HashMap::new();
-        return Ok(ApiResponse::ok(empty));
-    }
+    let daily = match find_daily_card(&pinia) {
+        Some(daily) => daily,
+        None => {
+            warn!("未找到“每日更新”模块，返回空结果。");
+            return Ok(HashMap::new());
+        }
+    };
     info!("成功获取腾讯视频动漫追番表数据");
-    let daily = daily.unwrap();
 
-    // 3. 提取今日更新视频列表
-    let tab_id = daily
+    // 3. 标签页 ID -> 更新日期
+    let selected_tab = daily
         .get("selectedTabId")
         .and_then(Value::as_str)
         .unwrap_or("");
-    let today_videos = daily
+    let mut tab_dates: HashMap<String, NaiveDate> = daily
+        .get("tabList")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|tab| {
+            let id = tab.get("tabId").and_then(Value::as_str)?;
+            let name = tab.get("tabName").and_then(Value::as_str)?;
+            let idx = WEEKDAY_TABS.iter().position(|w| *w == name.trim())?;
+            Some((
+                id.to_string(),
+                latest_weekday_on_or_before(today, idx as u32),
+            ))
+        })
+        .collect();
+    tab_dates.insert(selected_tab.to_string(), today);
+
+    // 4. 逐个标签页构建结果并记录日志
+    let banner_map = daily
         .get("videoBannerMap")
         .and_then(Value::as_object)
-        .and_then(|m| m.get(tab_id))
-        .and_then(Value::as_object)
-        .and_then(|m| m.get("videoList"))
-        .and_then(Value::as_array)
         .cloned()
         .unwrap_or_default();
-
-    // 4. 构建结果并记录日志
-    let mut comics: Vec<AniItem> = Vec::new();
-    for item in today_videos.iter().filter_map(build_aniitem) {
-        info!("识别到更新：{}, {}", item.title, item.update_info);
-        comics.push(item);
+    let mut result: AniItemResult = HashMap::new();
+    result.insert(format_date(today, DateFormat::Slash), Vec::new());
+    for (tab_id, banner) in &banner_map {
+        let Some(&date) = tab_dates.get(tab_id) else {
+            continue;
+        };
+        let comics = result
+            .entry(format_date(date, DateFormat::Slash))
+            .or_default();
+        for item in banner
+            .get("videoList")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|item| build_aniitem(item, date))
+        {
+            info!(
+                "识别到更新：{}, {}, {}",
+                item.update_time, item.title, item.update_info
+            );
+            comics.push(item);
+        }
     }
 
-    // 5. 存储并返回
-    let weekday = get_today_weekday().name_cn.to_string();
-    info!("成功提取到 {} 部今日更新的动漫", comics.len());
-    let mut result: AniItemResult = HashMap::new();
-    result.insert(weekday, comics);
-    Ok(ApiResponse::ok(result))
+    // 5. 返回
+    info!(
+        "成功提取到 {} 部今日更新的动漫",
+        result[&format_date(today, DateFormat::Slash)].len()
+    );
+    Ok(result)
 }
 
+/// “每日更新”标签页名称，按星期一到星期日排列
+const WEEKDAY_TABS: [&str; 7] = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"];
+
 /// 从页面 HTML 中提取 window.__vikor__context__ 嵌入的 JSON
-pub fn extract_vikor_json(html: String) -> Result<Value, Box<dyn Error>> {
+pub fn extract_vikor_json(html: &str) -> Result<Value, Box<dyn Error>> {
     // 解析 HTML 文档
-    let document = Html::parse_document(&html);
+    let document = Html::parse_document(html);
 
     // 创建 script 标签选择器
     let selector = Selector::parse("script").unwrap();
@@ -138,26 +182,34 @@ pub fn extract_vikor_json(html: String) -> Result<Value, Box<dyn Error>> {
     Ok(data)
 }
 
-/// 在 _piniaState 中定位 moduleTitle 为 “每日更新” 的卡片数据
-fn find_daily_card(pinia: &serde_json::Map<String, Value>) -> Option<Value> {
-    let cards = pinia
+/// 各频道“每日更新”模块的标题：动漫频道为“每日更新”，电视剧频道为“追剧日历”
+const DAILY_MODULE_TITLES: [&str; 2] = ["每日更新", "追剧日历"];
+
+/// 在 _piniaState 中定位“每日更新”卡片数据
+///
+/// 按频道 ID 顺序查找页面中已加载的各频道模块，动漫频道为 100119，电视剧频道为 100113
+pub fn find_daily_card(pinia: &serde_json::Map<String, Value>) -> Option<Value> {
+    let modules = pinia
         .get("channelPageData")
         .and_then(Value::as_object)
         .and_then(|m| m.get("channelsModulesMap"))
-        .and_then(Value::as_object)
-        .and_then(|m| m.get("100119"))
-        .and_then(Value::as_object)
-        .and_then(|m| m.get("cardListData"))
-        .and_then(Value::as_array)
-        .cloned()
-        .unwrap_or_default();
-    cards
+        .and_then(Value::as_object)?;
+    let mut channel_ids: Vec<&String> = modules.keys().collect();
+    channel_ids.sort();
+    channel_ids
         .into_iter()
-        .find(|c| c.get("moduleTitle").and_then(Value::as_str) == Some("每日更新"))
+        .filter_map(|id| modules[id].get("cardListData").and_then(Value::as_array))
+        .flatten()
+        .find(|c| {
+            c.get("moduleTitle")
+                .and_then(Value::as_str)
+                .is_some_and(|title| DAILY_MODULE_TITLES.contains(&title))
+        })
+        .cloned()
 }
 
 /// 根据 JSON 构建 AniItem
-fn build_aniitem(item: &Value) -> Option<AniItem> {
+fn build_aniitem(item: &Value, date: NaiveDate) -> Option<AniItem> {
     let platform = "tencent".to_string();
     let title = item
         .get("title")
@@ -166,15 +218,25 @@ fn build_aniitem(item: &Value) -> Option<AniItem> {
         .trim()
         .to_string();
 
+    // 动漫的集数在角标 tag_4 中，电视剧没有角标时在副标题中（如“更新至12集”）
     let uni_img = item.get("uniImgTag").and_then(Value::as_str).unwrap_or("");
-    let update_info_obj: Value = serde_json::from_str(uni_img).ok()?;
-    let update_count = update_info_obj
-        .get("tag_4")
-        .and_then(Value::as_object)
-        .and_then(|o| o.get("text"))
-        .and_then(Value::as_str)
-        .unwrap_or("");
-    let update_count = extract_number(update_count)?.to_string();
+    let tag_text = serde_json::from_str::<Value>(uni_img)
+        .ok()
+        .and_then(|tags| {
+            tags.get("tag_4")
+                .and_then(|o| o.get("text"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+    let update_count = tag_text
+        .or_else(|| {
+            item.get("secondTitle")
+                .and_then(Value::as_str)
+                .filter(|t| t.contains('集'))
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
+    let update_count = extract_number(&update_count)?.to_string();
 
     let update_count_info = format!("更新至{update_count}集");
     let update_info = item
@@ -196,7 +258,7 @@ fn build_aniitem(item: &Value) -> Option<AniItem> {
     let cid = item.get("cid").and_then(Value::as_str).unwrap_or("");
     let detail_url = get_qq_video_url(cid);
 
-    let update_time = get_today_slash();
+    let update_time = format_date(date, DateFormat::Slash);
 
     Some(AniItem {
         platform,
@@ -206,6 +268,8 @@ fn build_aniitem(item: &Value) -> Option<AniItem> {
         image_url,
         detail_url,
         update_time,
+        external_id: cid.to_string(),
+        category: String::new(),
     })
 }
 
@@ -1,101 +1,80 @@
+use crate::command::platforms::error::FetchError;
+use crate::command::platforms::Platform;
 use crate::types::{AniItem, AniItemResult, ApiResponse};
-use crate::utils::date_utils::{get_today_slash, get_today_weekday};
+use crate::utils::date_utils::{format_date, latest_weekday_on_or_before, Clock, DateFormat};
 use crate::utils::extract_number;
-use crate::utils::http_client::http_client;
+use crate::utils::http_client::HttpClient;
 use anyhow::{anyhow, Context, Result};
-use base64::{engine::general_purpose, Engine as _};
+use async_trait::async_trait;
+use chrono::NaiveDate;
 use log::{debug, info};
 use scraper::{Html, Selector};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::HashSet;
 use url::Url;
 
-/// 全局 HTTP 客户端复用
-fn client() -> Result<reqwest::Client> {
-    http_client().map_err(|e| anyhow!("创建 HTTP 客户端失败: {}", e))
-}
+/// 优酷
+pub struct Youku;
 
-/// 获取图片并转为 Base64 Data URL
-async fn fetch_image_base64(url: &str, referer: &str) -> Result<String> {
-    let resp = client()?
-        .get(url)
-        .header(reqwest::header::REFERER, referer)
-        .send()
-        .await
-        .context("请求图片失败")?;
-
-    let headers = resp.headers().clone();
-    let bytes = resp.bytes().await.context("读取图片字节失败")?;
-    let content_type = headers
-        .get(reqwest::header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("application/octet-stream");
-
-    let b64 = general_purpose::STANDARD.encode(&bytes);
-    Ok(format!("data:{content_type};base64,{b64}"))
-}
+#[async_trait]
+impl Platform for Youku {
+    fn id(&self) -> &'static str {
+        "youku"
+    }
+
+    fn name(&self) -> &'static str {
+        "优酷"
+    }
+
+    fn referer(&self) -> &'static str {
+        "https://www.youku.com/"
+    }
 
-#[tauri::command]
-pub async fn fetch_youku_image(url: String) -> Result<String, String> {
-    fetch_image_base64(&url, "https://www.youku.com/")
-        .await
-        .map_err(|e| e.to_string())
+    async fn fetch_schedule(
+        &self,
+        http: &HttpClient,
+        url: &str,
+        clock: &dyn Clock,
+    ) -> std::result::Result<ApiResponse<AniItemResult>, FetchError> {
+        // 1. 请求页面并读取 HTML
+        let html = self
+            .request(http, self.header_profile(), url)
+            .await?
+            .text()
+            .await?;
+        debug!("Youku HTML 前200字符: {}", &html[..html.len().min(200)]);
+
+        // 2. 解析页面
+        let result = parse_youku_html(&html, clock.today())
+            .map_err(|e| FetchError::Parse(format!("{e:#}")))?;
+        Ok(ApiResponse::ok(result))
+    }
 }
 
-#[tauri::command]
-pub async fn fetch_youku_ani_data(url: String) -> Result<ApiResponse<AniItemResult>, String> {
-    // 1. 获取 HTTP 客户端
-    let client = client().map_err(|e| e.to_string())?;
-    // 2. 请求页面并读取 HTML
-    let html = client
-        .get(&url)
-        .header(reqwest::header::REFERER, "https://www.youku.com/")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?
-        .text()
-        .await
-        .map_err(|e| e.to_string())?;
-    debug!("Youku HTML 前200字符: {}", &html[..html.len().min(200)]);
-
-    // 3. 提取初始数据
-    let data = match extract_initial_data(&html) {
-        Ok(d) => d,
-        Err(e) => {
-            // 业务层面解析失败，返回 ApiResponse::err
-            return Ok(ApiResponse::err(format!("解析初始数据失败：{e}")));
-        }
-    };
+/// 解析优酷动漫频道页 HTML，提取“每日更新”中全部七天的番剧
+pub fn parse_youku_html(html: &str, today: NaiveDate) -> Result<AniItemResult> {
+    // 1. 提取初始数据
+    let data = extract_initial_data(html).context("解析初始数据失败")?;
 
-    // 4. 获取模块列表
+    // 2. 获取模块列表，没有找到模块时返回空结果
     let modules = match data.get("moduleList").and_then(Value::as_array) {
         Some(arr) => arr,
-        None => {
-            // 没有找到模块，返回空结果
-            let empty: AniItemResult = AniItemResult::new();
-            return Ok(ApiResponse::ok(empty));
-        }
-    };
-
-    // 5. 解析模块列表为 AniItem 列表
-    let comics = match process_module_list(modules) {
-        Ok(list) => list,
-        Err(e) => {
-            // 业务层面处理失败，同样返回 ApiResponse::err
-            return Ok(ApiResponse::err(format!("处理模块列表失败：{e}")));
-        }
+        None => return Ok(AniItemResult::new()),
     };
 
-    info!("提取到 {} 部今日更新动漫", comics.len());
-
-    // 6. 构造并返回成功结果
-    let mut result = AniItemResult::new();
-    result.insert(get_today_weekday().name_cn.to_string(), comics);
-    Ok(ApiResponse::ok(result))
+    // 3. 解析模块列表，按更新日期分组
+    let result = process_module_list(modules, today).context("处理模块列表失败")?;
+    info!(
+        "提取到 {} 部今日更新动漫",
+        result
+            .get(&format_date(today, DateFormat::Slash))
+            .map_or(0, Vec::len)
+    );
+    Ok(result)
 }
 
 /// 提取 Initial Data
-fn extract_initial_data(html: &str) -> Result<Value> {
+pub fn extract_initial_data(html: &str) -> Result<Value> {
     let doc = Html::parse_document(html);
     // 不能使用 context，因为 SelectorErrorKind 不满足 StdError
     let script_sel =
@@ -118,11 +97,12 @@ fn extract_initial_data(html: &str) -> Result<Value> {
 }
 
 /// 处理模块列表，提取 "每日更新" 项
-fn process_module_list(modules: &[Value]) -> Result<Vec<AniItem>> {
-    let mut found = Vec::new();
-    let mut seen = HashMap::new();
-
-    let weekday = get_today_weekday().num_from_mon as usize;
+///
+/// “每日更新”按星期一到星期日分页，每页展示该星期最近一次的更新，
+/// 因此日期取 `today` 当天或之前最近的同一星期
+pub fn process_module_list(modules: &[Value], today: NaiveDate) -> Result<AniItemResult> {
+    let mut result = AniItemResult::new();
+    let mut seen = HashSet::new();
 
     for comp in modules
         .iter()
@@ -135,26 +115,32 @@ fn process_module_list(modules: &[Value]) -> Result<Vec<AniItem>> {
             None => continue,
         };
 
-        let today_items = match items.get(weekday).and_then(Value::as_array) {
-            Some(v) => v,
-            None => continue,
-        };
-
-        for item in today_items {
-            let ani = build_aniitem(item);
-
-            if seen.insert(ani.title.clone(), ()).is_none() {
-                info!("识别到更新: {} {}", ani.title, ani.update_info);
-                found.push(ani);
+        for (idx, day_items) in items.iter().take(7).enumerate() {
+            let date = latest_weekday_on_or_before(today, idx as u32);
+            let found = result
+                .entry(format_date(date, DateFormat::Slash))
+                .or_default();
+
+            for item in day_items.as_array().into_iter().flatten() {
+                let ani = build_aniitem(item, date);
+
+                // 同一天的重复标题只保留一条
+                if seen.insert((ani.update_time.clone(), ani.title.clone())) {
+                    info!(
+                        "识别到更新: {} {} {}",
+                        ani.update_time, ani.title, ani.update_info
+                    );
+                    found.push(ani);
+                }
             }
         }
     }
 
-    Ok(found)
+    Ok(result)
 }
 
 /// 构建 AniItem
-fn build_aniitem(map: &Value) -> AniItem {
+fn build_aniitem(map: &Value, date: NaiveDate) -> AniItem {
     let title = map
         .get("title")
         .and_then(Value::as_str)
@@ -197,7 +183,14 @@ fn build_aniitem(map: &Value) -> AniItem {
             .trim()
             .to_string(),
         detail_url: get_youku_video_url(Option::from(map)),
-        update_time: get_today_slash(),
+        update_time: format_date(date, DateFormat::Slash),
+        // action_value 即节目 ID
+        external_id: map
+            .get("action_value")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        category: String::new(),
     }
 }
 
@@ -0,0 +1,121 @@
+use crate::command::data_source::apply_changes;
+use crate::db::common::ge_db_pool;
+use crate::db::data_source::reschedule_data_source;
+use crate::db::task_run::{list_task_health, list_task_run};
+use crate::state::AppState;
+use crate::tasks::scheduler::{Scheduler, TaskInfo};
+use crate::types::ApiResponse;
+use cron::Schedule;
+use log::debug;
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::Arc;
+use tauri::State;
+
+/// 立即刷新：执行指定的数据源任务，为空时执行全部
+///
+/// 每个任务的开始、成功、失败会通过 `task-progress` 事件推送给前端
+#[tauri::command]
+pub async fn refresh_ani_data(
+    scheduler: State<'_, Arc<Scheduler>>,
+    task_name: Option<String>,
+) -> Result<ApiResponse, String> {
+    match scheduler.run_now(task_name.as_deref()) {
+        Ok(started) => {
+            debug!("手动刷新已启动 {started} 个任务");
+            Ok(ApiResponse::ok(json!({ "started": started })))
+        }
+        Err(e) => Ok(ApiResponse::err(format!("刷新失败：{e}"))),
+    }
+}
+
+/// 查询所有定时任务及下次执行时间
+#[tauri::command]
+pub async fn query_task_list(
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<ApiResponse<Vec<TaskInfo>>, String> {
+    Ok(ApiResponse::ok(scheduler.task_infos()))
+}
+
+/// 暂停任务的定时执行（手动刷新不受影响）
+#[tauri::command]
+pub async fn pause_task(
+    scheduler: State<'_, Arc<Scheduler>>,
+    task_name: String,
+) -> Result<ApiResponse, String> {
+    match scheduler.set_task_paused(&task_name, true) {
+        Ok(()) => Ok(ApiResponse::ok(json!({ "taskName": task_name }))),
+        Err(e) => Ok(ApiResponse::err(format!("暂停任务失败：{e}"))),
+    }
+}
+
+/// 恢复任务的定时执行
+#[tauri::command]
+pub async fn resume_task(
+    scheduler: State<'_, Arc<Scheduler>>,
+    task_name: String,
+) -> Result<ApiResponse, String> {
+    match scheduler.set_task_paused(&task_name, false) {
+        Ok(()) => Ok(ApiResponse::ok(json!({ "taskName": task_name }))),
+        Err(e) => Ok(ApiResponse::err(format!("恢复任务失败：{e}"))),
+    }
+}
+
+/// 修改任务的 cron 表达式，保存到数据源后按新表达式排期，之后不再跟随配置文件更新
+#[tauri::command]
+pub async fn reschedule_task(
+    state: State<'_, Arc<AppState>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+    task_name: String,
+    cron_expr: String,
+) -> Result<ApiResponse, String> {
+    if let Err(e) = Schedule::from_str(&cron_expr) {
+        return Ok(ApiResponse::err(format!(
+            "修改任务失败：cron 表达式无效：{e}"
+        )));
+    }
+    match reschedule_data_source(
+        ge_db_pool(&state.db),
+        &task_name,
+        &cron_expr,
+        state.clock.now_millis(),
+    )
+    .await
+    {
+        Ok(()) => {
+            apply_changes(&state, &scheduler).await;
+            Ok(ApiResponse::ok(
+                json!({ "taskName": task_name, "cronExpr": cron_expr }),
+            ))
+        }
+        Err(e) => Ok(ApiResponse::err(format!("修改任务失败：{e}"))),
+    }
+}
+
+/// 查询最近的任务执行记录，task_name 为空时查询所有任务
+#[tauri::command]
+pub async fn query_task_run_list(
+    state: State<'_, Arc<AppState>>,
+    task_name: Option<String>,
+    limit: i64,
+) -> Result<ApiResponse, String> {
+    let pool = ge_db_pool(&state.db);
+    match list_task_run(pool, task_name.as_deref(), limit.clamp(1, 500)).await {
+        Ok(list) => Ok(ApiResponse::ok(json!(list))),
+        Err(e) => Ok(ApiResponse::err(format!("查询失败：{e}"))),
+    }
+}
+
+/// 查询各数据源的健康状况：最近成功时间、连续失败次数、平均更新数等
+#[tauri::command]
+pub async fn query_task_health_list(
+    state: State<'_, Arc<AppState>>,
+    scheduler: State<'_, Arc<Scheduler>>,
+) -> Result<ApiResponse, String> {
+    let pool = ge_db_pool(&state.db);
+    let names: Vec<String> = scheduler.tasks().iter().map(|t| t.name.clone()).collect();
+    match list_task_health(pool, &names).await {
+        Ok(list) => Ok(ApiResponse::ok(json!(list))),
+        Err(e) => Ok(ApiResponse::err(format!("查询失败：{e}"))),
+    }
+}
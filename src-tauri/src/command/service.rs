@@ -1,21 +1,30 @@
+use crate::configuration::{app_config_dir, load_title_alias_rules};
 use crate::db::common::ge_db_pool;
 pub use crate::db::common::save_ani_item_data_db;
+use crate::db::notification::{list_notify_delivery, set_series_notify_muted};
 use crate::db::po::{AniColl, AniDto, AniIResult, AniWatch};
+use crate::db::progress::{list_series_progress, mark_episodes_watched, unmark_episodes_watched};
+use crate::db::series::renormalize_series_if_changed;
 use crate::db::sqlite::{
     delete_ani_collect, list_all_ani_history_data, list_all_ani_info_watched_today,
-    list_all_ani_update_today, list_all_follow_ani_update_today, upsert_ani_collect,
-    upsert_ani_watch_history,
+    list_all_ani_update_between, list_all_ani_update_today, list_all_follow_ani_update_today,
+    upsert_ani_collect, upsert_ani_watch_history,
 };
+use crate::feed::ics::build_followed_calendar;
+use crate::startup::spawn_refresh_tray;
 use crate::types::{AniItemResult, ApiResponse, PageData};
 use crate::utils::date_utils::{
-    get_today_slash, get_today_weekday, get_unix_timestamp_millis_now, parse_date_to_millis,
+    date_to_millis, format_date, get_weekday, parse_date_to_millis, timestamp_to_date_string,
+    week_start, DateFormat, DAY_MILLIS,
 };
+use crate::utils::title::set_alias_rules;
 use crate::AppState;
+use chrono::Duration;
 use log::debug;
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::State;
+use tauri::{AppHandle, State};
 
 /// 保存动漫数据到数据库
 #[tauri::command]
@@ -25,59 +34,196 @@ pub async fn save_ani_item_data(
 ) -> Result<ApiResponse, String> {
     // state.inner() -> &Arc<AppState>; state.db 是 Arc<SqlitePool>
     let db = state.db.clone();
-    save_ani_item_data_db(db, ani_data).await
+    save_ani_item_data_db(db, ani_data, state.clock.now_millis()).await
 }
 
 /// 插入动漫观看历史数据到数据库
-#[tauri::command]
-pub async fn watch_ani_item(
-    state: State<'_, Arc<AppState>>,
-    ani_id: i64,
-) -> Result<ApiResponse, String> {
+pub async fn watch_ani(state: &AppState, ani_id: i64) -> ApiResponse {
     let pool = ge_db_pool(&state.db);
     let record = AniWatch {
         user_id: "".to_string(),
         ani_item_id: ani_id,
-        watched_time: get_unix_timestamp_millis_now(),
+        watched_time: state.clock.now_millis(),
     };
 
     if let Err(e) = upsert_ani_watch_history(pool, &record).await {
-        return Ok(ApiResponse::err(format!("写入观看记录失败：{e}")));
+        return ApiResponse::err(format!("写入观看记录失败：{e}"));
     }
     debug!("观看历史已写入：id={ani_id}");
-    Ok(ApiResponse::ok(json!({ "message": "watch success" })))
+    ApiResponse::ok(json!({ "message": "watch success" }))
 }
 
-/// 查询今天更新的动漫列表
+/// 插入动漫观看历史数据到数据库
 #[tauri::command]
-pub async fn query_today_update_ani_list(
+pub async fn watch_ani_item(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    ani_id: i64,
+) -> Result<ApiResponse, String> {
+    let resp = watch_ani(&state, ani_id).await;
+    spawn_refresh_tray(&app);
+    Ok(resp)
+}
+
+/// 把番剧第 from_episode ~ to_episode 集标记为已看
+#[tauri::command]
+pub async fn watch_ani_episodes(
+    state: State<'_, Arc<AppState>>,
+    series_id: i64,
+    from_episode: i64,
+    to_episode: i64,
+) -> Result<ApiResponse, String> {
+    let pool = ge_db_pool(&state.db);
+    let now = state.clock.now_millis();
+    match mark_episodes_watched(pool, "", series_id, from_episode, to_episode, now).await {
+        Ok(added) => {
+            debug!("分集观看已写入：series_id={series_id} {from_episode}~{to_episode}");
+            Ok(ApiResponse::ok(json!({ "added": added })))
+        }
+        Err(e) => Ok(ApiResponse::err(format!("写入分集观看记录失败：{e}"))),
+    }
+}
+
+/// 取消番剧第 from_episode ~ to_episode 集的已看标记
+#[tauri::command]
+pub async fn unwatch_ani_episodes(
+    state: State<'_, Arc<AppState>>,
+    series_id: i64,
+    from_episode: i64,
+    to_episode: i64,
+) -> Result<ApiResponse, String> {
+    let pool = ge_db_pool(&state.db);
+    // 只有已经播出的最新一集被取消时才重置关注的已看标记
+    let tomorrow = match date_to_millis(state.clock.today() + Duration::days(1)) {
+        Ok(v) => v,
+        Err(e) => return Ok(ApiResponse::err(format!("时间解析失败：{e}"))),
+    };
+    match unmark_episodes_watched(pool, "", series_id, from_episode, to_episode, tomorrow).await {
+        Ok(removed) => {
+            debug!("分集观看已取消：series_id={series_id} {from_episode}~{to_episode}");
+            Ok(ApiResponse::ok(json!({ "removed": removed })))
+        }
+        Err(e) => Ok(ApiResponse::err(format!("取消分集观看记录失败：{e}"))),
+    }
+}
+
+/// 查询关注番剧的观看进度（看到第几集、落后几集）
+#[tauri::command]
+pub async fn query_series_progress_list(
     state: State<'_, Arc<AppState>>,
 ) -> Result<ApiResponse, String> {
     let pool = ge_db_pool(&state.db);
+    // 只计入已经播出的剧集
+    let tomorrow = match date_to_millis(state.clock.today() + Duration::days(1)) {
+        Ok(v) => v,
+        Err(e) => return Ok(ApiResponse::err(format!("时间解析失败：{e}"))),
+    };
+    match list_series_progress(pool, "", tomorrow).await {
+        Ok(list) => Ok(ApiResponse::ok(json!(list))),
+        Err(e) => Ok(ApiResponse::err(format!("查询失败：{e}"))),
+    }
+}
+
+/// 查询今天更新的动漫列表
+pub async fn today_update_ani_list(state: &AppState) -> ApiResponse {
+    let pool = ge_db_pool(&state.db);
 
     // 1. 解析今日时间戳
-    let today = get_today_slash();
+    let today = format_date(state.clock.today(), DateFormat::Slash);
     let ts = match parse_date_to_millis(&today, true) {
         Ok(v) => v,
-        Err(e) => return Ok(ApiResponse::err(format!("时间解析失败：{e}"))),
+        Err(e) => return ApiResponse::err(format!("时间解析失败：{e}")),
     };
 
     // 2. 查询数据
     let raw = match list_all_ani_update_today(pool, ts).await {
         Ok(v) => v,
-        Err(e) => return Ok(ApiResponse::err(format!("查询失败：{e}"))),
+        Err(e) => return ApiResponse::err(format!("查询失败：{e}")),
     };
 
     // 3. 转 DTO 并组织结果
     let dtos: Vec<AniDto> = raw.into_iter().map(AniDto::from).collect();
     let mut map: AniIResult = HashMap::new();
-    let weekday = get_today_weekday().name_cn.to_string();
+    let weekday = get_weekday(state.clock.today()).name_cn.to_string();
     map.insert(weekday.clone(), dtos.clone());
 
     debug!("今日更新动漫（{weekday}）：{dtos:?}");
+    ApiResponse::ok(json!(map))
+}
+
+/// 查询今天更新的动漫列表
+#[tauri::command]
+pub async fn query_today_update_ani_list(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ApiResponse, String> {
+    Ok(today_update_ani_list(&state).await)
+}
+
+/// 查询本周（星期一到星期日）的动漫更新日历，键为更新日期，包含即将更新的剧集
+#[tauri::command]
+pub async fn query_week_schedule_ani_list(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ApiResponse, String> {
+    let pool = ge_db_pool(&state.db);
+
+    // 1. 计算本周起止时间戳
+    let monday = week_start(state.clock.today());
+    let (start, end) = match (
+        date_to_millis(monday),
+        date_to_millis(monday + Duration::days(7)),
+    ) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(e), _) | (_, Err(e)) => return Ok(ApiResponse::err(format!("时间解析失败：{e}"))),
+    };
+
+    // 2. 查询数据
+    let raw = match list_all_ani_update_between(pool, start, end).await {
+        Ok(v) => v,
+        Err(e) => return Ok(ApiResponse::err(format!("查询失败：{e}"))),
+    };
+
+    // 3. 按日期分组，没有更新的日期保留空列表
+    let mut map: AniIResult = (0..7)
+        .map(|i| {
+            (
+                format_date(monday + Duration::days(i), DateFormat::Slash),
+                Vec::new(),
+            )
+        })
+        .collect();
+    for ani in raw {
+        let date = timestamp_to_date_string(ani.update_time / 1000, DateFormat::Slash);
+        map.entry(date).or_default().push(AniDto::from(ani));
+    }
+
+    debug!("本周更新日历：{map:?}");
     Ok(ApiResponse::ok(json!(map)))
 }
 
+/// 查询今天之后七天内即将更新的动漫列表（按更新时间升序）
+#[tauri::command]
+pub async fn query_upcoming_ani_list(
+    state: State<'_, Arc<AppState>>,
+) -> Result<ApiResponse, String> {
+    let pool = ge_db_pool(&state.db);
+
+    // 1. 解析明日时间戳
+    let tomorrow = match date_to_millis(state.clock.today() + Duration::days(1)) {
+        Ok(v) => v,
+        Err(e) => return Ok(ApiResponse::err(format!("时间解析失败：{e}"))),
+    };
+
+    // 2. 查询数据
+    let list = match list_all_ani_update_between(pool, tomorrow, tomorrow + 7 * DAY_MILLIS).await {
+        Ok(v) => v,
+        Err(e) => return Ok(ApiResponse::err(format!("查询失败：{e}"))),
+    };
+
+    let dtos: Vec<AniDto> = list.into_iter().map(AniDto::from).collect();
+    debug!("即将更新动漫：{dtos:?}");
+    Ok(ApiResponse::ok(json!(dtos)))
+}
+
 /// 查询今天已经观看的动漫列表
 #[tauri::command]
 pub async fn query_watched_ani_item_list(
@@ -86,7 +232,7 @@ pub async fn query_watched_ani_item_list(
     let pool = ge_db_pool(&state.db);
 
     // 1. 解析今日时间戳
-    let today = get_today_slash();
+    let today = format_date(state.clock.today(), DateFormat::Slash);
     let ts = match parse_date_to_millis(&today, true) {
         Ok(v) => v,
         Err(e) => return Ok(ApiResponse::err(format!("时间解析失败：{e}"))),
@@ -103,55 +249,69 @@ pub async fn query_watched_ani_item_list(
 }
 
 /// 获取关注动漫今日更新列表
-#[tauri::command]
-pub async fn query_favorite_ani_update_list(
-    state: State<'_, Arc<AppState>>,
-) -> Result<ApiResponse, String> {
+pub async fn favorite_ani_update_list(state: &AppState) -> ApiResponse {
     let pool = ge_db_pool(&state.db);
 
     // 1. 解析今日时间戳
-    let today = get_today_slash();
+    let today = format_date(state.clock.today(), DateFormat::Slash);
     let ts = match parse_date_to_millis(&today, true) {
         Ok(v) => v,
-        Err(e) => return Ok(ApiResponse::err(format!("时间解析失败：{e}"))),
+        Err(e) => return ApiResponse::err(format!("时间解析失败：{e}")),
     };
 
     // 2. 查询数据
     let list = match list_all_follow_ani_update_today(pool, ts).await {
         Ok(v) => v,
-        Err(e) => return Ok(ApiResponse::err(format!("查询失败：{e}"))),
+        Err(e) => return ApiResponse::err(format!("查询失败：{e}")),
     };
 
     debug!("关注动漫今日更新：{list:?}");
-    Ok(ApiResponse::ok(json!(list)))
+    ApiResponse::ok(json!(list))
 }
 
-/// 关注动漫
+/// 获取关注动漫今日更新列表
 #[tauri::command]
-pub async fn collect_ani_item(
+pub async fn query_favorite_ani_update_list(
     state: State<'_, Arc<AppState>>,
-    ani_id: i64,
-    ani_title: String,
 ) -> Result<ApiResponse, String> {
+    Ok(favorite_ani_update_list(&state).await)
+}
+
+/// 关注动漫
+pub async fn collect_ani(state: &AppState, ani_id: i64, ani_title: String) -> ApiResponse {
     let pool = ge_db_pool(&state.db);
     let record = AniColl {
         user_id: "".to_string(),
         ani_item_id: ani_id,
         ani_title: ani_title.clone(),
-        collect_time: get_today_slash(),
+        collect_time: format_date(state.clock.today(), DateFormat::Slash),
         is_watched: false,
     };
 
     if let Err(e) = upsert_ani_collect(pool, &record).await {
-        return Ok(ApiResponse::err(format!("插入或更新失败：{e}")));
+        return ApiResponse::err(format!("插入或更新失败：{e}"));
     }
     debug!("已收藏动漫：《{ani_title}》");
-    Ok(ApiResponse::ok(json!({ "message": "collect success" })))
+    ApiResponse::ok(json!({ "message": "collect success" }))
+}
+
+/// 关注动漫
+#[tauri::command]
+pub async fn collect_ani_item(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    ani_id: i64,
+    ani_title: String,
+) -> Result<ApiResponse, String> {
+    let resp = collect_ani(&state, ani_id, ani_title).await;
+    spawn_refresh_tray(&app);
+    Ok(resp)
 }
 
 /// 取消关注动漫
 #[tauri::command]
 pub async fn cancel_collect_ani_item(
+    app: AppHandle,
     state: State<'_, Arc<AppState>>,
     ani_id: i64,
     ani_title: String,
@@ -162,24 +322,25 @@ pub async fn cancel_collect_ani_item(
     }
 
     debug!("已取消收藏：《{ani_title}》(id={ani_id})");
+    spawn_refresh_tray(&app);
     Ok(ApiResponse::ok(json!({ "message": "cancel success" })))
 }
 
 /// 查询动漫历史更新信息列表
-#[tauri::command]
-pub async fn query_ani_history_list(
-    state: State<'_, Arc<AppState>>,
-    page: i64,
-    page_size: i64,
-) -> Result<ApiResponse, String> {
+pub async fn ani_history_list(state: &AppState, page: i64, page_size: i64) -> ApiResponse {
     let pool = ge_db_pool(&state.db);
+    // 只查询已经播出的数据，排除即将更新的剧集
+    let tomorrow = match date_to_millis(state.clock.today() + Duration::days(1)) {
+        Ok(v) => v,
+        Err(e) => return ApiResponse::err(format!("时间解析失败：{e}")),
+    };
     //查询数据
-    let list = match list_all_ani_history_data(pool, page, page_size).await {
+    let list = match list_all_ani_history_data(pool, tomorrow, page, page_size).await {
         Ok(v) => v,
-        Err(e) => return Ok(ApiResponse::err(format!("查询失败：{e}"))),
+        Err(e) => return ApiResponse::err(format!("查询失败：{e}")),
     };
     if list.is_empty() {
-        return Ok(ApiResponse::ok(json!({ "message": "没有历史更新数据" })));
+        return ApiResponse::ok(json!({ "message": "没有历史更新数据" }));
     }
     let total_count: i64 = list[0].total_count;
     debug!("历史更新数据：{list:?}");
@@ -190,5 +351,90 @@ pub async fn query_ani_history_list(
         page,
         page_size,
     };
-    Ok(ApiResponse::ok(json!(data)))
+    ApiResponse::ok(json!(data))
+}
+
+/// 查询动漫历史更新信息列表
+#[tauri::command]
+pub async fn query_ani_history_list(
+    state: State<'_, Arc<AppState>>,
+    page: i64,
+    page_size: i64,
+) -> Result<ApiResponse, String> {
+    Ok(ani_history_list(&state, page, page_size).await)
+}
+
+/// 开启或关闭某部番剧的更新通知
+#[tauri::command]
+pub async fn mute_series_notification(
+    state: State<'_, Arc<AppState>>,
+    series_id: i64,
+    muted: bool,
+) -> Result<ApiResponse, String> {
+    let pool = ge_db_pool(&state.db);
+    if let Err(e) = set_series_notify_muted(pool, series_id, muted).await {
+        return Ok(ApiResponse::err(format!("设置通知失败：{e}")));
+    }
+    debug!(
+        "番剧 series_id={series_id} 的更新通知已{}",
+        if muted { "关闭" } else { "开启" }
+    );
+    Ok(ApiResponse::ok(
+        json!({ "seriesId": series_id, "muted": muted }),
+    ))
+}
+
+/// 查询最近的通知投递记录
+#[tauri::command]
+pub async fn query_notify_delivery_list(
+    state: State<'_, Arc<AppState>>,
+    limit: i64,
+) -> Result<ApiResponse, String> {
+    let pool = ge_db_pool(&state.db);
+    match list_notify_delivery(pool, limit.clamp(1, 500)).await {
+        Ok(list) => Ok(ApiResponse::ok(json!(list))),
+        Err(e) => Ok(ApiResponse::err(format!("查询失败：{e}"))),
+    }
+}
+
+/// 把关注番剧的更新日历导出为 .ics 文件
+#[tauri::command]
+pub async fn export_followed_calendar(
+    state: State<'_, Arc<AppState>>,
+    file_path: String,
+) -> Result<ApiResponse, String> {
+    let calendar = match build_followed_calendar(&state).await {
+        Ok(v) => v,
+        Err(e) => return Ok(ApiResponse::err(format!("生成日历失败：{e}"))),
+    };
+    if let Err(e) = tokio::fs::write(&file_path, calendar).await {
+        return Ok(ApiResponse::err(format!("写入日历文件失败：{e}")));
+    }
+    debug!("关注番剧日历已导出：{file_path}");
+    Ok(ApiResponse::ok(json!({ "path": file_path })))
+}
+
+/// 重新加载标题别名规则，并按新规则重新匹配番剧
+#[tauri::command]
+pub async fn reload_title_alias_rules(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ApiResponse, String> {
+    // 1. 读取规则文件
+    let rules = match load_title_alias_rules(&app_config_dir(&app)) {
+        Ok(v) => v,
+        Err(e) => return Ok(ApiResponse::err(format!("读取别名规则失败：{e}"))),
+    };
+    set_alias_rules(&rules);
+
+    // 2. 按新规则重建匹配键，合并归一化后相同的番剧
+    let merged = match renormalize_series_if_changed(&state.db).await {
+        Ok(v) => v,
+        Err(e) => return Ok(ApiResponse::err(format!("重新匹配番剧失败：{e:#}"))),
+    };
+
+    debug!("已加载 {} 条别名规则，合并 {merged} 部番剧", rules.len());
+    Ok(ApiResponse::ok(
+        json!({ "rules": rules.len(), "merged": merged }),
+    ))
 }
@@ -1,32 +1,383 @@
+use crate::utils::http_client::DEFAULT_USER_AGENT;
+use crate::utils::title::TitleAliasRule;
+use crate::validation::ConfigDiagnostic;
+use config::ConfigError;
 use log::{error, info};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use tauri::{App, Manager};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{App, AppHandle, Manager};
+use yaml_rust2::yaml::Hash;
+use yaml_rust2::{Yaml, YamlEmitter, YamlLoader};
 
-#[derive(Debug, Deserialize)]
+/// 当前配置文件的格式版本，没有 version 字段的用户配置视为版本 1
+///
+/// 版本 1：config.yaml 是内置配置的完整副本，每次启动都会被覆盖
+/// 版本 2：config.yaml 只保存用户的改动，与内置默认配置合并
+pub const CONFIG_VERSION: i64 = 2;
+
+/// 内置默认配置的副本，每次启动时从安装目录刷新，请勿手动修改
+pub const DEFAULT_CONFIG_FILE: &str = "config.default.yaml";
+
+/// 用户配置文件，启动时不会被覆盖
+pub const USER_CONFIG_FILE: &str = "config.yaml";
+
+/// 按 name 合并数据源的配置项
+const DATASOURCE_KEY: &str = "datasource";
+
+/// 用户配置文件的说明，创建和迁移用户配置时写在文件开头
+const USER_CONFIG_HEADER: &str = r#"# 用户配置：只需写出要修改的部分，启动时与同目录的 config.default.yaml（内置默认配置）合并
+# 表按键合并；datasource 下的数据源按 name 合并，同名数据源只覆盖写出的字段，新的 name 追加为新数据源，
+# 设置 enabled: false 可停用默认数据源；其它列表（如 notification.push）整体替换。例如：
+# datasource:
+#   anime:
+#     - name: "哔哩哔哩国创"
+#       cron_expr: "0 0 9-23 * * * *"
+#     - name: "优酷动漫"
+#       enabled: false
+# http_server:
+#   enabled: true
+"#;
+
+/// 标题别名规则文件名（与 config.yaml 位于同一目录，由用户自行维护）
+pub const TITLE_ALIAS_FILE: &str = "title_aliases.yaml";
+
+/// 别名规则文件不存在时写入的模板
+const TITLE_ALIAS_TEMPLATE: &str = r#"# 番剧标题别名规则：aliases 中的标题都会被视为 title 对应的番剧
+# 修改后重启应用或在前端重新加载规则即可生效，例如：
+# rules:
+#   - title: 凡人修仙传
+#     aliases:
+#       - A Record of Mortal's Journey to Immortality
+rules: []
+"#;
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct DataSource {
     pub name: String,
     pub url: String,
     pub cmd: String,
     pub cron_expr: String,
     pub retry_times: u8,
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    /// 是否启用，停用的数据源不会创建定时任务
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// 数据源抓取失败时的重试策略：指数退避加随机抖动
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 第一次重试前等待的秒数
+    pub base_delay_secs: u64,
+    /// 单次等待的最长秒数（服务器要求的 Retry-After 也不超过该值）
+    pub max_delay_secs: u64,
+    /// 每次重试等待时间的倍数
+    pub multiplier: f64,
+    /// 随机抖动比例（0~1），避免多个任务同时重试
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay_secs: 5,
+            max_delay_secs: 300,
+            multiplier: 2.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 attempt 次重试（从 0 开始）前的等待时间，sample 为 [0, 1) 的随机数
+    pub fn backoff(&self, attempt: u32, sample: f64) -> Duration {
+        let delay = self.base_delay_secs as f64 * self.multiplier.powi(attempt as i32);
+        let jitter = 1.0 + self.jitter.clamp(0.0, 1.0) * (sample * 2.0 - 1.0);
+        Duration::from_secs_f64((delay * jitter).clamp(0.0, self.max_delay_secs as f64))
+    }
+
+    /// 服务器要求的等待时间，不超过最长等待时间
+    pub fn cap(&self, delay: Duration) -> Duration {
+        delay.min(Duration::from_secs(self.max_delay_secs))
+    }
+}
+
+/// 本地 HTTP 接口配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct HttpServerConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// 访问令牌，监听本机以外的地址时必须设置
+    pub token: Option<String>,
+}
+
+impl Default for HttpServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 8686,
+            token: None,
+        }
+    }
+}
+
+/// 抓取数据源和图片时使用的 HTTP 客户端配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct HttpClientConfig {
+    /// 建立连接的超时秒数
+    pub connect_timeout_secs: u64,
+    /// 读取响应的超时秒数（两次读到数据之间的最长间隔）
+    pub read_timeout_secs: u64,
+    /// 代理地址，支持 http://、https://、socks5://、socks5h://，留空时使用系统代理环境变量
+    pub proxy: String,
+    /// 额外信任的根证书（PEM 文件），相对路径相对于配置目录
+    pub ca_cert: String,
+    /// 请求时使用的 User-Agent
+    pub user_agent: String,
+    /// 按平台追加或覆盖的请求头，键为平台标识，值为空时不发送该请求头
+    pub headers: HashMap<String, HashMap<String, String>>,
+    /// 每个站点默认的限流配置
+    pub rate_limit: RateLimitConfig,
+    /// 按平台覆盖的限流配置，键为平台标识；多个平台请求同一站点时使用最先请求的平台的配置
+    pub rate_limits: HashMap<String, RateLimitConfig>,
+}
+
+/// 单个站点（域名）的限流配置：令牌桶加并发上限
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// 每秒最多发起的请求数，0 表示不限制
+    pub requests_per_second: f64,
+    /// 允许突发的请求数（令牌桶容量）
+    pub burst: u32,
+    /// 同时进行的请求数上限
+    pub max_concurrent: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 4.0,
+            burst: 8,
+            max_concurrent: 4,
+        }
+    }
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            proxy: String::new(),
+            ca_cert: String::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: HashMap::new(),
+            rate_limit: RateLimitConfig::default(),
+            rate_limits: HashMap::new(),
+        }
+    }
+}
+
+/// 关注番剧更新通知配置
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// 免打扰时段，如 "23:00-08:00"，留空表示不启用
+    pub quiet_hours: String,
+    /// 一次到达的更新超过该数量时合并为一条通知（只对桌面通知生效）
+    pub batch_threshold: usize,
+    /// 是否弹出系统桌面通知
+    pub desktop: bool,
+    /// 推送渠道（Webhook、Telegram、邮件），每集单独推送
+    pub push: Vec<PushChannelConfig>,
+    /// 推送失败后的重试次数，重试间隔依次翻倍
+    pub retry_times: u8,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            quiet_hours: String::new(),
+            batch_threshold: 3,
+            desktop: true,
+            push: Vec::new(),
+            retry_times: 3,
+        }
+    }
+}
+
+/// 推送渠道配置，按 `type` 区分
+///
+/// 模板中可使用 `{{title}}`、`{{update_count}}`、`{{update_info}}`、`{{image_url}}`、
+/// `{{detail_url}}`、`{{update_time}}`、`{{platform}}`、`{{platform_name}}` 占位符
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PushChannelConfig {
+    Webhook(WebhookConfig),
+    Telegram(TelegramConfig),
+    Smtp(SmtpConfig),
+}
+
+/// 通用 Webhook：向 url 发送 JSON 请求体
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WebhookConfig {
+    #[serde(default = "default_webhook_name")]
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_webhook_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// JSON 请求体模板，占位符的值会按 JSON 字符串转义；留空时发送整条更新信息
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+/// Telegram 机器人（或兼容其 sendMessage 接口的服务）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TelegramConfig {
+    #[serde(default = "default_telegram_name")]
+    pub name: String,
+    #[serde(default = "default_telegram_api_base")]
+    pub api_base: String,
+    pub bot_token: String,
+    pub chat_id: String,
+    /// 消息文本模板
+    #[serde(default = "default_text_template")]
+    pub template: String,
+}
+
+/// 邮件（SMTP）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SmtpConfig {
+    #[serde(default = "default_smtp_name")]
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    /// 连接加密方式：starttls、tls、none
+    #[serde(default = "default_smtp_security")]
+    pub security: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default = "default_subject_template")]
+    pub subject_template: String,
+    #[serde(default = "default_text_template")]
+    pub body_template: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_webhook_name() -> String {
+    "webhook".to_string()
+}
+
+fn default_webhook_method() -> String {
+    "POST".to_string()
+}
+
+fn default_telegram_name() -> String {
+    "telegram".to_string()
+}
+
+fn default_telegram_api_base() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+fn default_smtp_name() -> String {
+    "smtp".to_string()
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_smtp_security() -> String {
+    "starttls".to_string()
+}
+
+fn default_subject_template() -> String {
+    "《{{title}}》{{update_info}}".to_string()
+}
+
+fn default_text_template() -> String {
+    "《{{title}}》{{update_info}}\n{{platform_name}} {{detail_url}}".to_string()
 }
 
 // 不再需要 DataSourceCategory 结构体
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
     pub datasource: HashMap<String, Vec<DataSource>>, // 直接映射到 Vec<DataSource>
+    #[serde(default)]
+    pub http_server: HttpServerConfig,
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+    #[serde(default)]
+    pub notification: NotificationConfig,
 }
 
-// 读取配置文件
-pub fn load_configuration(config_path: PathBuf) -> Result<AppConfig, config::ConfigError> {
-    // 读取配置文件目录
-    let configuration_directory = config_path;
+/// 标题别名规则文件
+#[derive(Debug, Default, Deserialize)]
+pub struct TitleAliasConfig {
+    #[serde(default)]
+    pub rules: Vec<TitleAliasRule>,
+}
+
+/// 最近一次加载配置的检查结果，配置有误时前端可查询到错误原因
+#[derive(Debug, Default)]
+pub struct ConfigStatus {
+    diagnostics: RwLock<Vec<ConfigDiagnostic>>,
+}
+
+impl ConfigStatus {
+    pub fn set_diagnostics(&self, diagnostics: Vec<ConfigDiagnostic>) {
+        *self.diagnostics.write().unwrap() = diagnostics;
+    }
+
+    pub fn diagnostics(&self) -> Vec<ConfigDiagnostic> {
+        self.diagnostics.read().unwrap().clone()
+    }
+}
+
+// 读取配置文件：内置默认配置 < 用户配置 < 环境变量
+pub fn load_configuration(config_path: PathBuf) -> Result<AppConfig, ConfigError> {
+    build_configuration(load_layered_config(&config_path, None)?)
+}
+
+/// 用给定的内容代替 config.yaml 读取配置，用于保存前检查
+pub fn load_configuration_with(
+    config_path: &Path,
+    user_config: &str,
+) -> Result<AppConfig, ConfigError> {
+    let user = parse_yaml(user_config, USER_CONFIG_FILE)?;
+    build_configuration(load_layered_config(config_path, Some(user))?)
+}
+
+/// 合并后的配置再叠加环境变量，转换为 AppConfig
+fn build_configuration(merged: Yaml) -> Result<AppConfig, ConfigError> {
     let settings = config::Config::builder()
-        .add_source(config::File::from(
-            configuration_directory.join("config.yaml"),
+        .add_source(config::File::from_str(
+            &dump_yaml(&merged)?,
+            config::FileFormat::Yaml,
         ))
         .add_source(
             config::Environment::with_prefix("APP")
@@ -37,36 +388,303 @@ pub fn load_configuration(config_path: PathBuf) -> Result<AppConfig, config::Con
     settings.try_deserialize::<AppConfig>()
 }
 
-/// 初始化应用配置
-pub fn init_config(app: &mut App) -> std::io::Result<PathBuf> {
-    let app_name = app.handle().package_info().name.clone();
-    // 设置app配置文件的存放目录路径 (在 Windows 上通常是 AppData\Roaming\{app_name})
-    let config_path = app.path().config_dir().unwrap_or_default().join(app_name);
-    // 配置文件存放的目标路径
-    let target_config_path = config_path.join("config.yaml");
+/// 读取默认配置和用户配置（旧版本的用户配置先在内存中迁移），合并为一份；
+/// user 不为空时用它代替 config.yaml
+fn load_layered_config(config_path: &Path, user: Option<Yaml>) -> Result<Yaml, ConfigError> {
+    let default_file = config_path.join(DEFAULT_CONFIG_FILE);
+    let user_file = config_path.join(USER_CONFIG_FILE);
+    if !default_file.exists() && !user_file.exists() && user.is_none() {
+        return Err(ConfigError::NotFound(format!(
+            "配置目录 {} 中没有配置文件",
+            config_path.display()
+        )));
+    }
+    let mut merged = read_yaml(&default_file)?;
+    let mut user = match user {
+        Some(user) => user,
+        None => read_yaml(&user_file)?,
+    };
+    migrate_user_config(&mut user, &merged)?;
+    merge_yaml(&mut merged, user);
+    Ok(merged)
+}
 
+/// 读取 YAML 文件，文件不存在或为空时返回空表
+fn read_yaml(path: &Path) -> Result<Yaml, ConfigError> {
+    if !path.exists() {
+        return Ok(Yaml::Hash(Hash::new()));
+    }
+    let content = fs::read_to_string(path).map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+    parse_yaml(&content, &path.display().to_string())
+}
+
+/// 解析 YAML 文本，uri 用于错误信息
+fn parse_yaml(content: &str, uri: &str) -> Result<Yaml, ConfigError> {
+    let doc = YamlLoader::load_from_str(content)
+        .map_err(|e| ConfigError::FileParse {
+            uri: Some(uri.to_string()),
+            cause: Box::new(e),
+        })?
+        .into_iter()
+        .next();
+    match doc {
+        None | Some(Yaml::Null) => Ok(Yaml::Hash(Hash::new())),
+        Some(doc @ Yaml::Hash(_)) => Ok(doc),
+        Some(_) => Err(ConfigError::Message(format!(
+            "配置文件 {uri} 的顶层必须是键值表"
+        ))),
+    }
+}
+
+/// 输出为 YAML 文本
+fn dump_yaml(doc: &Yaml) -> Result<String, ConfigError> {
+    let mut out = String::new();
+    YamlEmitter::new(&mut out)
+        .dump(doc)
+        .map_err(|e| ConfigError::Message(format!("生成配置文件失败：{e}")))?;
+    Ok(out.trim_start_matches("---").trim_start().to_string())
+}
+
+/// 把用户配置合并到 base 上：表按键递归合并，datasource 下的数据源按 name 合并，其它值整体替换
+fn merge_yaml(base: &mut Yaml, overlay: Yaml) {
+    match (base, overlay) {
+        (Yaml::Hash(base_map), Yaml::Hash(overlay_map)) => {
+            for (key, value) in overlay_map {
+                let is_datasource = key.as_str() == Some(DATASOURCE_KEY);
+                match base_map.get_mut(&key) {
+                    Some(base_value) if is_datasource => merge_datasource(base_value, value),
+                    Some(base_value) => merge_yaml(base_value, value),
+                    None => {
+                        base_map.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// 合并各分类的数据源列表：同名数据源按字段合并，新的数据源追加到末尾
+fn merge_datasource(base: &mut Yaml, overlay: Yaml) {
+    let (Yaml::Hash(base_map), Yaml::Hash(overlay_map)) = (&mut *base, &overlay) else {
+        *base = overlay;
+        return;
+    };
+    for (category, sources) in overlay_map {
+        let (Some(Yaml::Array(base_sources)), Yaml::Array(sources)) =
+            (base_map.get_mut(category), sources)
+        else {
+            base_map.insert(category.clone(), sources.clone());
+            continue;
+        };
+        for source in sources {
+            let name = &source["name"];
+            match base_sources
+                .iter_mut()
+                .find(|s| !name.is_badvalue() && s["name"] == *name)
+            {
+                Some(base_source) => merge_yaml(base_source, source.clone()),
+                None => base_sources.push(source.clone()),
+            }
+        }
+    }
+}
+
+/// 把旧版本的用户配置迁移到当前版本，返回是否做了迁移
+pub fn migrate_user_config(user: &mut Yaml, defaults: &Yaml) -> Result<bool, ConfigError> {
+    let version = match &user["version"] {
+        Yaml::Integer(v) => *v,
+        Yaml::BadValue | Yaml::Null => 1,
+        other => {
+            return Err(ConfigError::Message(format!(
+                "配置文件版本号必须是整数：{other:?}"
+            )))
+        }
+    };
+    if version > CONFIG_VERSION {
+        return Err(ConfigError::Message(format!(
+            "配置文件版本 {version} 高于当前应用支持的版本 {CONFIG_VERSION}，请升级应用"
+        )));
+    }
+    if version == CONFIG_VERSION {
+        return Ok(false);
+    }
+    // 版本 1 的 config.yaml 是内置配置的完整副本：去掉与默认配置相同的部分，只保留用户的改动
+    if version < 2 {
+        strip_defaults(user, defaults);
+    }
+    if let Yaml::Hash(map) = user {
+        map.insert(
+            Yaml::String("version".to_string()),
+            Yaml::Integer(CONFIG_VERSION),
+        );
+    }
+    Ok(true)
+}
+
+/// 去掉 user 中与 defaults 相同的部分，返回去掉后是否已为空
+fn strip_defaults(user: &mut Yaml, defaults: &Yaml) -> bool {
+    if user == defaults {
+        return true;
+    }
+    let (Yaml::Hash(user_map), Yaml::Hash(default_map)) = (user, defaults) else {
+        return false;
+    };
+    let keys: Vec<Yaml> = user_map.keys().cloned().collect();
+    for key in keys {
+        let Some(default_value) = default_map.get(&key) else {
+            continue;
+        };
+        let value = user_map.get_mut(&key).unwrap();
+        let same = if key.as_str() == Some(DATASOURCE_KEY) {
+            strip_datasource(value, default_value)
+        } else {
+            strip_defaults(value, default_value)
+        };
+        if same {
+            user_map.remove(&key);
+        }
+    }
+    user_map.is_empty()
+}
+
+/// 按 name 去掉与默认配置相同的数据源，同名数据源只保留改动的字段；
+/// 旧配置中删掉的默认数据源改为 enabled: false，返回去掉后是否已为空
+fn strip_datasource(user: &mut Yaml, defaults: &Yaml) -> bool {
+    if user == defaults {
+        return true;
+    }
+    let (Yaml::Hash(user_map), Yaml::Hash(default_map)) = (user, defaults) else {
+        return false;
+    };
+    for (category, default_sources) in default_map {
+        let Yaml::Array(default_sources) = default_sources else {
+            continue;
+        };
+        let sources = match user_map.get_mut(category) {
+            Some(Yaml::Array(sources)) => std::mem::take(sources),
+            Some(_) => continue,
+            None => Vec::new(),
+        };
+        let names: Vec<Yaml> = sources.iter().map(|s| s["name"].clone()).collect();
+        let mut kept = Vec::new();
+        for mut source in sources {
+            let name = source["name"].clone();
+            match default_sources.iter().find(|d| d["name"] == name) {
+                Some(default_source) => {
+                    if !strip_defaults(&mut source, default_source) {
+                        if let Yaml::Hash(map) = &mut source {
+                            map.insert(Yaml::String("name".to_string()), name);
+                        }
+                        kept.push(source);
+                    }
+                }
+                None => kept.push(source),
+            }
+        }
+        for default_source in default_sources {
+            let name = &default_source["name"];
+            if !name.is_badvalue() && !names.contains(name) {
+                let mut disabled = Hash::new();
+                disabled.insert(Yaml::String("name".to_string()), name.clone());
+                disabled.insert(Yaml::String("enabled".to_string()), Yaml::Boolean(false));
+                kept.push(Yaml::Hash(disabled));
+            }
+        }
+        if kept.is_empty() {
+            user_map.remove(category);
+        } else {
+            user_map.insert(category.clone(), Yaml::Array(kept));
+        }
+    }
+    user_map.is_empty()
+}
+
+/// 读取标题别名规则，文件不存在时先写入一份空模板
+pub fn load_title_alias_rules(
+    config_path: &Path,
+) -> Result<Vec<TitleAliasRule>, config::ConfigError> {
+    let alias_file = config_path.join(TITLE_ALIAS_FILE);
+    if !alias_file.exists() {
+        fs::create_dir_all(config_path)
+            .and_then(|_| fs::write(&alias_file, TITLE_ALIAS_TEMPLATE))
+            .map_err(|e| config::ConfigError::Foreign(Box::new(e)))?;
+        info!("标题别名规则文件已创建：{:?}", alias_file);
+    }
+    let settings = config::Config::builder()
+        .add_source(config::File::from(alias_file))
+        .build()?;
+    Ok(settings.try_deserialize::<TitleAliasConfig>()?.rules)
+}
+
+/// app配置文件的存放目录路径 (在 Windows 上通常是 AppData\Roaming\{app_name})
+pub fn app_config_dir(app: &AppHandle) -> PathBuf {
+    let app_name = app.package_info().name.clone();
+    app.path().config_dir().unwrap_or_default().join(app_name)
+}
+
+/// 初始化应用配置，返回配置目录
+pub fn init_config(app: &mut App) -> Result<PathBuf, ConfigError> {
+    let config_path = app_config_dir(app.handle());
     // 获取app安装目录中
     let resource_path = app
         .path()
         .resource_dir()
-        .expect("Unable to get resource directory");
+        .map_err(|e| ConfigError::Message(format!("无法获取安装目录：{e}")))?;
     // 获取安装目录中的配置文件
-    let config_file_in_resources = resource_path.join("configuration/config.yaml");
-    // 如果配置文件不存在则直接报错退出程序
-    if !config_file_in_resources.exists() {
-        error!("Config file not found in {:?}", config_file_in_resources);
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "Config file not found in app resources",
-        ));
-    }
-    // 复制配置文件到目标目录(覆盖)
-    fs::create_dir_all(config_path.clone())?; // 如果目标目录不存在则创建
-    fs::copy(config_file_in_resources, target_config_path.clone())?;
-    info!("配置文件已初始化到目标目录：{:?}", target_config_path);
+    init_config_files(
+        &config_path,
+        &resource_path.join("configuration/config.yaml"),
+    )?;
     Ok(config_path)
 }
 
+/// 初始化配置目录中的文件
+///
+/// 安装目录中的配置复制为 config.default.yaml（每次启动刷新）；用户的 config.yaml 不存在时创建，
+/// 已存在时不覆盖，旧版本的 config.yaml 迁移到当前版本，原文件备份为 config.yaml.bak
+pub fn init_config_files(config_path: &Path, bundled_config: &Path) -> Result<(), ConfigError> {
+    let io_error = |e: std::io::Error| ConfigError::Foreign(Box::new(e));
+    if !bundled_config.exists() {
+        error!("Config file not found in {:?}", bundled_config);
+        return Err(ConfigError::NotFound(format!(
+            "安装目录中缺少配置文件：{}",
+            bundled_config.display()
+        )));
+    }
+    // 默认配置随应用升级，每次启动都刷新
+    fs::create_dir_all(config_path).map_err(io_error)?; // 如果目标目录不存在则创建
+    let default_file = config_path.join(DEFAULT_CONFIG_FILE);
+    fs::copy(bundled_config, &default_file).map_err(io_error)?;
+
+    let user_file = config_path.join(USER_CONFIG_FILE);
+    if !user_file.exists() {
+        fs::write(
+            &user_file,
+            format!("{USER_CONFIG_HEADER}version: {CONFIG_VERSION}\n"),
+        )
+        .map_err(io_error)?;
+        info!("用户配置文件已创建：{:?}", user_file);
+        return Ok(());
+    }
+    let defaults = read_yaml(&default_file)?;
+    let mut user = read_yaml(&user_file)?;
+    if migrate_user_config(&mut user, &defaults)? {
+        let backup = config_path.join(format!("{USER_CONFIG_FILE}.bak"));
+        fs::copy(&user_file, &backup).map_err(io_error)?;
+        fs::write(
+            &user_file,
+            format!("{USER_CONFIG_HEADER}{}\n", dump_yaml(&user)?),
+        )
+        .map_err(io_error)?;
+        info!(
+            "用户配置文件已迁移到版本 {CONFIG_VERSION}，原文件备份为：{:?}",
+            backup
+        );
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,4 +727,273 @@ mod tests {
         assert_eq!(drama_sources.len(), 1);
         assert_eq!(drama_sources[0].name, "腾讯视频");
     }
+
+    #[test]
+    fn test_http_server_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let datasource = "datasource:\n  anime: []\n";
+
+        // 未配置时使用默认值：不启用、只监听本机
+        fs::write(dir.path().join("config.yaml"), datasource).unwrap();
+        let config = load_configuration(dir.path().to_path_buf()).unwrap();
+        assert_eq!(config.http_server, HttpServerConfig::default());
+        assert!(!config.http_server.enabled);
+        assert_eq!(config.http_server.host, "127.0.0.1");
+
+        fs::write(
+            dir.path().join("config.yaml"),
+            format!("{datasource}http_server:\n  enabled: true\n  port: 9000\n"),
+        )
+        .unwrap();
+        let config = load_configuration(dir.path().to_path_buf()).unwrap();
+        assert!(config.http_server.enabled);
+        assert_eq!(config.http_server.host, "127.0.0.1");
+        assert_eq!(config.http_server.port, 9000);
+        assert_eq!(config.http_server.token, None);
+
+        fs::write(
+            dir.path().join("config.yaml"),
+            format!("{datasource}http_server:\n  host: 0.0.0.0\n  token: secret\n"),
+        )
+        .unwrap();
+        let config = load_configuration(dir.path().to_path_buf()).unwrap();
+        assert_eq!(config.http_server.host, "0.0.0.0");
+        assert_eq!(config.http_server.token.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_retry_policy_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("config.yaml"),
+            r#"
+datasource:
+  anime:
+    - name: "默认"
+      url: "https://example.com/a"
+      cmd: "fetch_bilibili_ani_data"
+      cron_expr: "0 0 * * * * *"
+      retry_times: 2
+    - name: "自定义"
+      url: "https://example.com/b"
+      cmd: "fetch_bilibili_ani_data"
+      cron_expr: "0 0 * * * * *"
+      retry_times: 3
+      retry_policy:
+        base_delay_secs: 10
+        max_delay_secs: 60
+"#,
+        )
+        .unwrap();
+        let config = load_configuration(dir.path().to_path_buf()).unwrap();
+        let sources = &config.datasource["anime"];
+        assert_eq!(sources[0].retry_policy, RetryPolicy::default());
+        let policy = &sources[1].retry_policy;
+        assert_eq!(policy.base_delay_secs, 10);
+        assert_eq!(policy.multiplier, 2.0);
+
+        // 指数退避，抖动在 ±20% 以内，不超过最长等待时间
+        assert_eq!(policy.backoff(0, 0.5), Duration::from_secs(10));
+        assert_eq!(policy.backoff(1, 0.5), Duration::from_secs(20));
+        assert_eq!(policy.backoff(1, 0.0), Duration::from_secs(16));
+        assert_eq!(policy.backoff(1, 1.0), Duration::from_secs(24));
+        assert_eq!(policy.backoff(5, 0.5), Duration::from_secs(60));
+        assert_eq!(
+            policy.cap(Duration::from_secs(3600)),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_push_channel_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("config.yaml"),
+            r#"
+datasource:
+  anime: []
+notification:
+  push:
+    - type: webhook
+      url: "https://example.com/hook"
+      headers:
+        Authorization: "Bearer token"
+    - type: telegram
+      bot_token: "123:abc"
+      chat_id: "-100"
+    - type: smtp
+      host: "smtp.example.com"
+      from: "AniTodo <bot@example.com>"
+      to: ["me@example.com"]
+"#,
+        )
+        .unwrap();
+        let config = load_configuration(dir.path().to_path_buf()).unwrap();
+        let notification = config.notification;
+        assert!(notification.desktop);
+        assert_eq!(notification.retry_times, 3);
+        assert_eq!(notification.push.len(), 3);
+        match &notification.push[0] {
+            PushChannelConfig::Webhook(c) => {
+                assert_eq!(c.method, "POST");
+                assert_eq!(c.headers.len(), 1);
+                assert_eq!(c.template, None);
+            }
+            other => panic!("unexpected channel: {other:?}"),
+        }
+        match &notification.push[1] {
+            PushChannelConfig::Telegram(c) => {
+                assert_eq!(c.api_base, "https://api.telegram.org");
+                assert_eq!(c.chat_id, "-100");
+            }
+            other => panic!("unexpected channel: {other:?}"),
+        }
+        match &notification.push[2] {
+            PushChannelConfig::Smtp(c) => {
+                assert_eq!(c.port, 587);
+                assert_eq!(c.security, "starttls");
+                assert_eq!(c.to, vec!["me@example.com".to_string()]);
+            }
+            other => panic!("unexpected channel: {other:?}"),
+        }
+    }
+
+    const BUNDLED_CONFIG: &str = r#"
+version: 2
+datasource:
+  anime:
+    - name: "哔哩哔哩国创"
+      url: "https://api.bilibili.com/pgc/web/timeline?types=4"
+      cmd: "fetch_bilibili_ani_data"
+      cron_expr: "0 17 10 * * * *"
+      retry_times: 0
+    - name: "优酷动漫"
+      url: "https://www.youku.com/ku/webcomic"
+      cmd: "fetch_youku_ani_data"
+      cron_expr: "0 17 10 * * * *"
+      retry_times: 0
+http_server:
+  enabled: false
+  port: 8686
+"#;
+
+    #[test]
+    fn test_layered_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundled = dir.path().join("bundled.yaml");
+        fs::write(&bundled, BUNDLED_CONFIG).unwrap();
+        let config_path = dir.path().join("config");
+
+        // 首次启动：复制默认配置并创建用户配置模板
+        init_config_files(&config_path, &bundled).unwrap();
+        assert!(config_path.join(DEFAULT_CONFIG_FILE).exists());
+        let config = load_configuration(config_path.clone()).unwrap();
+        assert_eq!(config.datasource["anime"].len(), 2);
+        assert!(config.datasource["anime"].iter().all(|s| s.enabled));
+
+        // 用户配置只写改动：同名数据源按字段合并，新数据源追加，其它表按键合并
+        let user = format!(
+            r#"version: {CONFIG_VERSION}
+datasource:
+  anime:
+    - name: "哔哩哔哩国创"
+      cron_expr: "0 0 9 * * * *"
+    - name: "优酷动漫"
+      enabled: false
+  drama:
+    - name: "腾讯视频"
+      url: "https://v.qq.com/channel/drama"
+      cmd: "tencent_parser"
+      cron_expr: "0 0 * * * * *"
+      retry_times: 1
+http_server:
+  port: 9000
+"#
+        );
+        fs::write(config_path.join(USER_CONFIG_FILE), &user).unwrap();
+        // 再次启动不会覆盖用户配置
+        init_config_files(&config_path, &bundled).unwrap();
+        assert_eq!(
+            fs::read_to_string(config_path.join(USER_CONFIG_FILE)).unwrap(),
+            user
+        );
+        let config = load_configuration(config_path.clone()).unwrap();
+        let anime = &config.datasource["anime"];
+        assert_eq!(anime.len(), 2);
+        assert_eq!(anime[0].cron_expr, "0 0 9 * * * *");
+        assert_eq!(anime[0].cmd, "fetch_bilibili_ani_data");
+        assert!(!anime[1].enabled);
+        assert_eq!(config.datasource["drama"][0].retry_times, 1);
+        assert!(!config.http_server.enabled);
+        assert_eq!(config.http_server.port, 9000);
+
+        // 配置有误时返回错误而不是 panic
+        fs::write(config_path.join(USER_CONFIG_FILE), "datasource: [\n").unwrap();
+        let err = load_configuration(config_path.clone()).unwrap_err();
+        assert!(err.to_string().contains(USER_CONFIG_FILE));
+        fs::write(config_path.join(USER_CONFIG_FILE), "version: 99\n").unwrap();
+        assert!(load_configuration(config_path.clone()).is_err());
+        assert!(init_config_files(&config_path, &dir.path().join("missing.yaml")).is_err());
+    }
+
+    #[test]
+    fn test_migrate_user_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundled = dir.path().join("bundled.yaml");
+        fs::write(&bundled, BUNDLED_CONFIG).unwrap();
+        // 版本 1 的用户配置是旧内置配置的完整副本：改了 cron，删掉了优酷动漫
+        let old = r#"
+datasource:
+  anime:
+    - name: "哔哩哔哩国创"
+      url: "https://api.bilibili.com/pgc/web/timeline?types=4"
+      cmd: "fetch_bilibili_ani_data"
+      cron_expr: "0 0 9 * * * *"
+      retry_times: 0
+http_server:
+  enabled: false
+  port: 8686
+"#;
+        fs::write(dir.path().join(USER_CONFIG_FILE), old).unwrap();
+
+        init_config_files(dir.path(), &bundled).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join(format!("{USER_CONFIG_FILE}.bak"))).unwrap(),
+            old
+        );
+        // 迁移后只保留改动，再次启动不会重复迁移
+        let mut user = read_yaml(&dir.path().join(USER_CONFIG_FILE)).unwrap();
+        assert_eq!(user["version"], Yaml::Integer(CONFIG_VERSION));
+        assert!(user["http_server"].is_badvalue());
+        let sources = user["datasource"]["anime"].as_vec().unwrap();
+        assert_eq!(sources.len(), 2);
+        assert!(sources[0]["url"].is_badvalue());
+        assert_eq!(sources[1]["enabled"], Yaml::Boolean(false));
+        assert!(!migrate_user_config(&mut user, &Yaml::Null).unwrap());
+
+        let config = load_configuration(dir.path().to_path_buf()).unwrap();
+        let anime = &config.datasource["anime"];
+        assert_eq!(anime[0].cron_expr, "0 0 9 * * * *");
+        assert_eq!(anime[1].name, "优酷动漫");
+        assert!(!anime[1].enabled);
+    }
+
+    #[test]
+    fn test_load_title_alias_rules() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // 文件不存在时写入模板，规则为空
+        assert!(load_title_alias_rules(dir.path()).unwrap().is_empty());
+        assert!(dir.path().join(TITLE_ALIAS_FILE).exists());
+
+        fs::write(
+            dir.path().join(TITLE_ALIAS_FILE),
+            "rules:\n  - title: 凡人修仙传\n    aliases:\n      - Dr.STONE 新石纪\n",
+        )
+        .unwrap();
+        let rules = load_title_alias_rules(dir.path()).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].title, "凡人修仙传");
+        assert_eq!(rules[0].aliases, vec!["Dr.STONE 新石纪".to_string()]);
+    }
 }
@@ -1,6 +1,5 @@
 use crate::db::sqlite::upsert_ani_info;
 use crate::types::{AniItemResult, ApiResponse};
-use crate::utils::date_utils::get_today_weekday;
 use anyhow::Result;
 use serde_json::json;
 use sqlx::{
@@ -30,21 +29,23 @@ where
 }
 
 // 内部通用函数：接受 Arc<SqlitePool>，可被后台任务/命令/测试调用
+// 结果中所有日期的数据都会按各自的更新日期保存，now 用于判断已保存的记录是否还未播出
 pub async fn save_ani_item_data_db(
     db: Arc<SqlitePool>,
     ani_data: AniItemResult,
+    now: i64,
 ) -> Result<ApiResponse, String> {
     let pool = ge_db_pool(&db);
-    let weekday = get_today_weekday().name_cn.to_string();
 
-    let items = match ani_data.get(&weekday) {
-        Some(v) if !v.is_empty() => v,
-        Some(_) => return Ok(ApiResponse::ok(json!({ "message": "没有可插入的数据" }))),
-        None => return Ok(ApiResponse::err("获取今日动漫数据失败")),
-    };
+    if ani_data.is_empty() {
+        return Ok(ApiResponse::err("获取动漫数据失败"));
+    }
+    if ani_data.values().all(Vec::is_empty) {
+        return Ok(ApiResponse::ok(json!({ "message": "没有可插入的数据" })));
+    }
 
-    for item in items {
-        if let Err(e) = upsert_ani_info(pool, item).await {
+    for item in ani_data.values().flatten() {
+        if let Err(e) = upsert_ani_info(pool, item, now).await {
             return Ok(ApiResponse::err(format!("插入失败：{e}")));
         }
     }
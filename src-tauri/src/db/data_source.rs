@@ -0,0 +1,572 @@
+use crate::configuration::{DataSource, RetryPolicy};
+use crate::db::po::DataSourceRecord;
+use crate::tasks::task::TaskMeta;
+use anyhow::{bail, Context, Result};
+use cron::Schedule;
+use log::warn;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+const SELECT_DATA_SOURCE: &str = r#"
+        SELECT id, name, category, url, cmd, cron_expr, retry_times, retry_policy, enabled, managed, created_time, updated_time
+        FROM data_source
+    "#;
+
+/// 配置文件中的一个数据源
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSource {
+    pub meta: TaskMeta,
+    /// 停用的数据源同样保存到数据库，便于在应用中重新启用
+    pub enabled: bool,
+    /// 检查未通过（如 cron 表达式有误）时为 false，数据库中保留上次有效的记录
+    pub valid: bool,
+}
+
+/// 用配置文件中的数据源更新数据库：
+/// - 新的数据源插入数据库，停用的数据源保存为停用；
+/// - 没有在应用中修改过的数据源按配置文件更新，配置文件中已删除的随之删除；
+/// - 检查未通过的数据源保持上次有效的记录不变；
+/// - 在应用中修改、删除过的数据源保持不变
+pub async fn sync_config_data_sources(
+    pool: &SqlitePool,
+    sources: &[ConfigSource],
+    now: i64,
+) -> Result<()> {
+    let mut tx = pool.begin().await.context("开启事务失败")?;
+    for ConfigSource { meta, enabled, .. } in sources.iter().filter(|s| s.valid) {
+        let retry_policy = serde_json::to_string(&meta.retry_policy)?;
+        let existing = sqlx::query_as::<_, (i64, bool)>(
+            r#"SELECT id, managed FROM data_source WHERE name = ?"#,
+        )
+        .bind(&meta.name)
+        .fetch_optional(&mut *tx)
+        .await
+        .context(format!("查询数据源 [{}] 失败", meta.name))?;
+        match existing {
+            None => {
+                sqlx::query(
+                    r#"
+                        INSERT INTO data_source (name, category, url, cmd, cron_expr, retry_times, retry_policy, enabled, managed, created_time, updated_time)
+                        VALUES (?, ?, ?, ?, ?, ?, ?, ?, 1, ?, ?)
+                    "#,
+                )
+                .bind(&meta.name)
+                .bind(&meta.category)
+                .bind(&meta.arg)
+                .bind(&meta.cmd)
+                .bind(&meta.cron_expr)
+                .bind(meta.retry_times as i64)
+                .bind(&retry_policy)
+                .bind(enabled)
+                .bind(now)
+                .bind(now)
+                .execute(&mut *tx)
+                .await
+                .context(format!("新增数据源 [{}] 失败", meta.name))?;
+            }
+            Some((id, true)) => {
+                // 只有内容变化时才更新修改时间
+                sqlx::query(
+                    r#"
+                        UPDATE data_source
+                        SET category = ?, url = ?, cmd = ?, cron_expr = ?, retry_times = ?, retry_policy = ?, enabled = ?, updated_time = ?
+                        WHERE id = ?
+                          AND (category IS NOT ? OR url IS NOT ? OR cmd IS NOT ? OR cron_expr IS NOT ?
+                               OR retry_times IS NOT ? OR retry_policy IS NOT ? OR enabled IS NOT ?)
+                    "#,
+                )
+                .bind(&meta.category)
+                .bind(&meta.arg)
+                .bind(&meta.cmd)
+                .bind(&meta.cron_expr)
+                .bind(meta.retry_times as i64)
+                .bind(&retry_policy)
+                .bind(enabled)
+                .bind(now)
+                .bind(id)
+                .bind(&meta.category)
+                .bind(&meta.arg)
+                .bind(&meta.cmd)
+                .bind(&meta.cron_expr)
+                .bind(meta.retry_times as i64)
+                .bind(&retry_policy)
+                .bind(enabled)
+                .execute(&mut *tx)
+                .await
+                .context(format!("更新数据源 [{}] 失败", meta.name))?;
+            }
+            Some((_, false)) => {}
+        }
+    }
+
+    // 检查未通过的数据源也在配置文件中，不删除
+    let names: HashSet<&str> = sources.iter().map(|s| s.meta.name.as_str()).collect();
+    let managed =
+        sqlx::query_as::<_, (i64, String)>(r#"SELECT id, name FROM data_source WHERE managed = 1"#)
+            .fetch_all(&mut *tx)
+            .await
+            .context("查询数据源失败")?;
+    for (id, name) in managed {
+        if !names.contains(name.as_str()) {
+            sqlx::query(r#"DELETE FROM data_source WHERE id = ?"#)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .context(format!("删除数据源 [{name}] 失败"))?;
+        }
+    }
+    tx.commit().await.context("提交事务失败")?;
+    Ok(())
+}
+
+/// 查询所有数据源（已删除的除外），按分类排序
+pub async fn list_data_source(pool: &SqlitePool) -> Result<Vec<DataSourceRecord>> {
+    let list = sqlx::query_as::<_, DataSourceRecord>(&format!(
+        "{SELECT_DATA_SOURCE} WHERE deleted = 0 ORDER BY category, id"
+    ))
+    .fetch_all(pool)
+    .await
+    .context("查询数据源失败")?;
+    Ok(list)
+}
+
+/// 启用的数据源转换为定时作业配置
+pub async fn list_enabled_task_metas(pool: &SqlitePool) -> Result<Vec<TaskMeta>> {
+    let metas = list_data_source(pool)
+        .await?
+        .into_iter()
+        .filter(|source| source.enabled)
+        .filter(|source| {
+            let valid = Schedule::from_str(&source.cron_expr).is_ok();
+            if !valid {
+                warn!(
+                    "数据源 [{}] 的 cron 表达式无效，不创建定时任务",
+                    source.name
+                );
+            }
+            valid
+        })
+        .map(|source| {
+            let retry_policy = record_retry_policy(&source);
+            TaskMeta {
+                name: source.name,
+                cmd: source.cmd,
+                arg: source.url,
+                cron_expr: source.cron_expr,
+                retry_times: source.retry_times.clamp(0, u8::MAX as i64) as u8,
+                retry_policy,
+                category: source.category,
+            }
+        })
+        .collect();
+    Ok(metas)
+}
+
+/// 数据源保存的重试策略，为空或格式有误时使用默认策略
+fn record_retry_policy(source: &DataSourceRecord) -> RetryPolicy {
+    source
+        .retry_policy
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<RetryPolicy>(json).ok())
+        .unwrap_or_default()
+}
+
+/// 在应用中新增数据源，名称与已删除的数据源相同时恢复该记录
+pub async fn insert_data_source(
+    pool: &SqlitePool,
+    category: &str,
+    source: &DataSource,
+    now: i64,
+) -> Result<i64> {
+    let retry_policy = serde_json::to_string(&source.retry_policy)?;
+    let existing =
+        sqlx::query_as::<_, (i64, bool)>(r#"SELECT id, deleted FROM data_source WHERE name = ?"#)
+            .bind(&source.name)
+            .fetch_optional(pool)
+            .await
+            .context(format!("查询数据源 [{}] 失败", source.name))?;
+    match existing {
+        Some((_, false)) => bail!("数据源名称已存在：{}", source.name),
+        Some((id, true)) => {
+            sqlx::query(
+                r#"
+                    UPDATE data_source
+                    SET category = ?, url = ?, cmd = ?, cron_expr = ?, retry_times = ?, retry_policy = ?, enabled = ?,
+                        managed = 0, deleted = 0, created_time = ?, updated_time = ?
+                    WHERE id = ?
+                "#,
+            )
+            .bind(category)
+            .bind(&source.url)
+            .bind(&source.cmd)
+            .bind(&source.cron_expr)
+            .bind(source.retry_times as i64)
+            .bind(&retry_policy)
+            .bind(source.enabled)
+            .bind(now)
+            .bind(now)
+            .bind(id)
+            .execute(pool)
+            .await
+            .context(format!("新增数据源 [{}] 失败", source.name))?;
+            Ok(id)
+        }
+        None => {
+            let result = sqlx::query(
+                r#"
+                    INSERT INTO data_source (name, category, url, cmd, cron_expr, retry_times, retry_policy, enabled, managed, created_time, updated_time)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0, ?, ?)
+                "#,
+            )
+            .bind(&source.name)
+            .bind(category)
+            .bind(&source.url)
+            .bind(&source.cmd)
+            .bind(&source.cron_expr)
+            .bind(source.retry_times as i64)
+            .bind(&retry_policy)
+            .bind(source.enabled)
+            .bind(now)
+            .bind(now)
+            .execute(pool)
+            .await
+            .context(format!("新增数据源 [{}] 失败", source.name))?;
+            Ok(result.last_insert_rowid())
+        }
+    }
+}
+
+/// 在应用中修改数据源，修改后不再跟随配置文件更新
+pub async fn update_data_source(
+    pool: &SqlitePool,
+    id: i64,
+    category: &str,
+    source: &DataSource,
+    now: i64,
+) -> Result<()> {
+    let retry_policy = serde_json::to_string(&source.retry_policy)?;
+    let mut tx = pool.begin().await.context("开启事务失败")?;
+    let existing = sqlx::query_as::<_, (i64, bool)>(
+        r#"SELECT id, deleted FROM data_source WHERE name = ? AND id != ?"#,
+    )
+    .bind(&source.name)
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .context(format!("查询数据源 [{}] 失败", source.name))?;
+    match existing {
+        Some((_, false)) => bail!("数据源名称已存在：{}", source.name),
+        // 同名的已删除记录不再需要，腾出名称
+        Some((deleted_id, true)) => {
+            sqlx::query(r#"DELETE FROM data_source WHERE id = ?"#)
+                .bind(deleted_id)
+                .execute(&mut *tx)
+                .await
+                .context(format!("删除数据源 [{}] 失败", source.name))?;
+        }
+        None => {}
+    }
+    let result = sqlx::query(
+        r#"
+            UPDATE data_source
+            SET name = ?, category = ?, url = ?, cmd = ?, cron_expr = ?, retry_times = ?, retry_policy = ?, enabled = ?,
+                managed = 0, updated_time = ?
+            WHERE id = ? AND deleted = 0
+        "#,
+    )
+    .bind(&source.name)
+    .bind(category)
+    .bind(&source.url)
+    .bind(&source.cmd)
+    .bind(&source.cron_expr)
+    .bind(source.retry_times as i64)
+    .bind(&retry_policy)
+    .bind(source.enabled)
+    .bind(now)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .context(format!("修改数据源 [{}] 失败", source.name))?;
+    if result.rows_affected() == 0 {
+        bail!("数据源不存在：{id}");
+    }
+    tx.commit().await.context("提交事务失败")?;
+    Ok(())
+}
+
+/// 在应用中删除数据源，保留记录，避免配置文件中的同名数据源再次添加
+pub async fn delete_data_source(pool: &SqlitePool, id: i64, now: i64) -> Result<()> {
+    let result = sqlx::query(
+        r#"
+            UPDATE data_source
+            SET deleted = 1, enabled = 0, managed = 0, updated_time = ?
+            WHERE id = ? AND deleted = 0
+        "#,
+    )
+    .bind(now)
+    .bind(id)
+    .execute(pool)
+    .await
+    .context(format!("删除数据源 {id} 失败"))?;
+    if result.rows_affected() == 0 {
+        bail!("数据源不存在：{id}");
+    }
+    Ok(())
+}
+
+/// 启用或停用数据源，修改后不再跟随配置文件更新
+pub async fn set_data_source_enabled(
+    pool: &SqlitePool,
+    id: i64,
+    enabled: bool,
+    now: i64,
+) -> Result<()> {
+    let result = sqlx::query(
+        r#"
+            UPDATE data_source
+            SET enabled = ?, managed = 0, updated_time = ?
+            WHERE id = ? AND deleted = 0
+        "#,
+    )
+    .bind(enabled)
+    .bind(now)
+    .bind(id)
+    .execute(pool)
+    .await
+    .context(format!("修改数据源 {id} 失败"))?;
+    if result.rows_affected() == 0 {
+        bail!("数据源不存在：{id}");
+    }
+    Ok(())
+}
+
+/// 在应用中修改数据源的 cron 表达式，和其它修改一样不再跟随配置文件更新
+pub async fn reschedule_data_source(
+    pool: &SqlitePool,
+    name: &str,
+    cron_expr: &str,
+    now: i64,
+) -> Result<()> {
+    let Some(record) = list_data_source(pool)
+        .await?
+        .into_iter()
+        .find(|source| source.name == name)
+    else {
+        bail!("数据源不存在：{name}");
+    };
+    let source = DataSource {
+        retry_policy: record_retry_policy(&record),
+        name: record.name,
+        url: record.url,
+        cmd: record.cmd,
+        cron_expr: cron_expr.to_string(),
+        retry_times: record.retry_times.clamp(0, u8::MAX as i64) as u8,
+        enabled: record.enabled,
+    };
+    update_data_source(pool, record.id, &record.category, &source, now).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::MIGRATOR;
+
+    fn meta(name: &str, cron_expr: &str) -> TaskMeta {
+        TaskMeta {
+            name: name.to_string(),
+            cmd: "fetch_bilibili_ani_data".to_string(),
+            arg: format!("https://example.com/{name}"),
+            cron_expr: cron_expr.to_string(),
+            retry_times: 1,
+            retry_policy: RetryPolicy::default(),
+            category: "anime".to_string(),
+        }
+    }
+
+    fn config(name: &str, cron_expr: &str) -> ConfigSource {
+        ConfigSource {
+            meta: meta(name, cron_expr),
+            enabled: true,
+            valid: true,
+        }
+    }
+
+    fn source(name: &str, cron_expr: &str) -> DataSource {
+        DataSource {
+            name: name.to_string(),
+            url: format!("https://example.com/{name}"),
+            cmd: "fetch_bilibili_ani_data".to_string(),
+            cron_expr: cron_expr.to_string(),
+            retry_times: 0,
+            retry_policy: RetryPolicy::default(),
+            enabled: true,
+        }
+    }
+
+    async fn crons(pool: &SqlitePool) -> Vec<(String, String)> {
+        list_enabled_task_metas(pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.name, m.cron_expr))
+            .collect()
+    }
+
+    fn pair(name: &str, cron_expr: &str) -> (String, String) {
+        (name.to_string(), cron_expr.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_data_source_crud() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        let hourly = "0 0 * * * * *";
+        let half = "0 30 * * * * *";
+
+        // 配置文件初始化数据库
+        sync_config_data_sources(&pool, &[config("A", hourly), config("B", hourly)], 1_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            crons(&pool).await,
+            vec![pair("A", hourly), pair("B", hourly)]
+        );
+        let metas = list_enabled_task_metas(&pool).await.unwrap();
+        assert_eq!(metas[0], meta("A", hourly));
+
+        // 在应用中修改 A、删除 B、新增 C
+        let list = list_data_source(&pool).await.unwrap();
+        let (a, b) = (list[0].id, list[1].id);
+        update_data_source(&pool, a, "anime", &source("A", half), 2_000)
+            .await
+            .unwrap();
+        delete_data_source(&pool, b, 2_000).await.unwrap();
+        let c = insert_data_source(&pool, "drama", &source("C", hourly), 2_000)
+            .await
+            .unwrap();
+        assert!(
+            insert_data_source(&pool, "drama", &source("C", hourly), 2_000)
+                .await
+                .is_err()
+        );
+        assert_eq!(crons(&pool).await, vec![pair("A", half), pair("C", hourly)]);
+        // 创建、修改时间使用传入的时刻
+        let list = list_data_source(&pool).await.unwrap();
+        assert_eq!((list[0].created_time, list[0].updated_time), (1_000, 2_000));
+        assert_eq!((list[1].created_time, list[1].updated_time), (2_000, 2_000));
+
+        // 配置文件再次加载时，应用中修改、删除过的数据源不受影响，配置中删除的随之删除
+        sync_config_data_sources(
+            &pool,
+            &[
+                config("A", hourly),
+                config("B", hourly),
+                config("D", hourly),
+            ],
+            3_000,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            crons(&pool).await,
+            vec![pair("A", half), pair("D", hourly), pair("C", hourly)]
+        );
+        sync_config_data_sources(&pool, &[config("A", hourly)], 3_000)
+            .await
+            .unwrap();
+        assert_eq!(crons(&pool).await, vec![pair("A", half), pair("C", hourly)]);
+
+        // 停用、改名为已删除的名称、恢复已删除的数据源
+        set_data_source_enabled(&pool, c, false, 4_000)
+            .await
+            .unwrap();
+        assert_eq!(crons(&pool).await, vec![pair("A", half)]);
+        assert_eq!(list_data_source(&pool).await.unwrap().len(), 2);
+        update_data_source(&pool, c, "drama", &source("B", hourly), 4_000)
+            .await
+            .unwrap();
+        assert_eq!(crons(&pool).await, vec![pair("A", half), pair("B", hourly)]);
+        delete_data_source(&pool, c, 4_000).await.unwrap();
+        assert!(delete_data_source(&pool, c, 4_000).await.is_err());
+        assert_eq!(
+            insert_data_source(&pool, "anime", &source("B", half), 5_000)
+                .await
+                .unwrap(),
+            c
+        );
+        assert_eq!(crons(&pool).await, vec![pair("A", half), pair("B", half)]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_disabled_and_invalid_sources() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        let hourly = "0 0 * * * * *";
+        let disabled = ConfigSource {
+            enabled: false,
+            ..config("B", hourly)
+        };
+
+        // 停用的数据源保存为停用，不创建任务，但可以在应用中重新启用
+        sync_config_data_sources(&pool, &[config("A", hourly), disabled], 1_000)
+            .await
+            .unwrap();
+        assert_eq!(crons(&pool).await, vec![pair("A", hourly)]);
+        let list = list_data_source(&pool).await.unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(!list[1].enabled);
+
+        // 配置中启用后随之启用
+        sync_config_data_sources(&pool, &[config("A", hourly), config("B", hourly)], 2_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            crons(&pool).await,
+            vec![pair("A", hourly), pair("B", hourly)]
+        );
+
+        // 检查未通过的数据源保留上次有效的记录
+        let invalid = ConfigSource {
+            valid: false,
+            ..config("A", "每小时")
+        };
+        sync_config_data_sources(&pool, &[invalid, config("B", hourly)], 3_000)
+            .await
+            .unwrap();
+        assert_eq!(
+            crons(&pool).await,
+            vec![pair("A", hourly), pair("B", hourly)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_data_source() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        let hourly = "0 0 * * * * *";
+        let half = "0 30 * * * * *";
+        sync_config_data_sources(&pool, &[config("A", hourly)], 1_000)
+            .await
+            .unwrap();
+
+        // 修改 cron 后保存到数据库，配置文件再次加载时不会恢复
+        reschedule_data_source(&pool, "A", half, 2_000)
+            .await
+            .unwrap();
+        assert!(reschedule_data_source(&pool, "B", half, 2_000)
+            .await
+            .is_err());
+        sync_config_data_sources(&pool, &[config("A", hourly)], 3_000)
+            .await
+            .unwrap();
+        assert_eq!(crons(&pool).await, vec![pair("A", half)]);
+        let list = list_data_source(&pool).await.unwrap();
+        assert!(!list[0].managed);
+        assert_eq!(list[0].updated_time, 2_000);
+        assert_eq!(
+            list_enabled_task_metas(&pool).await.unwrap()[0].retry_times,
+            1
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! 测试共用的数据
+
+use crate::types::AniItem;
+use crate::utils::date_utils::parse_date_to_millis;
+
+/// 测试中写入数据的时刻（2025/08/01），测试数据的更新日期都在此之前，即都已播出
+pub fn now() -> i64 {
+    parse_date_to_millis("2025/08/01", true).unwrap()
+}
+
+/// 哔哩哔哩上《凡人修仙传》第 count 集在 date（如 2025/07/13）的更新
+pub fn fanren_item(count: &str, date: &str) -> AniItem {
+    AniItem {
+        title: "凡人修仙传".to_string(),
+        update_count: count.to_string(),
+        update_info: format!("更新至第{count}话"),
+        image_url: "https://i0.hdslb.com/bfs/bangumi/image/f0e1d2c3b4a5.png".to_string(),
+        detail_url: format!("https://www.bilibili.com/bangumi/play/ep{count}"),
+        update_time: date.to_string(),
+        platform: "bilibili".to_string(),
+        external_id: "28747".to_string(),
+        category: String::new(),
+    }
+}
@@ -1,3 +1,10 @@
 pub mod common;
+pub mod data_source;
+#[cfg(test)]
+pub mod fixtures;
+pub mod notification;
 pub mod po;
+pub mod progress;
+pub mod series;
 pub mod sqlite;
+pub mod task_run;
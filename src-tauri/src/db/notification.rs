@@ -0,0 +1,124 @@
+use crate::db::po::{Ani, NotifyDelivery};
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+
+/// 通知去重用的剧集键：有集数时为集数，同一番剧同一集在各平台只通知一次；
+/// 没有集数时（如 mikanani）为这条更新记录和它的更新时间，每次更新都会通知
+pub fn episode_key(ani: &Ani) -> String {
+    if ani.update_count.starts_with(|c: char| c.is_ascii_digit()) {
+        ani.update_count.clone()
+    } else {
+        format!("#{}@{}", ani.id, ani.update_time)
+    }
+}
+
+/// 查询渠道已发送过的剧集 (series_id, 剧集键)，旧版本不区分渠道的记录对所有渠道都算已发送
+pub async fn list_notified_episodes(
+    pool: &SqlitePool,
+    channel: &str,
+) -> Result<HashSet<(i64, String)>> {
+    let list = sqlx::query_as::<_, (i64, String)>(
+        r#"SELECT series_id, update_count FROM ani_notification WHERE channel = ? OR channel = ''"#,
+    )
+    .bind(channel)
+    .fetch_all(pool)
+    .await
+    .context(format!("查询 {channel} 已发送的通知失败"))?;
+    Ok(list.into_iter().collect())
+}
+
+/// 记录渠道已发送某番剧某一集（见 [`episode_key`]）的通知
+pub async fn mark_notified(
+    pool: &SqlitePool,
+    channel: &str,
+    series_id: i64,
+    episode: &str,
+    notified_time: i64,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+                INSERT OR IGNORE INTO ani_notification (channel, series_id, update_count, notified_time)
+                VALUES (?, ?, ?, ?)
+            "#,
+    )
+    .bind(channel)
+    .bind(series_id)
+    .bind(episode)
+    .bind(notified_time)
+    .execute(pool)
+    .await
+    .context(format!(
+        "记录 {channel} 已发送番剧 series_id={series_id} 剧集 {episode} 的通知失败"
+    ))?;
+    Ok(())
+}
+
+/// 设置番剧是否关闭通知
+pub async fn set_series_notify_muted(pool: &SqlitePool, series_id: i64, muted: bool) -> Result<()> {
+    let res = sqlx::query(r#"UPDATE series SET notify_muted = ? WHERE id = ?"#)
+        .bind(muted)
+        .bind(series_id)
+        .execute(pool)
+        .await
+        .context(format!("设置番剧 series_id={series_id} 的通知开关失败"))?;
+    if res.rows_affected() == 0 {
+        return Err(anyhow::anyhow!("番剧 series_id={series_id} 不存在"));
+    }
+    Ok(())
+}
+
+/// 查询关闭了通知的番剧
+pub async fn list_muted_series(pool: &SqlitePool) -> Result<HashSet<i64>> {
+    let ids = sqlx::query_scalar::<_, i64>(r#"SELECT id FROM series WHERE notify_muted = 1"#)
+        .fetch_all(pool)
+        .await
+        .context("查询关闭通知的番剧失败")?;
+    Ok(ids.into_iter().collect())
+}
+
+/// 记录一次通知投递的结果
+pub async fn insert_notify_delivery(
+    pool: &SqlitePool,
+    channel: &str,
+    ani_item_id: Option<i64>,
+    title: &str,
+    error: Option<&str>,
+    attempts: u32,
+    delivered_time: i64,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+                INSERT INTO notify_delivery (channel, ani_item_id, title, status, attempts, error, delivered_time)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+    )
+    .bind(channel)
+    .bind(ani_item_id)
+    .bind(title)
+    .bind(if error.is_none() { "ok" } else { "failed" })
+    .bind(attempts)
+    .bind(error)
+    .bind(delivered_time)
+    .execute(pool)
+    .await
+    .context(format!("记录 {channel} 通知投递结果失败"))?;
+    Ok(())
+}
+
+/// 查询最近的通知投递记录
+pub async fn list_notify_delivery(pool: &SqlitePool, limit: i64) -> Result<Vec<NotifyDelivery>> {
+    let list = sqlx::query_as::<_, NotifyDelivery>(
+        r#"
+                SELECT id, channel, ani_item_id, title, status, attempts, error, delivered_time
+                FROM notify_delivery
+                ORDER BY delivered_time DESC, id DESC
+                LIMIT ?
+            "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .context("查询通知投递记录失败")?;
+    Ok(list)
+}
@@ -13,6 +13,11 @@ pub struct Ani {
     pub detail_url: String,
     pub update_time: i64,
     pub platform: String,
+    #[sqlx(default)]
+    pub series_id: Option<i64>,
+    #[serde(default)]
+    #[sqlx(default)]
+    pub category: String,
 }
 
 pub type AniIResult = HashMap<String, Vec<AniDto>>;
@@ -46,6 +51,8 @@ pub struct AniDto {
     pub update_time: i64,
     pub update_time_str: String, // 👈 额外加字段
     pub platform: String,
+    pub series_id: Option<i64>,
+    pub category: String,
 }
 
 impl From<Ani> for AniDto {
@@ -60,10 +67,110 @@ impl From<Ani> for AniDto {
             update_time: a.update_time,
             update_time_str: format_timestamp_millis(a.update_time), // 👈 格式化后的字符串
             platform: a.platform,
+            series_id: a.series_id,
+            category: a.category,
         }
     }
 }
 
+/// 番剧（系列），关联同一部番剧在各平台的更新
+#[derive(Debug, Clone, FromRow, PartialEq, Deserialize, Serialize)]
+pub struct Series {
+    pub id: i64,
+    pub title: String,
+    pub normalized_title: String,
+    pub image_url: String,
+    pub created_time: i64,
+}
+
+/// 通知投递记录
+#[derive(Debug, Clone, FromRow, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotifyDelivery {
+    pub id: i64,
+    pub channel: String,
+    pub ani_item_id: Option<i64>,
+    pub title: String,
+    /// ok / failed
+    pub status: String,
+    pub attempts: i64,
+    pub error: Option<String>,
+    pub delivered_time: i64,
+}
+
+/// 数据源任务的一次执行记录
+#[derive(Debug, Clone, FromRow, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    pub id: i64,
+    pub task_name: String,
+    pub manual: bool,
+    /// success / failed
+    pub status: String,
+    pub attempts: i64,
+    pub item_count: Option<i64>,
+    pub error: Option<String>,
+    /// 解析告警，每行一条
+    pub warnings: Option<String>,
+    pub started_time: i64,
+    pub finished_time: i64,
+}
+
+/// 数据源，启动时由配置文件初始化，可在应用中增删改
+#[derive(Debug, Clone, FromRow, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataSourceRecord {
+    pub id: i64,
+    pub name: String,
+    pub category: String,
+    pub url: String,
+    pub cmd: String,
+    pub cron_expr: String,
+    pub retry_times: i64,
+    /// 重试策略（JSON），为空时使用默认策略
+    #[serde(skip)]
+    pub retry_policy: Option<String>,
+    pub enabled: bool,
+    /// 是否跟随配置文件更新，在应用中修改过后为 false
+    pub managed: bool,
+    pub created_time: i64,
+    pub updated_time: i64,
+}
+
+/// 数据源健康状况，由最近的执行记录汇总
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskHealth {
+    pub task_name: String,
+    /// ok / degraded / failing / unknown
+    pub status: String,
+    pub last_run_time: Option<i64>,
+    pub last_success_time: Option<i64>,
+    /// 最近连续失败次数
+    pub consecutive_failures: i64,
+    /// 最近成功执行的平均更新数
+    pub avg_items: Option<f64>,
+    pub last_item_count: Option<i64>,
+    pub last_error: Option<String>,
+    pub last_warnings: Vec<String>,
+}
+
+/// 关注番剧的分集观看进度
+#[derive(Debug, Clone, FromRow, PartialEq, Deserialize, Serialize)]
+pub struct SeriesProgress {
+    pub series_id: i64,
+    pub title: String,
+    pub image_url: String,
+    /// 看过的最大集数，未看过为 0
+    pub last_watched_episode: i64,
+    /// 看过的集数
+    pub watched_count: i64,
+    /// 已播出的最新集数
+    pub latest_episode: i64,
+    /// 落后的集数
+    pub behind: i64,
+}
+
 #[derive(Debug, Clone, FromRow, PartialEq, Deserialize, Serialize)]
 pub struct AniColl {
     pub user_id: String,
@@ -0,0 +1,325 @@
+use crate::db::common::run_query;
+use crate::db::po::SeriesProgress;
+use anyhow::{anyhow, Context, Result};
+use sqlx::SqlitePool;
+
+/// 单次标记的最大集数范围，避免误操作写入过多记录
+pub const MAX_EPISODE_RANGE: i64 = 5000;
+
+/// 校验集数范围 [from, to]
+fn check_range(from: i64, to: i64) -> Result<()> {
+    if from < 1 || to < from {
+        return Err(anyhow!("集数范围无效：{from} ~ {to}"));
+    }
+    if to - from >= MAX_EPISODE_RANGE {
+        return Err(anyhow!("一次最多标记 {MAX_EPISODE_RANGE} 集"));
+    }
+    Ok(())
+}
+
+/// 把番剧第 from ~ to 集标记为已看，返回新增的记录数
+pub async fn mark_episodes_watched(
+    pool: &SqlitePool,
+    user_id: &str,
+    series_id: i64,
+    from: i64,
+    to: i64,
+    watched_time: i64,
+) -> Result<u64> {
+    check_range(from, to)?;
+    let res = sqlx::query(
+        r#"
+                WITH RECURSIVE ep(n) AS (
+                    SELECT ?
+                    UNION ALL
+                    SELECT n + 1 FROM ep WHERE n < ?
+                )
+                INSERT OR IGNORE INTO episode_watch (user_id, series_id, episode, watched_time)
+                SELECT ?, ?, n, ? FROM ep
+            "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(user_id)
+    .bind(series_id)
+    .bind(watched_time)
+    .execute(pool)
+    .await
+    .context(format!(
+        "标记番剧 series_id={series_id} 第 {from} ~ {to} 集为已看失败"
+    ))?;
+    Ok(res.rows_affected())
+}
+
+/// 取消番剧第 from ~ to 集的已看标记，对应更新的观看历史一并删除，返回删除的分集记录数
+///
+/// `aired_before` 之前（不含）播出的最新一集被取消时，关注记录才重置为未看
+pub async fn unmark_episodes_watched(
+    pool: &SqlitePool,
+    user_id: &str,
+    series_id: i64,
+    from: i64,
+    to: i64,
+    aired_before: i64,
+) -> Result<u64> {
+    check_range(from, to)?;
+    let mut tx = pool.begin().await.context("开启事务失败")?;
+
+    let res = sqlx::query(
+        r#"
+                DELETE FROM episode_watch
+                WHERE user_id = ? AND series_id = ? AND episode BETWEEN ? AND ?
+            "#,
+    )
+    .bind(user_id)
+    .bind(series_id)
+    .bind(from)
+    .bind(to)
+    .execute(&mut *tx)
+    .await
+    .context(format!(
+        "取消番剧 series_id={series_id} 第 {from} ~ {to} 集的已看标记失败"
+    ))?;
+
+    sqlx::query(
+        r#"
+                DELETE FROM ani_watch_history
+                WHERE user_id = ?
+                  AND ani_item_id IN (
+                      SELECT id FROM ani_info
+                      WHERE series_id = ?
+                        AND update_count GLOB '[0-9]*'
+                        AND CAST(update_count AS INTEGER) BETWEEN ? AND ?
+                  )
+            "#,
+    )
+    .bind(user_id)
+    .bind(series_id)
+    .bind(from)
+    .bind(to)
+    .execute(&mut *tx)
+    .await
+    .context(format!("删除番剧 series_id={series_id} 的观看历史失败"))?;
+
+    // 取消的范围包含已播出的最新一集时，关注记录的已看标记随之重置
+    sqlx::query(
+        r#"
+                UPDATE ani_collect
+                SET is_watched = 0
+                WHERE user_id = ?
+                  AND series_id = ?
+                  AND (
+                      SELECT MAX(CAST(update_count AS INTEGER))
+                      FROM ani_info
+                      WHERE series_id = ?
+                        AND update_time < ?
+                        AND update_count GLOB '[0-9]*'
+                  ) BETWEEN ? AND ?
+            "#,
+    )
+    .bind(user_id)
+    .bind(series_id)
+    .bind(series_id)
+    .bind(aired_before)
+    .bind(from)
+    .bind(to)
+    .execute(&mut *tx)
+    .await
+    .context(format!("重置番剧 series_id={series_id} 的已看标记失败"))?;
+
+    tx.commit().await.context("提交事务失败")?;
+    Ok(res.rows_affected())
+}
+
+/// 查询所有关注番剧的观看进度
+///
+/// `aired_before` 之前（不含）的更新才计入最新集数，避免把即将更新的剧集算作落后
+pub async fn list_series_progress(
+    pool: &SqlitePool,
+    user_id: &str,
+    aired_before: i64,
+) -> Result<Vec<SeriesProgress>> {
+    let query = sqlx::query_as::<_, SeriesProgress>(
+        r#"
+                SELECT s.id AS series_id,
+                       s.title,
+                       s.image_url,
+                       COALESCE(w.last_watched_episode, 0) AS last_watched_episode,
+                       COALESCE(w.watched_count, 0)        AS watched_count,
+                       COALESCE(l.latest_episode, 0)       AS latest_episode,
+                       MAX(COALESCE(l.latest_episode, 0) - COALESCE(w.last_watched_episode, 0), 0)
+                                                           AS behind
+                FROM series s
+                         LEFT JOIN (
+                    SELECT series_id,
+                           MAX(episode) AS last_watched_episode,
+                           COUNT(*)     AS watched_count
+                    FROM episode_watch
+                    WHERE user_id = ?
+                    GROUP BY series_id
+                ) w ON w.series_id = s.id
+                         LEFT JOIN (
+                    SELECT series_id,
+                           MAX(CAST(update_count AS INTEGER)) AS latest_episode
+                    FROM ani_info
+                    WHERE update_time < ?
+                      AND update_count GLOB '[0-9]*'
+                    GROUP BY series_id
+                ) l ON l.series_id = s.id
+                WHERE s.id IN (SELECT series_id FROM ani_collect WHERE user_id = ?)
+                ORDER BY behind DESC, s.id
+            "#,
+    )
+    .bind(user_id)
+    .bind(aired_before)
+    .bind(user_id);
+    let list = run_query(pool, query).await?;
+    Ok(list)
+}
+
+/// 根据已有的观看历史回填分集观看记录，返回新增的记录数
+pub async fn backfill_episode_watch(pool: &SqlitePool) -> Result<u64> {
+    let res = sqlx::query(
+        r#"
+                INSERT OR IGNORE INTO episode_watch (user_id, series_id, episode, watched_time)
+                SELECT awh.user_id, ai.series_id, CAST(ai.update_count AS INTEGER), awh.watched_time
+                FROM ani_watch_history awh
+                         INNER JOIN ani_info ai
+                         ON ai.id = awh.ani_item_id
+                WHERE ai.series_id IS NOT NULL
+                  AND ai.update_count GLOB '[0-9]*'
+            "#,
+    )
+    .execute(pool)
+    .await
+    .context("回填分集观看记录失败")?;
+    Ok(res.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures::{fanren_item as item, now};
+    use crate::db::po::{AniColl, AniWatch};
+    use crate::db::sqlite::{
+        list_all_follow_ani_update_today, upsert_ani_collect, upsert_ani_info,
+        upsert_ani_watch_history, MIGRATOR,
+    };
+    use crate::utils::date_utils::{parse_date_to_millis, DAY_MILLIS};
+
+    async fn init_pool() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        upsert_ani_info(&pool, &item("151", "2025/07/06"), now())
+            .await
+            .unwrap();
+        upsert_ani_info(&pool, &item("152", "2025/07/13"), now())
+            .await
+            .unwrap();
+        // 即将更新的一集
+        upsert_ani_info(&pool, &item("153", "2025/07/20"), now())
+            .await
+            .unwrap();
+        upsert_ani_collect(
+            &pool,
+            &AniColl {
+                user_id: "".to_string(),
+                ani_item_id: 1,
+                ani_title: "凡人修仙传".to_string(),
+                collect_time: "2025/07/06".to_string(),
+                is_watched: false,
+            },
+        )
+        .await
+        .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_series_progress() {
+        let pool = init_pool().await;
+        let today = parse_date_to_millis("2025/07/13", true).unwrap();
+        let tomorrow = today + DAY_MILLIS;
+
+        // 还没看过：落后 152 集（不计即将更新的 153）
+        let list = list_series_progress(&pool, "", tomorrow).await.unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].latest_episode, 152);
+        assert_eq!(list[0].last_watched_episode, 0);
+        assert_eq!(list[0].behind, 152);
+
+        // 观看第 151 集的更新，触发器同步记录分集进度
+        upsert_ani_watch_history(
+            &pool,
+            &AniWatch {
+                user_id: "".to_string(),
+                ani_item_id: 1,
+                watched_time: today,
+            },
+        )
+        .await
+        .unwrap();
+        let list = list_series_progress(&pool, "", tomorrow).await.unwrap();
+        assert_eq!(list[0].last_watched_episode, 151);
+        assert_eq!(list[0].watched_count, 1);
+        assert_eq!(list[0].behind, 1);
+
+        // 批量标记 1 ~ 152 集
+        let series_id = list[0].series_id;
+        let added = mark_episodes_watched(&pool, "", series_id, 1, 152, today)
+            .await
+            .unwrap();
+        assert_eq!(added, 151);
+        let list = list_series_progress(&pool, "", tomorrow).await.unwrap();
+        assert_eq!(list[0].behind, 0);
+        assert_eq!(list[0].watched_count, 152);
+        // 今日更新的第 152 集已看，不再出现在关注更新中
+        assert!(list_all_follow_ani_update_today(&pool, today)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let is_watched =
+            || sqlx::query_scalar::<_, bool>("SELECT is_watched FROM ani_collect").fetch_one(&pool);
+        assert!(is_watched().await.unwrap());
+
+        // 取消较早的集数，最新一集仍是已看，关注记录保持已看
+        let removed = unmark_episodes_watched(&pool, "", series_id, 1, 10, tomorrow)
+            .await
+            .unwrap();
+        assert_eq!(removed, 10);
+        assert!(is_watched().await.unwrap());
+
+        // 取消第 151 ~ 152 集，包含已播出的最新一集，关注记录重置为未看
+        let removed = unmark_episodes_watched(&pool, "", series_id, 151, 152, tomorrow)
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert!(!is_watched().await.unwrap());
+        let list = list_series_progress(&pool, "", tomorrow).await.unwrap();
+        assert_eq!(list[0].last_watched_episode, 150);
+        assert_eq!(list[0].behind, 2);
+        assert_eq!(
+            list_all_follow_ani_update_today(&pool, today)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_episode_range() {
+        let pool = init_pool().await;
+        assert!(mark_episodes_watched(&pool, "", 1, 0, 3, 0).await.is_err());
+        assert!(mark_episodes_watched(&pool, "", 1, 5, 3, 0).await.is_err());
+        assert!(
+            mark_episodes_watched(&pool, "", 1, 1, MAX_EPISODE_RANGE + 1, 0)
+                .await
+                .is_err()
+        );
+        assert!(unmark_episodes_watched(&pool, "", 1, 3, 2, 0)
+            .await
+            .is_err());
+    }
+}
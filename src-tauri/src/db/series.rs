@@ -0,0 +1,972 @@
+use crate::db::common::run_query;
+use crate::db::po::{Ani, Series};
+use crate::types::AniItem;
+use crate::utils::title::{base_title, normalize_title, title_rules_fingerprint};
+use anyhow::{Context, Result};
+use log::info;
+use sqlx::{Executor, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::{BTreeMap, HashMap};
+
+/// app_meta 中记录标题规则指纹的键
+const TITLE_RULES_KEY: &str = "title_rules";
+
+/// 为一条更新记录找到所属的 series，找不到则新建
+///
+/// 匹配顺序：平台外部 ID -> 归一化标题/别名；匹配后顺带记录外部 ID 与新的标题写法
+pub async fn match_series(pool: &SqlitePool, item: &AniItem, seen_time: i64) -> Result<i64> {
+    let normalized = normalize_title(&item.title);
+
+    // 1. 按平台外部 ID 匹配
+    let mut series_id = if item.external_id.is_empty() {
+        None
+    } else {
+        find_series_by_external_id(pool, &item.platform, &item.external_id).await?
+    };
+
+    // 2. 按标题和别名匹配
+    if series_id.is_none() {
+        series_id = find_series_by_title(pool, &normalized).await?;
+    }
+
+    // 3. 都没有则新建
+    let series_id = match series_id {
+        Some(id) => id,
+        None => {
+            let mut conn = pool.acquire().await.context("获取数据库连接失败")?;
+            let id = insert_series(
+                &mut conn,
+                &item.title,
+                &normalized,
+                &item.image_url,
+                seen_time,
+            )
+            .await?;
+            info!("新建番剧：{}（series_id={id}）", item.title);
+            id
+        }
+    };
+
+    // 4. 记录外部 ID 和标题别名
+    if !item.external_id.is_empty() {
+        add_series_platform(pool, series_id, &item.platform, &item.external_id).await?;
+    }
+    add_series_alias(pool, series_id, &item.title).await?;
+
+    Ok(series_id)
+}
+
+/// 按平台外部 ID 查找 series
+pub async fn find_series_by_external_id(
+    pool: &SqlitePool,
+    platform: &str,
+    external_id: &str,
+) -> Result<Option<i64>> {
+    let id = sqlx::query_scalar::<_, i64>(
+        r#"SELECT series_id FROM series_platform WHERE platform = ? AND external_id = ?"#,
+    )
+    .bind(platform)
+    .bind(external_id)
+    .fetch_optional(pool)
+    .await
+    .context(format!("按外部 ID 查询番剧 {platform}:{external_id} 失败"))?;
+    Ok(id)
+}
+
+/// 按归一化标题查找 series（同时匹配标题和别名）
+pub async fn find_series_by_title(pool: &SqlitePool, normalized: &str) -> Result<Option<i64>> {
+    let id = sqlx::query_scalar::<_, i64>(
+        r#"
+                SELECT id FROM series WHERE normalized_title = ?
+                UNION ALL
+                SELECT series_id FROM series_alias WHERE normalized_alias = ?
+                LIMIT 1
+            "#,
+    )
+    .bind(normalized)
+    .bind(normalized)
+    .fetch_optional(pool)
+    .await
+    .context(format!("按标题查询番剧 {normalized} 失败"))?;
+    Ok(id)
+}
+
+/// 新建 series，标题已存在时返回已有的 ID
+async fn insert_series(
+    conn: &mut SqliteConnection,
+    title: &str,
+    normalized: &str,
+    image_url: &str,
+    created_time: i64,
+) -> Result<i64> {
+    sqlx::query(
+        r#"
+                INSERT INTO series (title, normalized_title, base_title, image_url, created_time)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(normalized_title) DO NOTHING
+            "#,
+    )
+    .bind(title)
+    .bind(normalized)
+    .bind(base_title(title))
+    .bind(image_url)
+    .bind(created_time)
+    .execute(&mut *conn)
+    .await
+    .context(format!("新建番剧 {title} 失败"))?;
+
+    let id = sqlx::query_scalar::<_, i64>(r#"SELECT id FROM series WHERE normalized_title = ?"#)
+        .bind(normalized)
+        .fetch_one(&mut *conn)
+        .await
+        .context(format!("查询番剧 {title} 失败"))?;
+    Ok(id)
+}
+
+/// 为 series 添加别名，与标题相同或已被占用的别名会被忽略
+pub async fn add_series_alias<'e, E>(executor: E, series_id: i64, alias: &str) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let normalized = normalize_title(alias);
+    sqlx::query(
+        r#"
+                INSERT OR IGNORE INTO series_alias (series_id, alias, normalized_alias)
+                SELECT ?, ?, ?
+                WHERE NOT EXISTS (SELECT 1 FROM series WHERE normalized_title = ?)
+            "#,
+    )
+    .bind(series_id)
+    .bind(alias)
+    .bind(&normalized)
+    .bind(&normalized)
+    .execute(executor)
+    .await
+    .context(format!("添加番剧别名 {alias} 失败"))?;
+    Ok(())
+}
+
+/// 记录 series 在某个平台上的外部 ID
+async fn add_series_platform(
+    pool: &SqlitePool,
+    series_id: i64,
+    platform: &str,
+    external_id: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+                INSERT OR IGNORE INTO series_platform (series_id, platform, external_id)
+                VALUES (?, ?, ?)
+            "#,
+    )
+    .bind(series_id)
+    .bind(platform)
+    .bind(external_id)
+    .execute(pool)
+    .await
+    .context(format!("记录番剧外部 ID {platform}:{external_id} 失败"))?;
+    Ok(())
+}
+
+/// 根据 ID 查询 series
+pub async fn get_series_by_id(pool: &SqlitePool, id: i64) -> Result<Series> {
+    let series = sqlx::query_as::<_, Series>(
+        r#"SELECT id, title, normalized_title, image_url, created_time FROM series WHERE id = ?"#,
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await
+    .context(format!("查询番剧 id={id} 失败"))?;
+    Ok(series)
+}
+
+/// 为尚未关联 series 的历史数据补齐关联，返回补齐的更新记录数
+pub async fn backfill_series(pool: &SqlitePool) -> Result<u64> {
+    let query = sqlx::query_as::<_, Ani>(
+        r#"
+                SELECT id,
+                       title,
+                       update_count,
+                       update_info,
+                       image_url,
+                       detail_url,
+                       update_time,
+                       platform
+                FROM ani_info
+                WHERE series_id IS NULL
+                ORDER BY id
+            "#,
+    );
+    let list = run_query(pool, query).await?;
+
+    for ani in &list {
+        let item = AniItem {
+            title: ani.title.clone(),
+            update_count: ani.update_count.clone(),
+            update_info: ani.update_info.clone(),
+            image_url: ani.image_url.clone(),
+            detail_url: ani.detail_url.clone(),
+            update_time: String::new(),
+            platform: ani.platform.clone(),
+            external_id: String::new(),
+            category: String::new(),
+        };
+        let series_id = match_series(pool, &item, ani.update_time).await?;
+        sqlx::query(r#"UPDATE ani_info SET series_id = ? WHERE id = ?"#)
+            .bind(series_id)
+            .bind(ani.id)
+            .execute(pool)
+            .await
+            .context(format!("回填 ani_info id={} 的番剧失败", ani.id))?;
+    }
+
+    sqlx::query(
+        r#"
+                UPDATE ani_collect
+                SET series_id = (SELECT series_id FROM ani_info WHERE id = ani_collect.ani_item_id)
+                WHERE series_id IS NULL
+            "#,
+    )
+    .execute(pool)
+    .await
+    .context("回填 ani_collect 的番剧失败")?;
+
+    if !list.is_empty() {
+        info!("已为 {} 条历史更新记录关联番剧", list.len());
+    }
+    Ok(list.len() as u64)
+}
+
+/// 把 `from` 合并到 `into`：更新记录、关注记录、外部 ID 和别名都转到 `into` 名下
+///
+/// 在调用方的事务中执行，合并不可撤销
+pub async fn merge_series(conn: &mut SqliteConnection, from: i64, into: i64) -> Result<()> {
+    if from == into {
+        return Ok(());
+    }
+    let from_title = sqlx::query_scalar::<_, String>(r#"SELECT title FROM series WHERE id = ?"#)
+        .bind(from)
+        .fetch_one(&mut *conn)
+        .await
+        .context(format!("查询番剧 id={from} 失败"))?;
+
+    for sql in [
+        r#"UPDATE ani_info SET series_id = ? WHERE series_id = ?"#,
+        r#"UPDATE ani_collect SET series_id = ? WHERE series_id = ?"#,
+        r#"UPDATE OR IGNORE series_platform SET series_id = ? WHERE series_id = ?"#,
+        r#"UPDATE OR IGNORE series_alias SET series_id = ? WHERE series_id = ?"#,
+        r#"UPDATE OR IGNORE episode_watch SET series_id = ? WHERE series_id = ?"#,
+        r#"UPDATE OR IGNORE ani_notification SET series_id = ? WHERE series_id = ?"#,
+    ] {
+        sqlx::query(sql)
+            .bind(into)
+            .bind(from)
+            .execute(&mut *conn)
+            .await
+            .context(format!("合并番剧 {from} -> {into} 失败"))?;
+    }
+    sqlx::query(r#"DELETE FROM series WHERE id = ?"#)
+        .bind(from)
+        .execute(&mut *conn)
+        .await
+        .context(format!("删除番剧 id={from} 失败"))?;
+
+    // 原标题作为别名保留
+    add_series_alias(&mut *conn, into, &from_title).await?;
+    info!("已合并番剧：{from_title}（{from}） -> {into}");
+    Ok(())
+}
+
+/// 标题规则（归一化版本、别名规则）与上次重建时不同才重建匹配键，返回合并的番剧数量
+pub async fn renormalize_series_if_changed(pool: &SqlitePool) -> Result<u64> {
+    let stored = sqlx::query_scalar::<_, String>(r#"SELECT value FROM app_meta WHERE key = ?"#)
+        .bind(TITLE_RULES_KEY)
+        .fetch_optional(pool)
+        .await
+        .context("查询标题规则版本失败")?;
+    if stored.as_deref() == Some(title_rules_fingerprint().as_str()) {
+        return Ok(0);
+    }
+    renormalize_series(pool).await
+}
+
+/// 按当前的归一化规则（含别名规则）重新计算所有标题键，归一化后相同的番剧会被合并
+///
+/// 整个重建在一个事务中完成，中途出错时不会留下缺失别名的数据；返回合并的番剧数量
+pub async fn renormalize_series(pool: &SqlitePool) -> Result<u64> {
+    let fingerprint = title_rules_fingerprint();
+    let mut tx = pool.begin().await.context("开启事务失败")?;
+    let mut merged = 0;
+
+    // 1. 标题与别名的全部写法，按 series 的先后顺序处理，合并时保留较早的 series
+    let titles = sqlx::query_as::<_, (i64, String)>(
+        r#"
+                SELECT id, title FROM series
+                UNION ALL
+                SELECT series_id, alias FROM series_alias
+                ORDER BY 1
+            "#,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("查询番剧标题失败")?;
+
+    // 2. 同一个键出现在不同番剧下时，把后出现的番剧合并到先出现的番剧
+    let mut owners: HashMap<String, i64> = HashMap::new();
+    let mut merged_into: HashMap<i64, i64> = HashMap::new();
+    let resolve = |merged_into: &HashMap<i64, i64>, mut id: i64| {
+        while let Some(&next) = merged_into.get(&id) {
+            id = next;
+        }
+        id
+    };
+    for (series_id, title) in titles {
+        let current = resolve(&merged_into, series_id);
+        let key = normalize_title(&title);
+        match owners.get(&key).map(|&owner| resolve(&merged_into, owner)) {
+            Some(owner) if owner != current => {
+                merge_series(&mut tx, current, owner).await?;
+                merged_into.insert(current, owner);
+                merged += 1;
+            }
+            Some(_) => {}
+            None => {
+                owners.insert(key, current);
+            }
+        }
+    }
+
+    // 3. 写回新的标题键，并重建别名键
+    let series = sqlx::query_as::<_, (i64, String)>(r#"SELECT id, title FROM series"#)
+        .fetch_all(&mut *tx)
+        .await
+        .context("查询番剧失败")?;
+    let aliases =
+        sqlx::query_as::<_, (i64, String)>(r#"SELECT series_id, alias FROM series_alias"#)
+            .fetch_all(&mut *tx)
+            .await
+            .context("查询番剧别名失败")?;
+    // 先写入临时键，避免更新过程中触发唯一约束
+    sqlx::query(r#"UPDATE series SET normalized_title = '#' || id"#)
+        .execute(&mut *tx)
+        .await
+        .context("重置番剧标题键失败")?;
+    sqlx::query(r#"DELETE FROM series_alias"#)
+        .execute(&mut *tx)
+        .await
+        .context("重置番剧别名失败")?;
+    for (id, title) in &series {
+        sqlx::query(
+            r#"UPDATE OR IGNORE series SET normalized_title = ?, base_title = ? WHERE id = ?"#,
+        )
+        .bind(normalize_title(title))
+        .bind(base_title(title))
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .context(format!("更新番剧 {title} 的标题键失败"))?;
+    }
+    for (series_id, alias) in &aliases {
+        add_series_alias(&mut *tx, *series_id, alias).await?;
+    }
+
+    // 4. 旧规则把不同季归到了同一部番剧，按季拆开
+    split_seasons(&mut tx).await?;
+
+    // 5. 关注记录的标题键，同一标题只算一次，键没变的不更新
+    let collects = sqlx::query_as::<_, (String, Option<String>, Option<String>)>(
+        r#"SELECT DISTINCT ani_title, normalized_title, base_title FROM ani_collect"#,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("查询关注记录失败")?;
+    for (title, stored, stored_base) in &collects {
+        let key = normalize_title(title);
+        let base = base_title(title);
+        if stored.as_deref() == Some(key.as_str()) && stored_base.as_deref() == Some(base.as_str())
+        {
+            continue;
+        }
+        sqlx::query(
+            r#"UPDATE ani_collect SET normalized_title = ?, base_title = ? WHERE ani_title = ?"#,
+        )
+        .bind(&key)
+        .bind(&base)
+        .bind(title)
+        .execute(&mut *tx)
+        .await
+        .context(format!("更新关注记录 {title} 的标题键失败"))?;
+    }
+
+    // 6. 记录本次使用的标题规则
+    sqlx::query(
+        r#"
+                INSERT INTO app_meta (key, value) VALUES (?, ?)
+                ON CONFLICT(key) DO UPDATE SET value = excluded.value
+            "#,
+    )
+    .bind(TITLE_RULES_KEY)
+    .bind(&fingerprint)
+    .execute(&mut *tx)
+    .await
+    .context("保存标题规则版本失败")?;
+    tx.commit().await.context("提交事务失败")?;
+
+    if merged > 0 {
+        info!("按新的标题规则合并了 {merged} 部番剧");
+    }
+    Ok(merged)
+}
+
+/// 需要从原番剧拆出的一季
+struct SplitSeason {
+    title: String,
+    image_url: String,
+    created_time: i64,
+    ani_ids: Vec<i64>,
+}
+
+/// 拆开被合并到同一部番剧下的不同季：更新记录的标题键与番剧不同、只差季度后缀时，转到该季自己的番剧
+///
+/// 需在标题键重建之后调用；被拆分的番剧的外部 ID 无法区分属于哪一季，会被清除，下次抓取时按标题重新记录
+async fn split_seasons(conn: &mut SqliteConnection) -> Result<u64> {
+    let rows = sqlx::query_as::<_, (i64, String, i64, String, String, i64)>(
+        r#"
+                SELECT s.id, s.title, ai.id, ai.title, ai.image_url, ai.update_time
+                FROM ani_info ai
+                         INNER JOIN series s
+                         ON s.id = ai.series_id
+                ORDER BY ai.id
+            "#,
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .context("查询更新记录失败")?;
+
+    // (原番剧, 该季的标题键) -> 该季
+    let mut seasons: BTreeMap<(i64, String), SplitSeason> = BTreeMap::new();
+    for (series_id, series_title, ani_id, title, image_url, update_time) in rows {
+        let key = normalize_title(&title);
+        if key == normalize_title(&series_title) || base_title(&title) != base_title(&series_title)
+        {
+            continue;
+        }
+        let season = seasons
+            .entry((series_id, key))
+            .or_insert_with(|| SplitSeason {
+                title,
+                image_url,
+                created_time: update_time,
+                ani_ids: vec![],
+            });
+        season.created_time = season.created_time.min(update_time);
+        season.ani_ids.push(ani_id);
+    }
+
+    for ((from, key), season) in &seasons {
+        let SplitSeason {
+            title,
+            image_url,
+            created_time,
+            ani_ids,
+        } = season;
+        // 该季的别名不再属于原番剧
+        sqlx::query(r#"DELETE FROM series_alias WHERE normalized_alias = ?"#)
+            .bind(key)
+            .execute(&mut *conn)
+            .await
+            .context(format!("删除番剧别名 {title} 失败"))?;
+        let into = insert_series(&mut *conn, title, key, image_url, *created_time).await?;
+        for ani_id in ani_ids {
+            sqlx::query(r#"UPDATE ani_info SET series_id = ? WHERE id = ?"#)
+                .bind(into)
+                .bind(ani_id)
+                .execute(&mut *conn)
+                .await
+                .context(format!("更新 ani_info id={ani_id} 的番剧失败"))?;
+        }
+        // 关注记录跟随所关注的更新记录
+        sqlx::query(
+            r#"
+                UPDATE ani_collect
+                SET series_id = (SELECT series_id FROM ani_info WHERE id = ani_collect.ani_item_id)
+                WHERE series_id = ?
+            "#,
+        )
+        .bind(from)
+        .execute(&mut *conn)
+        .await
+        .context(format!("拆分番剧 {title} 的关注记录失败"))?;
+        // 看过的集数按观看历史转到新番剧，原番剧中只由这一季的观看产生的记录随之删除
+        sqlx::query(
+            r#"
+                INSERT OR IGNORE INTO episode_watch (user_id, series_id, episode, watched_time)
+                SELECT awh.user_id, ai.series_id, CAST(ai.update_count AS INTEGER), awh.watched_time
+                FROM ani_watch_history awh
+                         INNER JOIN ani_info ai
+                         ON ai.id = awh.ani_item_id
+                WHERE ai.series_id = ?
+                  AND ai.update_count GLOB '[0-9]*'
+            "#,
+        )
+        .bind(into)
+        .execute(&mut *conn)
+        .await
+        .context(format!("拆分番剧 {title} 的观看记录失败"))?;
+        sqlx::query(
+            r#"
+                DELETE FROM episode_watch
+                WHERE series_id = ?
+                  AND episode IN (SELECT CAST(ai.update_count AS INTEGER)
+                                  FROM ani_watch_history awh
+                                           INNER JOIN ani_info ai ON ai.id = awh.ani_item_id
+                                  WHERE ai.series_id = ? AND ai.update_count GLOB '[0-9]*')
+                  AND episode NOT IN (SELECT CAST(ai.update_count AS INTEGER)
+                                      FROM ani_watch_history awh
+                                               INNER JOIN ani_info ai ON ai.id = awh.ani_item_id
+                                      WHERE ai.series_id = ? AND ai.update_count GLOB '[0-9]*')
+            "#,
+        )
+        .bind(from)
+        .bind(into)
+        .bind(from)
+        .execute(&mut *conn)
+        .await
+        .context(format!("拆分番剧 {title} 的观看记录失败"))?;
+        sqlx::query(r#"DELETE FROM series_platform WHERE series_id = ?"#)
+            .bind(from)
+            .execute(&mut *conn)
+            .await
+            .context(format!("清除番剧 id={from} 的外部 ID 失败"))?;
+        info!("已拆分番剧：{title} 从 {from} 转到 {into}");
+    }
+    Ok(seasons.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures::now;
+    use crate::db::po::{AniColl, AniWatch};
+    use crate::db::sqlite::{
+        list_all_ani_update_today, list_all_follow_ani_update_today, upsert_ani_collect,
+        upsert_ani_info, upsert_ani_watch_history, MIGRATOR,
+    };
+    use crate::utils::date_utils::parse_date_to_millis;
+
+    fn item(title: &str, platform: &str, external_id: &str, count: &str) -> AniItem {
+        AniItem {
+            title: title.to_string(),
+            update_count: count.to_string(),
+            update_info: format!("更新至{count}集"),
+            image_url: format!("https://img.example.com/{platform}.jpg"),
+            detail_url: format!("https://{platform}.example.com/{external_id}"),
+            update_time: "2025/07/13".to_string(),
+            platform: platform.to_string(),
+            external_id: external_id.to_string(),
+            category: String::new(),
+        }
+    }
+
+    fn collect(ani_item_id: i64, title: &str) -> AniColl {
+        AniColl {
+            user_id: "".to_string(),
+            ani_item_id,
+            ani_title: title.to_string(),
+            collect_time: "2025/07/13".to_string(),
+            is_watched: false,
+        }
+    }
+
+    fn watch(ani_item_id: i64, watched_time: i64) -> AniWatch {
+        AniWatch {
+            user_id: "".to_string(),
+            ani_item_id,
+            watched_time,
+        }
+    }
+
+    async fn init_pool() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        pool
+    }
+
+    async fn series_of(pool: &SqlitePool, title: &str, platform: &str) -> Option<i64> {
+        sqlx::query_scalar(r#"SELECT series_id FROM ani_info WHERE title = ? AND platform = ?"#)
+            .bind(title)
+            .bind(platform)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_match_series_across_platforms() {
+        let pool = init_pool().await;
+
+        // 不同平台、空白写法不同的同一部番剧归到同一 series
+        upsert_ani_info(
+            &pool,
+            &item("凡人修仙传", "bilibili", "28747", "152"),
+            now(),
+        )
+        .await
+        .unwrap();
+        upsert_ani_info(
+            &pool,
+            &item("凡人 修仙传", "tencent", "mzc001", "152"),
+            now(),
+        )
+        .await
+        .unwrap();
+        let bilibili = series_of(&pool, "凡人修仙传", "bilibili").await.unwrap();
+        let tencent = series_of(&pool, "凡人 修仙传", "tencent").await.unwrap();
+        assert_eq!(bilibili, tencent);
+        assert_eq!(
+            get_series_by_id(&pool, bilibili).await.unwrap().title,
+            "凡人修仙传"
+        );
+
+        // 平台改了标题，但外部 ID 不变，仍然归到同一 series，新标题记为别名
+        upsert_ani_info(
+            &pool,
+            &item("凡人修仙传 年番", "bilibili", "28747", "153"),
+            now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            series_of(&pool, "凡人修仙传 年番", "bilibili").await,
+            Some(bilibili)
+        );
+        assert_eq!(
+            find_series_by_title(&pool, &normalize_title("凡人修仙传年番"))
+                .await
+                .unwrap(),
+            Some(bilibili)
+        );
+
+        // 同日同集在两个平台更新，今日列表只保留一条
+        let today_ts = parse_date_to_millis("2025/07/13", true).unwrap();
+        let today = list_all_ani_update_today(&pool, today_ts).await.unwrap();
+        assert_eq!(today.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_follow_and_watch_by_series() {
+        let pool = init_pool().await;
+        upsert_ani_info(
+            &pool,
+            &item("凡人修仙传", "bilibili", "28747", "152"),
+            now(),
+        )
+        .await
+        .unwrap();
+        upsert_ani_info(
+            &pool,
+            &item("凡人 修仙传", "tencent", "mzc001", "152"),
+            now(),
+        )
+        .await
+        .unwrap();
+        let today_ts = parse_date_to_millis("2025/07/13", true).unwrap();
+
+        // 在 B 站的条目上关注，腾讯的同一集也能匹配到，但只返回一条
+        upsert_ani_collect(
+            &pool,
+            &AniColl {
+                user_id: "".to_string(),
+                ani_item_id: 1,
+                ani_title: "凡人修仙传".to_string(),
+                collect_time: "2025/07/13".to_string(),
+                is_watched: false,
+            },
+        )
+        .await
+        .unwrap();
+        let follow = list_all_follow_ani_update_today(&pool, today_ts)
+            .await
+            .unwrap();
+        assert_eq!(follow.len(), 1);
+
+        // 在腾讯看过后，同一番剧同一集不再出现在关注更新中
+        upsert_ani_watch_history(
+            &pool,
+            &AniWatch {
+                user_id: "".to_string(),
+                ani_item_id: 2,
+                watched_time: today_ts,
+            },
+        )
+        .await
+        .unwrap();
+        let follow = list_all_follow_ani_update_today(&pool, today_ts)
+            .await
+            .unwrap();
+        assert!(follow.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_follow_without_episode_count() {
+        let pool = init_pool().await;
+        // mikanani 没有集数
+        upsert_ani_info(&pool, &item("琉璃的宝石", "mikanani", "3663", ""), now())
+            .await
+            .unwrap();
+        upsert_ani_info(
+            &pool,
+            &AniItem {
+                update_time: "2025/07/20".to_string(),
+                ..item("琉璃的宝石（中配）", "mikanani", "3663", "")
+            },
+            now(),
+        )
+        .await
+        .unwrap();
+        upsert_ani_collect(&pool, &collect(1, "琉璃的宝石"))
+            .await
+            .unwrap();
+        let first_ts = parse_date_to_millis("2025/07/13", true).unwrap();
+        let next_ts = parse_date_to_millis("2025/07/20", true).unwrap();
+
+        // 看过第一次更新后，之后的更新仍然出现
+        upsert_ani_watch_history(&pool, &watch(1, first_ts + 3_600_000))
+            .await
+            .unwrap();
+        assert!(list_all_follow_ani_update_today(&pool, first_ts)
+            .await
+            .unwrap()
+            .is_empty());
+        let follow = list_all_follow_ani_update_today(&pool, next_ts)
+            .await
+            .unwrap();
+        assert_eq!(follow.len(), 1);
+        assert_eq!(follow[0].id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_seasons_are_separate_series() {
+        let pool = init_pool().await;
+        upsert_ani_info(&pool, &item("鬼灭之刃", "bilibili", "1", "5"), now())
+            .await
+            .unwrap();
+        upsert_ani_info(&pool, &item("鬼灭之刃 第二季", "tencent", "2", "5"), now())
+            .await
+            .unwrap();
+        let first = series_of(&pool, "鬼灭之刃", "bilibili").await.unwrap();
+        let second = series_of(&pool, "鬼灭之刃 第二季", "tencent")
+            .await
+            .unwrap();
+        assert_ne!(first, second);
+        let today_ts = parse_date_to_millis("2025/07/13", true).unwrap();
+        assert_eq!(
+            list_all_ani_update_today(&pool, today_ts)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // 关注第一季，第二季的更新也会出现
+        upsert_ani_collect(&pool, &collect(1, "鬼灭之刃"))
+            .await
+            .unwrap();
+        let follow = list_all_follow_ani_update_today(&pool, today_ts)
+            .await
+            .unwrap();
+        assert_eq!(follow.len(), 2);
+
+        // 看过第一季第 5 集，第二季第 5 集仍未看
+        upsert_ani_watch_history(&pool, &watch(1, today_ts))
+            .await
+            .unwrap();
+        let follow = list_all_follow_ani_update_today(&pool, today_ts)
+            .await
+            .unwrap();
+        assert_eq!(follow.len(), 1);
+        assert_eq!(follow[0].series_id, Some(second));
+    }
+
+    #[tokio::test]
+    async fn test_split_seasons() {
+        let pool = init_pool().await;
+        upsert_ani_info(&pool, &item("鬼灭之刃", "bilibili", "1", "5"), now())
+            .await
+            .unwrap();
+        upsert_ani_info(&pool, &item("鬼灭之刃 第二季", "bilibili", "2", "5"), now())
+            .await
+            .unwrap();
+        let first = series_of(&pool, "鬼灭之刃", "bilibili").await.unwrap();
+        let second = series_of(&pool, "鬼灭之刃 第二季", "bilibili")
+            .await
+            .unwrap();
+
+        // 模拟旧规则：第二季被归到第一季名下，并记成了别名
+        for sql in [
+            r#"UPDATE ani_info SET series_id = ?1 WHERE series_id = ?2"#,
+            r#"UPDATE series_platform SET series_id = ?1 WHERE series_id = ?2"#,
+            r#"DELETE FROM series WHERE id = ?2 AND ?1 IS NOT NULL"#,
+            r#"INSERT INTO series_alias (series_id, alias, normalized_alias)
+               VALUES (?1, '鬼灭之刃 第二季', '鬼灭之刃第二季')"#,
+            r#"DELETE FROM app_meta"#,
+        ] {
+            sqlx::query(sql)
+                .bind(first)
+                .bind(second)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        // 在旧数据上关注并看过第二季第 5 集
+        upsert_ani_collect(&pool, &collect(2, "鬼灭之刃 第二季"))
+            .await
+            .unwrap();
+        upsert_ani_watch_history(&pool, &watch(2, 0)).await.unwrap();
+
+        // 重建时按季拆开，关注和观看记录随之转移
+        renormalize_series_if_changed(&pool).await.unwrap();
+        let split = series_of(&pool, "鬼灭之刃 第二季", "bilibili")
+            .await
+            .unwrap();
+        assert_ne!(split, first);
+        assert_eq!(series_of(&pool, "鬼灭之刃", "bilibili").await, Some(first));
+        let collect_series: Option<i64> =
+            sqlx::query_scalar(r#"SELECT series_id FROM ani_collect"#)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(collect_series, Some(split));
+        let watched: Vec<i64> =
+            sqlx::query_scalar(r#"SELECT series_id FROM episode_watch WHERE episode = 5"#)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(watched, vec![split]);
+        assert_eq!(
+            find_series_by_title(&pool, &normalize_title("鬼灭之刃 第二季"))
+                .await
+                .unwrap(),
+            Some(split)
+        );
+        // 无法区分季的外部 ID 被清除
+        assert_eq!(
+            find_series_by_external_id(&pool, "bilibili", "2")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_renormalize_series_merges() {
+        let pool = init_pool().await;
+        upsert_ani_info(
+            &pool,
+            &item("鬥破蒼穹 年番", "bilibili", "28747", "156"),
+            now(),
+        )
+        .await
+        .unwrap();
+        let old = series_of(&pool, "鬥破蒼穹 年番", "bilibili").await.unwrap();
+        // 模拟旧规则下生成的标题键：繁简写法被当成了两部番剧
+        sqlx::query(r#"UPDATE series SET normalized_title = '鬥破蒼穹年番' WHERE id = ?"#)
+            .bind(old)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(r#"DELETE FROM series_alias"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        upsert_ani_info(&pool, &item("斗破苍穹", "tencent", "mzc002", "156"), now())
+            .await
+            .unwrap();
+        let new = series_of(&pool, "斗破苍穹", "tencent").await.unwrap();
+        assert_ne!(old, new);
+
+        // 关注腾讯的条目
+        upsert_ani_collect(
+            &pool,
+            &AniColl {
+                user_id: "".to_string(),
+                ani_item_id: 2,
+                ani_title: "斗破苍穹".to_string(),
+                collect_time: "2025/07/13".to_string(),
+                is_watched: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        // 按新规则重建后合并到较早的番剧，关注记录随之转移
+        assert_eq!(renormalize_series(&pool).await.unwrap(), 1);
+        assert_eq!(series_of(&pool, "斗破苍穹", "tencent").await, Some(old));
+        assert!(get_series_by_id(&pool, new).await.is_err());
+        assert_eq!(
+            get_series_by_id(&pool, old).await.unwrap().normalized_title,
+            "斗破苍穹"
+        );
+        let collect_series: Option<i64> =
+            sqlx::query_scalar(r#"SELECT series_id FROM ani_collect"#)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(collect_series, Some(old));
+        // 外部 ID 仍可匹配
+        assert_eq!(
+            find_series_by_external_id(&pool, "tencent", "mzc002")
+                .await
+                .unwrap(),
+            Some(old)
+        );
+        assert_eq!(renormalize_series(&pool).await.unwrap(), 0);
+
+        // 规则未变时启动不再重建；规则变化（此处删除记录模拟）后才重建
+        sqlx::query(r#"UPDATE series SET normalized_title = '斗破苍穹旧键' WHERE id = ?"#)
+            .bind(old)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(renormalize_series_if_changed(&pool).await.unwrap(), 0);
+        assert_eq!(
+            get_series_by_id(&pool, old).await.unwrap().normalized_title,
+            "斗破苍穹旧键"
+        );
+        add_series_alias(&pool, old, "Battle Through the Heavens")
+            .await
+            .unwrap();
+        sqlx::query(r#"DELETE FROM app_meta"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(renormalize_series_if_changed(&pool).await.unwrap(), 0);
+        assert_eq!(
+            get_series_by_id(&pool, old).await.unwrap().normalized_title,
+            "斗破苍穹"
+        );
+        // 重建后别名仍在
+        assert_eq!(
+            find_series_by_title(&pool, &normalize_title("Battle Through the Heavens"))
+                .await
+                .unwrap(),
+            Some(old)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_series() {
+        let pool = init_pool().await;
+        upsert_ani_info(&pool, &item("琉璃的宝石", "mikanani", "3663", "2"), now())
+            .await
+            .unwrap();
+        // 模拟迁移前的历史数据
+        sqlx::query(r#"UPDATE ani_info SET series_id = NULL"#)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(backfill_series(&pool).await.unwrap(), 1);
+        assert!(series_of(&pool, "琉璃的宝石", "mikanani").await.is_some());
+        assert_eq!(backfill_series(&pool).await.unwrap(), 0);
+    }
+}
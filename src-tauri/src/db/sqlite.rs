@@ -4,8 +4,11 @@ use crate::db::po::AniColl;
 use crate::db::po::AniCollect;
 use crate::db::po::AniHistoryInfo;
 use crate::db::po::{AniWatch, AniWatchHistory};
-use crate::types::AniItem;
-use crate::utils::date_utils::parse_date_to_millis;
+use crate::db::progress::backfill_episode_watch;
+use crate::db::series::{backfill_series, match_series, renormalize_series_if_changed};
+use crate::types::{AniItem, DEFAULT_CATEGORY};
+use crate::utils::date_utils::{parse_date_to_millis, DAY_MILLIS};
+use crate::utils::title::{base_title, normalize_title};
 use anyhow::{Context, Error, Result};
 use log::info;
 use sqlx::migrate::Migrator;
@@ -56,6 +59,15 @@ pub async fn init_and_migrate_db(app: &AppHandle) -> Result<Pool<Sqlite>> {
         .await
         .context("数据库迁移或初始化失败!")?;
 
+    // 为历史数据关联番剧；标题规则变化后按新规则重建匹配键
+    backfill_series(&pool).await.context("回填番剧数据失败")?;
+    renormalize_series_if_changed(&pool)
+        .await
+        .context("重建番剧标题键失败")?;
+    backfill_episode_watch(&pool)
+        .await
+        .context("回填分集观看记录失败")?;
+
     info!("数据库初始化成功");
     Ok(pool)
 }
@@ -100,8 +112,13 @@ pub async fn setup_app_db(app: &mut tauri::App) -> Result<(), Box<dyn std::error
 }
 
 /// 动漫信息插入新记录
-pub async fn upsert_ani_info(pool: &SqlitePool, item: &AniItem) -> Result<()> {
+///
+/// 已保存的记录还未播出（更新时间晚于 now）时，按新抓取的播出日期修正，平台调整排期后能跟上；
+/// 已播出的记录保留原来的更新时间
+pub async fn upsert_ani_info(pool: &SqlitePool, item: &AniItem, now: i64) -> Result<()> {
     let update_time = parse_date_to_millis(&item.update_time, true)?;
+    // 先关联到所属番剧
+    let series_id = match_series(pool, item, update_time).await?;
     let _ = sqlx::query(
         r#"
                     INSERT INTO ani_info (
@@ -111,12 +128,20 @@ pub async fn upsert_ani_info(pool: &SqlitePool, item: &AniItem) -> Result<()> {
                         image_url,
                         detail_url,
                         update_time,
-                        platform
-                    ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                        platform,
+                        series_id,
+                        category
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
                     ON CONFLICT(title, platform, update_count) DO UPDATE SET
                         update_info = excluded.update_info,
+                        update_time = CASE
+                            WHEN ani_info.update_time > ? THEN excluded.update_time
+                            ELSE ani_info.update_time
+                        END,
                         image_url = excluded.image_url,
-                        detail_url = excluded.detail_url
+                        detail_url = excluded.detail_url,
+                        series_id = excluded.series_id,
+                        category = excluded.category
                 "#,
     )
     .bind(&item.title)
@@ -126,6 +151,13 @@ pub async fn upsert_ani_info(pool: &SqlitePool, item: &AniItem) -> Result<()> {
     .bind(&item.detail_url)
     .bind(update_time)
     .bind(&item.platform)
+    .bind(series_id)
+    .bind(if item.category.is_empty() {
+        DEFAULT_CATEGORY
+    } else {
+        &item.category
+    })
+    .bind(now)
     .execute(pool)
     .await
     .map_err(|e| anyhow::anyhow!("插入或更新 ani_info {:?} 失败: {}", item, e))?;
@@ -143,7 +175,9 @@ pub async fn get_ani_info_by_id(pool: &SqlitePool, id: i64) -> Result<Ani> {
                     image_url,
                     detail_url,
                     update_time,
-                    platform
+                    platform,
+                    series_id,
+                    category
                 FROM ani_info
                 WHERE
                   id = ?
@@ -218,7 +252,9 @@ pub async fn list_all_ani_info_by_update_time(
                     image_url,
                     detail_url,
                     update_time,
-                    platform
+                    platform,
+                    series_id,
+                    category
                 FROM ani_info
                 WHERE
                    update_time >= ?
@@ -281,11 +317,17 @@ pub async fn upsert_ani_collect(pool: &SqlitePool, item: &AniColl) -> Result<i64
                     ani_item_id,
                     ani_title,
                     collect_time,
-                    is_watched
-                ) VALUES (?, ?, ?, ?, ?)
+                    is_watched,
+                    series_id,
+                    normalized_title,
+                    base_title
+                ) VALUES (?, ?, ?, ?, ?, (SELECT series_id FROM ani_info WHERE id = ?), ?, ?)
                 ON CONFLICT(user_id, ani_item_id)
                 DO UPDATE SET
-                    collect_time = excluded.collect_time
+                    collect_time = excluded.collect_time,
+                    series_id = excluded.series_id,
+                    normalized_title = excluded.normalized_title,
+                    base_title = excluded.base_title
              "#,
     )
     .bind(&item.user_id)
@@ -293,6 +335,9 @@ pub async fn upsert_ani_collect(pool: &SqlitePool, item: &AniColl) -> Result<i64
     .bind(&item.ani_title)
     .bind(update_time)
     .bind(item.is_watched)
+    .bind(item.ani_item_id)
+    .bind(normalize_title(&item.ani_title))
+    .bind(base_title(&item.ani_title))
     .execute(pool)
     .await
     .context(format!("插入或者更新 ani_collect ={item:?} 失败"))?;
@@ -300,17 +345,21 @@ pub async fn upsert_ani_collect(pool: &SqlitePool, item: &AniColl) -> Result<i64
     Ok(res.last_insert_rowid())
 }
 
-/// 删除指定的动漫收藏
+/// 删除指定的动漫收藏（同一番剧的所有关注记录一并删除）
 pub async fn delete_ani_collect(pool: &SqlitePool, ani_id: i64, ani_title: String) -> Result<u64> {
     let res = sqlx::query(
         r#"DELETE FROM ani_collect
                       WHERE
                             ani_item_id = ? OR
-                            ani_title = ?
+                            ani_title = ? OR
+                            normalized_title = ? OR
+                            series_id = (SELECT series_id FROM ani_info WHERE id = ?)
                   ;"#,
     )
     .bind(ani_id)
     .bind(&ani_title)
+    .bind(normalize_title(&ani_title))
+    .bind(ani_id)
     .execute(pool)
     .await
     .context(format!(
@@ -341,6 +390,15 @@ pub async fn list_all_ani_collect(pool: &SqlitePool) -> Result<Vec<AniCollect>>
 
 /// 查询所有今日更新的动漫
 pub async fn list_all_ani_update_today(pool: &SqlitePool, today_ts: i64) -> Result<Vec<Ani>> {
+    list_all_ani_update_between(pool, today_ts, today_ts + DAY_MILLIS).await
+}
+
+/// 查询更新时间在 [start_ts, end_ts) 内的动漫，同一标题同一集只取一条（没有集数的不合并）
+pub async fn list_all_ani_update_between(
+    pool: &SqlitePool,
+    start_ts: i64,
+    end_ts: i64,
+) -> Result<Vec<Ani>> {
     // 构造带绑定参数的 QueryAs
     let sql = sqlx::query_as::<_, Ani>(
         r#"
@@ -351,24 +409,66 @@ pub async fn list_all_ani_update_today(pool: &SqlitePool, today_ts: i64) -> Resu
                        ai.image_url,
                        ai.detail_url,
                        ai.update_time,
-                       ai.platform
+                       ai.platform,
+                       ai.series_id,
+                       ai.category
                 FROM ani_info ai
                          INNER JOIN (
-                    SELECT title, update_count, MIN(id) AS min_id
+                    SELECT MIN(id) AS min_id
                     FROM ani_info
-                    WHERE update_time >= ?
-                    GROUP BY title, update_count
-                ) t ON ai.title = t.title
-                    AND ai.update_count = t.update_count
-                    AND ai.id = t.min_id;
+                    WHERE update_time >= ? AND update_time < ?
+                    GROUP BY COALESCE(series_id, title),
+                             CASE WHEN update_count GLOB '[0-9]*' THEN update_count ELSE '#' || id END
+                ) t ON ai.id = t.min_id
+                ORDER BY ai.update_time, ai.id;
            ;"#,
     )
-    .bind(today_ts);
+    .bind(start_ts)
+    .bind(end_ts);
     // 调用通用的 run_query
     let list = run_query(pool, sql).await?;
     Ok(list)
 }
 
+/// 查询关注番剧在 [start_ts, end_ts) 内的更新（含即将更新的剧集），同一番剧同一集只取一条
+pub async fn list_follow_ani_update_between(
+    pool: &SqlitePool,
+    start_ts: i64,
+    end_ts: i64,
+) -> Result<Vec<Ani>> {
+    let sql = sqlx::query_as::<_, Ani>(
+        r#"
+                SELECT ai.id,
+                       ai.title,
+                       ai.update_count,
+                       ai.update_info,
+                       ai.image_url,
+                       ai.detail_url,
+                       ai.update_time,
+                       ai.platform,
+                       ai.series_id,
+                       ai.category
+                FROM ani_info ai
+                         INNER JOIN (
+                    SELECT MIN(fi.id) AS min_id
+                    FROM ani_info fi
+                             LEFT JOIN series s
+                             ON s.id = fi.series_id
+                    WHERE fi.update_time >= ? AND fi.update_time < ?
+                      AND (fi.series_id IN (SELECT series_id FROM ani_collect)
+                        OR s.base_title IN (SELECT base_title FROM ani_collect))
+                    GROUP BY fi.series_id,
+                             CASE WHEN fi.update_count GLOB '[0-9]*' THEN fi.update_count ELSE '#' || fi.id END
+                ) t ON ai.id = t.min_id
+                ORDER BY ai.update_time, ai.id;
+           ;"#,
+    )
+    .bind(start_ts)
+    .bind(end_ts);
+    let list = run_query(pool, sql).await?;
+    Ok(list)
+}
+
 pub async fn upsert_ani_watch_history(pool: &SqlitePool, item: &AniWatch) -> Result<()> {
     let _ = sqlx::query(
         r#"
@@ -394,11 +494,23 @@ pub async fn upsert_ani_watch_history(pool: &SqlitePool, item: &AniWatch) -> Res
 pub async fn list_all_follow_ani_update_today(
     pool: &SqlitePool,
     today_ts: i64,
+) -> Result<Vec<Ani>> {
+    list_unwatched_follow_ani_between(pool, today_ts, today_ts + DAY_MILLIS).await
+}
+
+/// 查询关注的动漫在 [start_ts, end_ts) 内尚未观看的更新
+///
+/// 按番剧或去掉季度后缀的标题匹配关注记录（关注某一季时其它季也会出现）；
+/// 同一番剧同一集只返回一条，任一平台看过即视为已看；没有集数的更新按记录本身判断
+pub async fn list_unwatched_follow_ani_between(
+    pool: &SqlitePool,
+    start_ts: i64,
+    end_ts: i64,
 ) -> Result<Vec<Ani>> {
     // 构造带绑定参数的 QueryAs
     let sql = sqlx::query_as::<_, Ani>(
         r#"
-            SELECT DISTINCT
+            SELECT
                 ai.id,
                 ai.title,
                 ai.update_count,
@@ -406,26 +518,54 @@ pub async fn list_all_follow_ani_update_today(
                 ai.image_url,
                 ai.detail_url,
                 ai.update_time,
-                ai.platform
+                ai.platform,
+                ai.series_id,
+                ai.category
             FROM ani_info ai
-                     INNER JOIN ani_collect ac
-                     ON ac.ani_title = ai.title
-                     LEFT JOIN ani_watch_history awh
-                     ON awh.ani_item_id = ai.id
-            WHERE
-                ai.update_time = ?
-              AND awh.ani_item_id IS NULL;
+                     INNER JOIN (
+                SELECT MIN(fi.id) AS min_id
+                FROM ani_info fi
+                         LEFT JOIN series s
+                         ON s.id = fi.series_id
+                WHERE fi.update_time >= ? AND fi.update_time < ?
+                  AND (fi.series_id IN (SELECT series_id FROM ani_collect)
+                    OR s.base_title IN (SELECT base_title FROM ani_collect))
+                  AND NOT EXISTS (
+                      SELECT 1
+                      FROM ani_watch_history awh
+                               INNER JOIN ani_info wi
+                               ON wi.id = awh.ani_item_id
+                      WHERE CASE
+                                WHEN fi.update_count GLOB '[0-9]*'
+                                    THEN wi.series_id = fi.series_id AND wi.update_count = fi.update_count
+                                -- 没有集数（如 mikanani）时只认这条记录本身在更新之后的观看
+                                ELSE wi.id = fi.id AND awh.watched_time >= fi.update_time
+                            END
+                  )
+                  AND NOT EXISTS (
+                      SELECT 1
+                      FROM episode_watch ew
+                      WHERE ew.series_id = fi.series_id
+                        AND fi.update_count GLOB '[0-9]*'
+                        AND ew.episode = CAST(fi.update_count AS INTEGER)
+                  )
+                GROUP BY fi.series_id,
+                         CASE WHEN fi.update_count GLOB '[0-9]*' THEN fi.update_count ELSE '#' || fi.id END
+            ) t ON ai.id = t.min_id
+            ORDER BY ai.update_time, ai.id;
            ;"#,
     )
-    .bind(today_ts);
+    .bind(start_ts)
+    .bind(end_ts);
     // 调用通用的 run_query
     let list = run_query(pool, sql).await?;
     Ok(list)
 }
 
-/// 查询所有动漫的历史数据
+/// 查询所有动漫的历史数据（更新时间早于 before_ts，即不含尚未播出的剧集）
 pub async fn list_all_ani_history_data(
     pool: &SqlitePool,
+    before_ts: i64,
     page: i64,
     page_size: i64,
 ) -> Result<Vec<AniHistoryInfo>> {
@@ -448,10 +588,12 @@ pub async fn list_all_ani_history_data(
                 FROM ani_info ai
                 LEFT JOIN ani_watch_history awh
                        ON ai.id = awh.ani_item_id
+                WHERE ai.update_time < ?
                 ORDER BY ai.update_time DESC
                 LIMIT ? OFFSET ?
            ;"#,
     )
+    .bind(before_ts)
     .bind(page_size)
     .bind((page - 1) * page_size);
     // 调用通用的 run_query
@@ -462,10 +604,12 @@ pub async fn list_all_ani_history_data(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::common::save_ani_item_data_db;
+    use crate::db::fixtures::{fanren_item, now};
     use crate::db::sqlite::upsert_ani_info;
     use crate::db::sqlite::{creat_database_connection_pool, test_init_db_schema};
     use crate::types::AniItem;
-    use crate::utils::date_utils::parse_date_to_millis;
+    use crate::utils::date_utils::{parse_date_to_millis, DAY_MILLIS};
     use anyhow::Context;
     use sqlx::{Pool, Sqlite, SqlitePool};
     use std::fs::File;
@@ -521,6 +665,8 @@ mod tests {
             detail_url: "https://mikanani.me/Home/Bangumi/227".to_string(),
             update_time: "2025/07/13".to_string(), // 2025/07/13 的时间戳
             platform: "mikanani".to_string(),
+            external_id: String::new(),
+            category: String::new(),
         };
         let ani_info2 = AniItem{
             title: "You and idol 光之美少女♪".to_string(),
@@ -530,6 +676,8 @@ mod tests {
             detail_url: "https://mikanani.me/Home/Bangumi/3570".to_string(),
             update_time: "2025/07/13".to_string(), // 2025/07/13 的时间戳
             platform: "mikanani".to_string(),
+            external_id: String::new(),
+            category: String::new(),
         };
 
         let ani_info3 = AniItem{
@@ -540,6 +688,8 @@ mod tests {
             detail_url: "https://mikanani.me/Home/Bangumi/3587".to_string(),
             update_time: "2025/07/13".to_string(), // 2025/07/13 的时间戳
             platform: "mikanani".to_string(),
+            external_id: String::new(),
+            category: String::new(),
         };
 
         let ani_info4 = AniItem{
@@ -550,6 +700,8 @@ mod tests {
             detail_url: "https://mikanani.me/Home/Bangumi/3640".to_string(),
             update_time: "2025/07/13".to_string(), // 2025/07/13 的时间戳
             platform: "mikanani".to_string(),
+            external_id: String::new(),
+            category: String::new(),
         };
 
         let ani_info5 = AniItem{
@@ -560,22 +712,24 @@ mod tests {
             detail_url: "https://mikanani.me/Home/Bangumi/3663".to_string(),
             update_time: "2025/07/13".to_string(), // 2025/07/13 的时间戳
             platform: "mikanani".to_string(),
+            external_id: String::new(),
+            category: String::new(),
         };
 
         // 执行sql
-        let _ = upsert_ani_info(pool, &ani_info1)
+        let _ = upsert_ani_info(pool, &ani_info1, now())
             .await
             .context("插入数据失败");
-        let _ = upsert_ani_info(pool, &ani_info2)
+        let _ = upsert_ani_info(pool, &ani_info2, now())
             .await
             .context("插入数据失败");
-        let _ = upsert_ani_info(pool, &ani_info3)
+        let _ = upsert_ani_info(pool, &ani_info3, now())
             .await
             .context("插入数据失败");
-        let _ = upsert_ani_info(pool, &ani_info4)
+        let _ = upsert_ani_info(pool, &ani_info4, now())
             .await
             .context("插入数据失败");
-        let _ = upsert_ani_info(pool, &ani_info5)
+        let _ = upsert_ani_info(pool, &ani_info5, now())
             .await
             .context("插入数据失败");
         Ok(())
@@ -594,8 +748,10 @@ mod tests {
             detail_url: "https://mikanani.me/Home/Bangumi/3663".to_string(),
             update_time: "2025/07/13".to_string(), // 2025/07/13 的时间戳
             platform: "mikanani".to_string(),
+            external_id: String::new(),
+            category: String::new(),
         };
-        upsert_ani_info(&pool, &ani_item1)
+        upsert_ani_info(&pool, &ani_item1, now())
             .await
             .expect("插入数据失败");
         let ani_item2 = AniItem {
@@ -606,9 +762,11 @@ mod tests {
             detail_url: "https://mikanani.me/Home/Bangumi/3663".to_string(),
             update_time: "2025/07/14".to_string(), // 2025/07/14 的时间戳
             platform: "mikanani".to_string(),
+            external_id: String::new(),
+            category: String::new(),
         };
         // 插入第一条记录
-        upsert_ani_info(&pool, &ani_item2)
+        upsert_ani_info(&pool, &ani_item2, now())
             .await
             .expect("插入数据失败");
         // 测试违反唯一约束更新更新数据
@@ -640,6 +798,97 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_upcoming_update_time_follows_schedule() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        let item = |date: &str| AniItem {
+            title: "琉璃的宝石".to_string(),
+            update_count: "19".to_string(),
+            update_info: "更新至第19集".to_string(),
+            image_url: String::new(),
+            detail_url: "https://mikanani.me/Home/Bangumi/3663".to_string(),
+            update_time: date.to_string(),
+            platform: "mikanani".to_string(),
+            external_id: String::new(),
+            category: String::new(),
+        };
+        let update_time =
+            || sqlx::query_scalar::<_, i64>("SELECT update_time FROM ani_info").fetch_one(&pool);
+        let before_air = parse_date_to_millis("2025/07/18", true).unwrap();
+
+        // 未播出的剧集平台推迟了一天，按新的播出日期修正
+        upsert_ani_info(&pool, &item("2025/07/20"), before_air)
+            .await
+            .unwrap();
+        upsert_ani_info(&pool, &item("2025/07/21"), before_air)
+            .await
+            .unwrap();
+        assert_eq!(
+            update_time().await.unwrap(),
+            parse_date_to_millis("2025/07/21", true).unwrap()
+        );
+
+        // 已播出后再抓到不同的日期，保留原来的更新时间
+        upsert_ani_info(&pool, &item("2025/07/25"), now())
+            .await
+            .unwrap();
+        assert_eq!(
+            update_time().await.unwrap(),
+            parse_date_to_millis("2025/07/21", true).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_ani_item_data_all_days() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        test_init_db_schema(&pool).await.unwrap();
+        let pool = std::sync::Arc::new(pool);
+        let item = |title: &str, count: &str, date: &str| AniItem {
+            title: title.to_string(),
+            external_id: String::new(),
+            ..fanren_item(count, date)
+        };
+        let mut data = std::collections::HashMap::new();
+        data.insert(
+            "2025/07/12".to_string(),
+            vec![item("间谍过家家", "40", "2025/07/12")],
+        );
+        data.insert(
+            "2025/07/13".to_string(),
+            vec![item("凡人修仙传", "152", "2025/07/13")],
+        );
+        data.insert(
+            "2025/07/14".to_string(),
+            vec![item("凡人修仙传", "153", "2025/07/14")],
+        );
+
+        // 所有日期的数据都会按各自的更新日期保存
+        let res = save_ani_item_data_db(pool.clone(), data, now())
+            .await
+            .unwrap();
+        assert_eq!(res.status, "ok");
+        assert_eq!(list_all_ani_info(&pool).await.unwrap().len(), 3);
+
+        // “今日更新”只包含当天，不包含即将更新的剧集
+        let today_ts = parse_date_to_millis("2025/07/13", true).unwrap();
+        let today = list_all_ani_update_today(&pool, today_ts).await.unwrap();
+        assert_eq!(today.len(), 1);
+        assert_eq!(today[0].update_count, "152");
+
+        // 历史数据不包含尚未播出的剧集
+        let history = list_all_ani_history_data(&pool, today_ts + DAY_MILLIS, 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+
+        // 空结果视为抓取失败
+        let res = save_ani_item_data_db(pool.clone(), std::collections::HashMap::new(), now())
+            .await
+            .unwrap();
+        assert_eq!(res.status, "error");
+    }
+
     #[tokio::test]
     async fn test_db_select_by_id() {
         // 获取数据库连接池
@@ -679,6 +928,43 @@ mod tests {
         assert_eq!(ani_items[0].title, "琉璃的宝石");
     }
 
+    #[tokio::test]
+    async fn test_db_ani_info_category() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        test_init_db_schema(&pool).await.unwrap();
+        let item = |title: &str, category: &str| AniItem {
+            title: title.to_string(),
+            update_count: "1".to_string(),
+            update_info: "更新至1集".to_string(),
+            image_url: String::new(),
+            detail_url: String::new(),
+            update_time: "2025/07/13".to_string(),
+            platform: "tencent".to_string(),
+            external_id: String::new(),
+            category: category.to_string(),
+        };
+        upsert_ani_info(&pool, &item("庆余年第二季", "drama"), now())
+            .await
+            .unwrap();
+        // 未指定分类时按动漫保存
+        upsert_ani_info(&pool, &item("斗罗大陆", ""), now())
+            .await
+            .unwrap();
+
+        let today = parse_date_to_millis("2025/07/13", true).unwrap();
+        let list = list_all_ani_update_between(&pool, today, today + DAY_MILLIS)
+            .await
+            .unwrap();
+        let categories: Vec<(&str, &str)> = list
+            .iter()
+            .map(|a| (a.title.as_str(), a.category.as_str()))
+            .collect();
+        assert_eq!(
+            categories,
+            vec![("庆余年第二季", "drama"), ("斗罗大陆", "anime")]
+        );
+    }
+
     #[tokio::test]
     async fn test_db_update_ani_info() {
         let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
@@ -692,6 +978,8 @@ mod tests {
             detail_url: "https://mikanani.me/Home/Bangumi/227".to_string(),
             update_time: parse_date_to_millis("2025/07/14", true).unwrap(),
             platform: "tencent".to_string(),
+            series_id: None,
+            category: "anime".to_string(),
         };
         // update sql 测试
         update_ani_info(&pool, &ani_item)
@@ -0,0 +1,218 @@
+use crate::db::po::{TaskHealth, TaskRun};
+use crate::tasks::task::{TaskEvent, TaskStatus};
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+
+/// 每个任务保留的执行记录条数
+const KEEP_RUNS_PER_TASK: i64 = 500;
+
+/// 汇总健康状况时参考的最近执行次数
+const HEALTH_WINDOW: i64 = 20;
+
+/// 连续失败达到该次数视为数据源不可用
+const FAILING_THRESHOLD: i64 = 3;
+
+/// 记录一次任务执行结果（开始事件不记录），并清理过旧的记录
+pub async fn insert_task_run(pool: &SqlitePool, event: &TaskEvent) -> Result<()> {
+    let status = match event.status {
+        TaskStatus::Started => return Ok(()),
+        TaskStatus::Success => "success",
+        TaskStatus::Failed => "failed",
+    };
+    let warnings = (!event.warnings.is_empty()).then(|| event.warnings.join("\n"));
+    sqlx::query(
+        r#"
+                INSERT INTO task_run (task_name, manual, status, attempts, item_count, error, warnings, started_time, finished_time)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+    )
+    .bind(&event.task_name)
+    .bind(event.manual)
+    .bind(status)
+    .bind(event.attempts)
+    .bind(event.count.map(|c| c as i64))
+    .bind(&event.message)
+    .bind(warnings)
+    .bind(event.started_time)
+    .bind(event.time)
+    .execute(pool)
+    .await
+    .context(format!("记录任务 [{}] 执行结果失败", event.task_name))?;
+
+    sqlx::query(
+        r#"
+                DELETE FROM task_run
+                WHERE task_name = ?
+                  AND id NOT IN (SELECT id FROM task_run WHERE task_name = ? ORDER BY id DESC LIMIT ?)
+            "#,
+    )
+    .bind(&event.task_name)
+    .bind(&event.task_name)
+    .bind(KEEP_RUNS_PER_TASK)
+    .execute(pool)
+    .await
+    .context(format!("清理任务 [{}] 执行记录失败", event.task_name))?;
+    Ok(())
+}
+
+/// 查询最近的任务执行记录，task_name 为空时查询所有任务
+pub async fn list_task_run(
+    pool: &SqlitePool,
+    task_name: Option<&str>,
+    limit: i64,
+) -> Result<Vec<TaskRun>> {
+    let list = sqlx::query_as::<_, TaskRun>(
+        r#"
+                SELECT id, task_name, manual, status, attempts, item_count, error, warnings, started_time, finished_time
+                FROM task_run
+                WHERE ? IS NULL OR task_name = ?
+                ORDER BY started_time DESC, id DESC
+                LIMIT ?
+            "#,
+    )
+    .bind(task_name)
+    .bind(task_name)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .context("查询任务执行记录失败")?;
+    Ok(list)
+}
+
+/// 汇总各任务的健康状况
+pub async fn list_task_health(pool: &SqlitePool, task_names: &[String]) -> Result<Vec<TaskHealth>> {
+    let mut list = Vec::with_capacity(task_names.len());
+    for name in task_names {
+        let runs = list_task_run(pool, Some(name), HEALTH_WINDOW).await?;
+        let last_success_time = sqlx::query_scalar::<_, Option<i64>>(
+            r#"SELECT MAX(finished_time) FROM task_run WHERE task_name = ? AND status = 'success'"#,
+        )
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .context(format!("查询任务 [{name}] 最近成功时间失败"))?;
+        list.push(summarize_task_health(name, &runs, last_success_time));
+    }
+    Ok(list)
+}
+
+/// 由最近的执行记录（按时间倒序）计算健康状况
+pub fn summarize_task_health(
+    task_name: &str,
+    runs: &[TaskRun],
+    last_success_time: Option<i64>,
+) -> TaskHealth {
+    let consecutive_failures = runs.iter().take_while(|r| r.status == "failed").count() as i64;
+    let successes: Vec<&TaskRun> = runs.iter().filter(|r| r.status == "success").collect();
+    let counts: Vec<i64> = successes.iter().filter_map(|r| r.item_count).collect();
+    let avg_items =
+        (!counts.is_empty()).then(|| counts.iter().sum::<i64>() as f64 / counts.len() as f64);
+    let last = runs.first();
+    let last_success = successes.first();
+    let last_warnings: Vec<String> = last_success
+        .and_then(|r| r.warnings.as_deref())
+        .map(|w| w.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let status = match last {
+        None => "unknown",
+        Some(_) if consecutive_failures >= FAILING_THRESHOLD => "failing",
+        // 最近失败，或最近一次成功时解析出了告警（如页面结构变化导致没有数据）
+        Some(_) if consecutive_failures > 0 || !last_warnings.is_empty() => "degraded",
+        Some(_) => "ok",
+    };
+
+    TaskHealth {
+        task_name: task_name.to_string(),
+        status: status.to_string(),
+        last_run_time: last.map(|r| r.finished_time),
+        last_success_time,
+        consecutive_failures,
+        avg_items,
+        last_item_count: last_success.and_then(|r| r.item_count),
+        last_error: last
+            .filter(|r| r.status == "failed")
+            .and_then(|r| r.error.clone()),
+        last_warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::MIGRATOR;
+
+    fn event(name: &str, status: TaskStatus, count: Option<usize>, time: i64) -> TaskEvent {
+        TaskEvent {
+            task_name: name.to_string(),
+            status,
+            manual: false,
+            count,
+            message: (status == TaskStatus::Failed).then(|| "连接超时".to_string()),
+            attempts: 1,
+            warnings: if count == Some(0) {
+                vec!["没有解析到任何更新，页面结构可能已变化".to_string()]
+            } else {
+                vec![]
+            },
+            started_time: time - 100,
+            time,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_run_and_health() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+
+        // 开始事件不记录
+        insert_task_run(&pool, &event("哔哩哔哩", TaskStatus::Started, None, 1_000))
+            .await
+            .unwrap();
+        for (i, count) in [10, 20, 30].into_iter().enumerate() {
+            let time = 2_000 + i as i64 * 1_000;
+            insert_task_run(
+                &pool,
+                &event("哔哩哔哩", TaskStatus::Success, Some(count), time),
+            )
+            .await
+            .unwrap();
+        }
+        insert_task_run(&pool, &event("腾讯", TaskStatus::Success, Some(8), 2_000))
+            .await
+            .unwrap();
+        insert_task_run(&pool, &event("腾讯", TaskStatus::Success, Some(0), 3_000))
+            .await
+            .unwrap();
+        for time in [4_000, 5_000, 6_000] {
+            insert_task_run(&pool, &event("优酷", TaskStatus::Failed, None, time))
+                .await
+                .unwrap();
+        }
+
+        let runs = list_task_run(&pool, None, 100).await.unwrap();
+        assert_eq!(runs.len(), 8);
+        let runs = list_task_run(&pool, Some("腾讯"), 100).await.unwrap();
+        assert_eq!(
+            runs[0].warnings.as_deref(),
+            Some("没有解析到任何更新，页面结构可能已变化")
+        );
+        assert_eq!(runs[0].started_time, 2_900);
+
+        let names: Vec<String> = ["哔哩哔哩", "腾讯", "优酷", "爱奇艺"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let health = list_task_health(&pool, &names).await.unwrap();
+        assert_eq!(health[0].status, "ok");
+        assert_eq!(health[0].avg_items, Some(20.0));
+        assert_eq!(health[0].last_success_time, Some(4_000));
+        assert_eq!(health[1].status, "degraded");
+        assert_eq!(health[1].last_item_count, Some(0));
+        assert_eq!(health[2].status, "failing");
+        assert_eq!(health[2].consecutive_failures, 3);
+        assert_eq!(health[2].last_error.as_deref(), Some("连接超时"));
+        assert_eq!(health[2].last_success_time, None);
+        assert_eq!(health[3].status, "unknown");
+    }
+}
@@ -0,0 +1,314 @@
+//! 关注番剧的更新日历（iCalendar，RFC 5545）
+//!
+//! 每一集对应一个全天事件，UID 由番剧 ID 和集数（无集数时用日期）生成，
+//! 重复导出或订阅刷新时日历应用会更新原有事件而不是新增重复事件。
+
+use crate::db::common::ge_db_pool;
+use crate::db::po::Ani;
+use crate::db::sqlite::list_follow_ani_update_between;
+use crate::state::AppState;
+use crate::utils::date_utils::{date_to_millis, format_date, DateFormat};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use std::collections::HashMap;
+
+/// 日历名称
+pub const CALENDAR_NAME: &str = "AniTodo 关注番剧";
+
+/// 导出今天之前多少天的更新
+const PAST_DAYS: i64 = 30;
+
+/// 导出今天之后多少天的更新（与即将更新列表一致）
+const UPCOMING_DAYS: i64 = 7;
+
+/// 每周更新的间隔
+const WEEKLY_DAYS: i64 = 7;
+
+const PRODID: &str = "-//ani-todo-app//Followed Anime//CN";
+const UID_DOMAIN: &str = "ani-todo-app";
+
+/// 日历中的一个全天事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub date: NaiveDate,
+    pub summary: String,
+    pub description: String,
+    pub url: String,
+    /// 根据每周更新规律推测出的事件
+    pub tentative: bool,
+}
+
+/// 生成关注番剧的日历文本
+pub async fn build_followed_calendar(state: &AppState) -> Result<String> {
+    let pool = ge_db_pool(&state.db);
+    let today = state.clock.today();
+    let start = date_to_millis(today - Duration::days(PAST_DAYS))?;
+    let end = date_to_millis(today + Duration::days(UPCOMING_DAYS + 1))?;
+    let list = list_follow_ani_update_between(pool, start, end).await?;
+    let events = events_from_updates(&list, today);
+    Ok(render_calendar(
+        CALENDAR_NAME,
+        &events,
+        state.clock.now().with_timezone(&Utc),
+    ))
+}
+
+/// 由更新记录生成事件，并为最近一周有更新、但还没有下一集排期的番剧推测下一集
+pub fn events_from_updates(list: &[Ani], today: NaiveDate) -> Vec<CalendarEvent> {
+    let mut events = Vec::new();
+    // 番剧 -> 最近一次更新
+    let mut latest: HashMap<i64, (&Ani, NaiveDate)> = HashMap::new();
+
+    for ani in list {
+        let Some(series_id) = ani.series_id else {
+            continue;
+        };
+        let Some(date) = millis_to_date(ani.update_time) else {
+            continue;
+        };
+        events.push(CalendarEvent {
+            uid: event_uid(series_id, &ani.update_count, date),
+            date,
+            summary: format!("{} {}", ani.title, ani.update_info),
+            description: format!("平台：{}\n{}", ani.platform, ani.update_info),
+            url: ani.detail_url.clone(),
+            tentative: false,
+        });
+        match latest.get(&series_id) {
+            Some((_, d)) if *d >= date => {}
+            _ => {
+                latest.insert(series_id, (ani, date));
+            }
+        }
+    }
+
+    let mut expected: Vec<CalendarEvent> = latest
+        .into_iter()
+        .filter_map(|(series_id, (ani, date))| {
+            let next_date = date + Duration::days(WEEKLY_DAYS);
+            // 已有排期或停更超过一周的不推测
+            if date > today || next_date < today {
+                return None;
+            }
+            let next_count = ani
+                .update_count
+                .parse::<u32>()
+                .map(|n| (n + 1).to_string())
+                .unwrap_or_default();
+            let summary = if next_count.is_empty() {
+                format!("{}（预计更新）", ani.title)
+            } else {
+                format!("{} 第{}集（预计更新）", ani.title, next_count)
+            };
+            Some(CalendarEvent {
+                uid: event_uid(series_id, &next_count, next_date),
+                date: next_date,
+                summary,
+                description: format!("平台：{}\n按每周更新推测", ani.platform),
+                url: ani.detail_url.clone(),
+                tentative: true,
+            })
+        })
+        .collect();
+    expected.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.uid.cmp(&b.uid)));
+    events.extend(expected);
+    events
+}
+
+/// 渲染为 iCalendar 文本
+pub fn render_calendar(name: &str, events: &[CalendarEvent], stamp: DateTime<Utc>) -> String {
+    let stamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{PRODID}"),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+    for event in events {
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", event.uid),
+            format!("DTSTAMP:{stamp}"),
+            format!(
+                "DTSTART;VALUE=DATE:{}",
+                format_date(event.date, DateFormat::Number)
+            ),
+            format!(
+                "DTEND;VALUE=DATE:{}",
+                format_date(event.date + Duration::days(1), DateFormat::Number)
+            ),
+            format!("SUMMARY:{}", escape_text(&event.summary)),
+            format!("DESCRIPTION:{}", escape_text(&event.description)),
+        ]);
+        if !event.url.is_empty() {
+            lines.push(format!("URL:{}", event.url));
+        }
+        lines.extend([
+            format!(
+                "STATUS:{}",
+                if event.tentative {
+                    "TENTATIVE"
+                } else {
+                    "CONFIRMED"
+                }
+            ),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold_line(line) + "\r\n")
+        .collect::<String>()
+}
+
+/// 事件 UID：有集数时按集数生成，预计更新与实际更新共用同一个 UID
+fn event_uid(series_id: i64, update_count: &str, date: NaiveDate) -> String {
+    match update_count.parse::<u32>() {
+        Ok(n) => format!("series-{series_id}-ep-{n}@{UID_DOMAIN}"),
+        Err(_) => format!(
+            "series-{series_id}-{}@{UID_DOMAIN}",
+            format_date(date, DateFormat::Number)
+        ),
+    }
+}
+
+/// 毫秒时间戳转本地日期
+fn millis_to_date(ts: i64) -> Option<NaiveDate> {
+    Local
+        .timestamp_millis_opt(ts)
+        .single()
+        .map(|dt| dt.date_naive())
+}
+
+/// 转义 TEXT 类型的值
+fn escape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// 按 75 字节折行，续行以空格开头，不拆开多字节字符
+fn fold_line(line: &str) -> String {
+    const LIMIT: usize = 75;
+    let mut out = String::with_capacity(line.len() + line.len() / LIMIT * 3);
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > LIMIT {
+            out.push_str("\r\n ");
+            // 续行开头的空格也计入长度
+            width = 1;
+        }
+        out.push(c);
+        width += len;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::date_utils::parse_date_to_millis;
+
+    fn ani(series_id: i64, count: &str, date: &str) -> Ani {
+        Ani {
+            id: 0,
+            title: "凡人修仙传".to_string(),
+            update_count: count.to_string(),
+            update_info: format!("更新至第{count}话"),
+            image_url: String::new(),
+            detail_url: format!("https://www.bilibili.com/bangumi/play/ep{count}"),
+            update_time: parse_date_to_millis(date, true).unwrap(),
+            platform: "bilibili".to_string(),
+            series_id: Some(series_id),
+            category: "anime".to_string(),
+        }
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_events_from_updates() {
+        let today = day(2025, 7, 13);
+        let list = vec![
+            ani(1, "151", "2025/07/06"),
+            ani(1, "152", "2025/07/13"),
+            // 已有下一集排期，不推测
+            ani(2, "11", "2025/07/12"),
+            ani(2, "12", "2025/07/19"),
+            // 停更超过一周，不推测
+            ani(3, "24", "2025/06/29"),
+        ];
+        let events = events_from_updates(&list, today);
+        assert_eq!(events.len(), 6);
+        assert_eq!(events[1].uid, "series-1-ep-152@ani-todo-app");
+        assert_eq!(events[1].date, today);
+        assert!(!events[1].tentative);
+
+        let expected = &events[5];
+        assert!(expected.tentative);
+        assert_eq!(expected.date, day(2025, 7, 20));
+        assert_eq!(expected.summary, "凡人修仙传 第153集（预计更新）");
+        // 与真正更新后的事件共用 UID
+        assert_eq!(expected.uid, "series-1-ep-153@ani-todo-app");
+
+        // 没有集数时按日期生成 UID
+        assert_eq!(
+            event_uid(1, "", day(2025, 7, 20)),
+            "series-1-20250720@ani-todo-app"
+        );
+    }
+
+    #[test]
+    fn test_render_calendar() {
+        let event = CalendarEvent {
+            uid: "series-1-ep-152@ani-todo-app".to_string(),
+            date: day(2025, 7, 13),
+            summary: "Re:Zero; 第三季, 第1集".to_string(),
+            description: "平台：bilibili\n更新至第1话".to_string(),
+            url: "https://www.bilibili.com/bangumi/play/ep1".to_string(),
+            tentative: false,
+        };
+        let stamp = Utc.with_ymd_and_hms(2025, 7, 13, 4, 0, 0).unwrap();
+        let ics = render_calendar(CALENDAR_NAME, &[event], stamp);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20250713T040000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART;VALUE=DATE:20250713\r\n"));
+        assert!(ics.contains("\r\nDTEND;VALUE=DATE:20250714\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Re:Zero\\; 第三季\\, 第1集\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:平台：bilibili\\n更新至第1话\r\n"));
+        assert!(ics.contains("\r\nSTATUS:CONFIRMED\r\n"));
+        // 不出现裸换行
+        assert!(!ics.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let line = format!("SUMMARY:{}", "凡".repeat(40));
+        let folded = fold_line(&line);
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= 75);
+        }
+        assert_eq!(folded.replace("\r\n ", ""), line);
+        assert_eq!(fold_line("SUMMARY:short"), "SUMMARY:short");
+    }
+}
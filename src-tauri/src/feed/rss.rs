@@ -0,0 +1,337 @@
+//! 今日更新、关注更新的订阅源（Atom 1.0 / RSS 2.0）
+
+use crate::db::common::ge_db_pool;
+use crate::db::po::Ani;
+use crate::db::sqlite::{list_all_ani_update_today, list_all_follow_ani_update_today};
+use crate::state::AppState;
+use crate::utils::date_utils::date_to_millis;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, TimeZone};
+use once_cell::sync::Lazy;
+use std::path::Path;
+use tokio::sync::Mutex;
+
+/// 订阅源文件所在目录（位于应用数据目录下）
+pub const FEED_DIR: &str = "feeds";
+
+/// 同一时间只允许一个任务写订阅源文件
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// 订阅源种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedKind {
+    /// 今日更新
+    Today,
+    /// 关注动漫今日更新（不含已看）
+    Follow,
+}
+
+impl FeedKind {
+    pub const ALL: [FeedKind; 2] = [FeedKind::Today, FeedKind::Follow];
+
+    /// 文件名、路由中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            FeedKind::Today => "today",
+            FeedKind::Follow => "follow",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            FeedKind::Today => "AniTodo 今日更新",
+            FeedKind::Follow => "AniTodo 关注更新",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.name() == name)
+    }
+}
+
+/// 订阅源格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    pub const ALL: [FeedFormat; 2] = [FeedFormat::Atom, FeedFormat::Rss];
+
+    /// 文件扩展名
+    pub fn ext(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "atom",
+            FeedFormat::Rss => "rss",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+
+    pub fn from_ext(ext: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.ext() == ext)
+    }
+}
+
+/// 查询数据并生成订阅源
+pub async fn build_feed(state: &AppState, kind: FeedKind, format: FeedFormat) -> Result<String> {
+    let pool = ge_db_pool(&state.db);
+    let today_ts = date_to_millis(state.clock.today())?;
+    let list = match kind {
+        FeedKind::Today => list_all_ani_update_today(pool, today_ts).await?,
+        FeedKind::Follow => list_all_follow_ani_update_today(pool, today_ts).await?,
+    };
+    let now = state.clock.now();
+    Ok(match format {
+        FeedFormat::Atom => render_atom(kind, &list, now),
+        FeedFormat::Rss => render_rss(kind, &list, now),
+    })
+}
+
+/// 把所有订阅源写入 dir 目录，文件名如 `today.atom`、`follow.rss`
+pub async fn write_feeds(state: &AppState, dir: &Path) -> Result<()> {
+    let _guard = WRITE_LOCK.lock().await;
+    tokio::fs::create_dir_all(dir)
+        .await
+        .context(format!("创建订阅源目录 {dir:?} 失败"))?;
+    for kind in FeedKind::ALL {
+        for format in FeedFormat::ALL {
+            let feed = build_feed(state, kind, format).await?;
+            let path = dir.join(format!("{}.{}", kind.name(), format.ext()));
+            tokio::fs::write(&path, feed)
+                .await
+                .context(format!("写入订阅源 {path:?} 失败"))?;
+        }
+    }
+    Ok(())
+}
+
+/// 渲染为 Atom 1.0
+pub fn render_atom(kind: FeedKind, list: &[Ani], now: DateTime<Local>) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <id>{}</id>\n", feed_id(kind)));
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(kind.title())));
+    xml.push_str(&format!("  <updated>{}</updated>\n", now.to_rfc3339()));
+    xml.push_str("  <author><name>AniTodo</name></author>\n");
+    for ani in list {
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <id>{}</id>\n", entry_id(ani)));
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            escape_xml(&entry_title(ani))
+        ));
+        xml.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            millis_to_local(ani.update_time).to_rfc3339()
+        ));
+        if !ani.detail_url.is_empty() {
+            xml.push_str(&format!(
+                "    <link rel=\"alternate\" href=\"{}\"/>\n",
+                escape_xml(&ani.detail_url)
+            ));
+        }
+        xml.push_str(&format!(
+            "    <category term=\"{}\"/>\n",
+            escape_xml(&ani.platform)
+        ));
+        xml.push_str(&format!(
+            "    <content type=\"html\">{}</content>\n",
+            escape_xml(&entry_html(ani))
+        ));
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// 渲染为 RSS 2.0
+pub fn render_rss(kind: FeedKind, list: &[Ani], now: DateTime<Local>) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\">\n<channel>\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(kind.title())));
+    xml.push_str("  <link>https://github.com/bruceblink/ani-todo-app</link>\n");
+    xml.push_str(&format!(
+        "  <description>{}</description>\n",
+        escape_xml(kind.title())
+    ));
+    xml.push_str(&format!(
+        "  <lastBuildDate>{}</lastBuildDate>\n",
+        now.to_rfc2822()
+    ));
+    for ani in list {
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            escape_xml(&entry_title(ani))
+        ));
+        if !ani.detail_url.is_empty() {
+            xml.push_str(&format!(
+                "    <link>{}</link>\n",
+                escape_xml(&ani.detail_url)
+            ));
+        }
+        xml.push_str(&format!(
+            "    <guid isPermaLink=\"false\">{}</guid>\n",
+            entry_id(ani)
+        ));
+        xml.push_str(&format!(
+            "    <pubDate>{}</pubDate>\n",
+            millis_to_local(ani.update_time).to_rfc2822()
+        ));
+        xml.push_str(&format!(
+            "    <category>{}</category>\n",
+            escape_xml(&ani.platform)
+        ));
+        xml.push_str(&format!(
+            "    <description>{}</description>\n",
+            escape_xml(&entry_html(ani))
+        ));
+        xml.push_str("  </item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+fn feed_id(kind: FeedKind) -> String {
+    format!("urn:ani-todo-app:feed:{}", kind.name())
+}
+
+/// 条目 ID 与数据库记录一一对应，阅读器据此去重
+fn entry_id(ani: &Ani) -> String {
+    format!("urn:ani-todo-app:ani:{}", ani.id)
+}
+
+fn entry_title(ani: &Ani) -> String {
+    format!("{} {}", ani.title, ani.update_info)
+}
+
+/// 条目正文：封面、更新信息、平台
+fn entry_html(ani: &Ani) -> String {
+    let mut html = String::new();
+    if !ani.image_url.is_empty() {
+        html.push_str(&format!(
+            "<p><img src=\"{}\" alt=\"{}\"/></p>",
+            escape_xml(&ani.image_url),
+            escape_xml(&ani.title)
+        ));
+    }
+    html.push_str(&format!(
+        "<p>{}</p><p>平台：{}</p>",
+        escape_xml(&ani.update_info),
+        escape_xml(&ani.platform)
+    ));
+    html
+}
+
+fn millis_to_local(ts: i64) -> DateTime<Local> {
+    Local
+        .timestamp_millis_opt(ts)
+        .single()
+        .unwrap_or_else(|| Local.timestamp_millis_opt(0).unwrap())
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures::{fanren_item, now};
+    use crate::db::sqlite::{upsert_ani_info, MIGRATOR};
+    use crate::utils::date_utils::{parse_date_to_millis, FixedClock};
+    use crate::utils::http_client::HttpClient;
+    use chrono::NaiveDate;
+    use sqlx::SqlitePool;
+    use std::sync::Arc;
+
+    fn ani() -> Ani {
+        Ani {
+            id: 7,
+            title: "间谍过家家 <中配>".to_string(),
+            update_count: "12".to_string(),
+            update_info: "更新至第12话".to_string(),
+            image_url: "https://puui.qpic.cn/vcover_vt_pic/0/a.jpg?x=1&y=2".to_string(),
+            detail_url: "https://v.qq.com/x/cover/abc.html".to_string(),
+            update_time: parse_date_to_millis("2025/07/13", true).unwrap(),
+            platform: "tencent".to_string(),
+            series_id: Some(1),
+            category: "anime".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_atom() {
+        let now = Local.with_ymd_and_hms(2025, 7, 13, 20, 0, 0).unwrap();
+        let xml = render_atom(FeedKind::Today, &[ani()], now);
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>"));
+        assert!(xml.contains("<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+        assert!(xml.contains("<id>urn:ani-todo-app:feed:today</id>"));
+        assert!(xml.contains("<id>urn:ani-todo-app:ani:7</id>"));
+        assert!(xml.contains("<title>间谍过家家 &lt;中配&gt; 更新至第12话</title>"));
+        assert!(
+            xml.contains("<link rel=\"alternate\" href=\"https://v.qq.com/x/cover/abc.html\"/>")
+        );
+        assert!(xml.contains("<category term=\"tencent\"/>"));
+        // 正文中的 HTML 被转义，封面地址中的 & 被转义两次
+        assert!(xml.contains(
+            "&lt;img src=&quot;https://puui.qpic.cn/vcover_vt_pic/0/a.jpg?x=1&amp;amp;y=2&quot;"
+        ));
+    }
+
+    #[test]
+    fn test_render_rss() {
+        let now = Local.with_ymd_and_hms(2025, 7, 13, 20, 0, 0).unwrap();
+        let xml = render_rss(FeedKind::Follow, &[ani()], now);
+        assert!(xml.contains("<rss version=\"2.0\">"));
+        assert!(xml.contains("<title>AniTodo 关注更新</title>"));
+        assert!(xml.contains("<guid isPermaLink=\"false\">urn:ani-todo-app:ani:7</guid>"));
+        assert!(xml.contains("<pubDate>Sun, 13 Jul 2025 00:00:00"));
+        assert!(xml.contains("<link>https://v.qq.com/x/cover/abc.html</link>"));
+        assert!(xml.ends_with("</channel>\n</rss>\n"));
+    }
+
+    #[tokio::test]
+    async fn test_write_feeds() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        upsert_ani_info(&pool, &fanren_item("152", "2025/07/13"), now())
+            .await
+            .unwrap();
+        let state = AppState {
+            db: Arc::new(pool),
+            clock: Arc::new(FixedClock::at_date(
+                NaiveDate::from_ymd_opt(2025, 7, 13).unwrap(),
+            )),
+            http: Arc::new(HttpClient::default()),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        write_feeds(&state, dir.path()).await.unwrap();
+        let today = std::fs::read_to_string(dir.path().join("today.atom")).unwrap();
+        assert!(today.contains("凡人修仙传 更新至第152话"));
+        let follow = std::fs::read_to_string(dir.path().join("follow.rss")).unwrap();
+        // 没有关注任何番剧
+        assert!(!follow.contains("<item>"));
+        assert!(dir.path().join("today.rss").exists());
+        assert!(dir.path().join("follow.atom").exists());
+    }
+}
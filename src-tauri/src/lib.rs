@@ -1,34 +1,58 @@
 pub mod command;
 pub mod configuration;
 pub mod db;
+pub mod feed;
+pub mod notify;
+pub mod server;
 mod startup;
 pub mod state;
 mod tasks;
 pub mod types;
 pub mod utils;
+pub mod validation;
 
+use crate::command::config::{check_config, query_config_status};
+use crate::command::data_source::{
+    add_data_source, delete_data_source, query_data_source_list, set_data_source_enabled,
+    update_data_source,
+};
+use crate::command::scheduler::{
+    pause_task, query_task_health_list, query_task_list, query_task_run_list, refresh_ani_data,
+    reschedule_task, resume_task,
+};
 use crate::command::service::{
-    cancel_collect_ani_item, collect_ani_item, query_ani_history_list,
-    query_favorite_ani_update_list, query_today_update_ani_list, query_watched_ani_item_list,
-    save_ani_item_data, watch_ani_item,
+    cancel_collect_ani_item, collect_ani_item, export_followed_calendar, mute_series_notification,
+    query_ani_history_list, query_favorite_ani_update_list, query_notify_delivery_list,
+    query_series_progress_list, query_today_update_ani_list, query_upcoming_ani_list,
+    query_watched_ani_item_list, query_week_schedule_ani_list, reload_title_alias_rules,
+    save_ani_item_data, unwatch_ani_episodes, watch_ani_episodes, watch_ani_item,
+};
+use crate::configuration::{
+    app_config_dir, init_config, load_title_alias_rules, ConfigStatus, NotificationConfig,
 };
-use crate::configuration::init_config;
 use crate::db::sqlite::init_and_migrate_db;
-use crate::startup::{init_logger, init_system_tray, start_async_timer_task};
+use crate::notify::desktop::DesktopChannel;
+use crate::notify::{build_push_channels, Notifier};
+use crate::server::start_http_server;
+use crate::startup::{init_logger, init_system_tray, spawn_refresh_tray, start_async_timer_task};
 use crate::state::AppState;
-use command::platforms::agedm::{fetch_agedm_ani_data, fetch_agedm_image};
-use command::platforms::bilibili::{fetch_bilibili_ani_data, fetch_bilibili_image};
-use command::platforms::iqiyi::{fetch_iqiyi_ani_data, fetch_iqiyi_image};
-use command::platforms::mikanani::{fetch_mikanani_ani_data, fetch_mikanani_image};
-use command::platforms::tencent::{fetch_qq_ani_data, fetch_qq_image};
-use command::platforms::youku::{fetch_youku_ani_data, fetch_youku_image};
-use log::info;
+use crate::tasks::scheduler::Scheduler;
+use crate::utils::date_utils::clock_from_env;
+use crate::utils::http_client::HttpClient;
+use crate::utils::title::set_alias_rules;
+use crate::validation::{load_checked_configuration, ConfigDiagnostic};
+use command::platforms::{fetch_ani_data, fetch_ani_image};
+use log::{error, info, warn};
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::async_runtime::block_on;
 use tauri::Manager;
 use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 use tauri_plugin_single_instance::init;
 
+/// 退出时等待执行中任务的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -37,15 +61,77 @@ pub fn run() {
             init_logger(app)?;
             //托盘初始化
             init_system_tray(app)?;
-            // 初始化配置
-            let config_path = init_config(app).expect("配置文件初始化失败!");
+            // 初始化配置，出错时记录下来供前端查询，应用继续以现有配置启动
+            let mut diagnostics = vec![];
+            let config_path = match init_config(app) {
+                Ok(path) => path,
+                Err(e) => {
+                    error!("配置文件初始化失败：{e}");
+                    diagnostics.push(ConfigDiagnostic::error(
+                        "",
+                        None,
+                        format!("配置文件初始化失败：{e}"),
+                    ));
+                    app_config_dir(app.handle())
+                }
+            };
+            // 加载标题别名规则（需在数据库初始化前完成，迁移后会按规则重建番剧匹配键）
+            match load_title_alias_rules(&config_path) {
+                Ok(rules) => set_alias_rules(&rules),
+                Err(e) => warn!("标题别名规则加载失败，将不使用别名：{e}"),
+            }
             let handle = app.handle();
+            // 检查配置，检查结果供前端查询
+            let (config, checked) = load_checked_configuration(&config_path, None);
+            diagnostics.extend(checked);
+            // 创建共享的 HTTP 客户端，代理、证书等配置有误时使用默认配置
+            let http_config = config
+                .as_ref()
+                .map(|c| c.http_client.clone())
+                .unwrap_or_default();
+            let http = HttpClient::new(&http_config, &config_path).unwrap_or_else(|e| {
+                diagnostics.push(ConfigDiagnostic::error(
+                    "http_client",
+                    None,
+                    format!("{e:#}，使用默认的 HTTP 客户端配置"),
+                ));
+                HttpClient::default()
+            });
+            for diagnostic in &diagnostics {
+                warn!("配置检查：{diagnostic}");
+            }
+            let config_status = Arc::new(ConfigStatus::default());
+            config_status.set_diagnostics(diagnostics);
+            handle.manage(config_status);
             // 同步执行数据库初始化
             let pool = block_on(init_and_migrate_db(handle))?;
             // 注入全局状态
-            handle.manage(Arc::new(AppState { db: Arc::new(pool) }));
+            let state = Arc::new(AppState {
+                db: Arc::new(pool),
+                clock: clock_from_env(),
+                http: Arc::new(http),
+            });
+            handle.manage(state.clone());
             info!("数据库连接池已注册到全局状态");
-            start_async_timer_task(handle, config_path);
+            // 按配置启动本地 HTTP 接口、创建更新通知
+            let http = state.http.clone();
+            let notification = match config {
+                Some(config) => {
+                    let app = handle.clone();
+                    start_http_server(state, &config.http_server, move || spawn_refresh_tray(&app));
+                    config.notification
+                }
+                None => {
+                    warn!("读取配置失败，本地 HTTP 接口不会启动，通知使用默认配置");
+                    NotificationConfig::default()
+                }
+            };
+            let mut channels = build_push_channels(&notification.push, &http);
+            if notification.desktop {
+                channels.insert(0, Arc::new(DesktopChannel::new(handle.clone())));
+            }
+            let notifier = Notifier::new(notification, channels);
+            start_async_timer_task(handle, config_path, Arc::new(notifier));
             info!("执行异步获取动漫更新数据的任务");
             Ok(())
         })
@@ -62,26 +148,39 @@ pub fn run() {
             }
         }))
         .invoke_handler(tauri::generate_handler![
-            fetch_bilibili_image,
-            fetch_bilibili_ani_data,
-            fetch_iqiyi_ani_data,
-            fetch_iqiyi_image,
-            fetch_mikanani_image,
-            fetch_mikanani_ani_data,
-            fetch_qq_image,
-            fetch_qq_ani_data,
-            fetch_youku_image,
-            fetch_youku_ani_data,
+            fetch_ani_data,
+            fetch_ani_image,
             save_ani_item_data,
             watch_ani_item,
-            fetch_agedm_ani_data,
-            fetch_agedm_image,
+            watch_ani_episodes,
+            unwatch_ani_episodes,
+            query_series_progress_list,
             query_today_update_ani_list,
+            query_week_schedule_ani_list,
+            query_upcoming_ani_list,
             query_watched_ani_item_list,
             query_favorite_ani_update_list,
             collect_ani_item,
             cancel_collect_ani_item,
             query_ani_history_list,
+            reload_title_alias_rules,
+            export_followed_calendar,
+            mute_series_notification,
+            query_notify_delivery_list,
+            refresh_ani_data,
+            query_task_list,
+            pause_task,
+            resume_task,
+            reschedule_task,
+            query_task_run_list,
+            query_task_health_list,
+            query_config_status,
+            check_config,
+            query_data_source_list,
+            add_data_source,
+            update_data_source,
+            delete_data_source,
+            set_data_source_enabled,
         ])
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
@@ -90,6 +189,14 @@ pub fn run() {
                 api.prevent_close();
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // 退出前停止调度，等待执行中的任务保存完成
+                if let Some(scheduler) = app.try_state::<Arc<Scheduler>>() {
+                    block_on(scheduler.shutdown(SHUTDOWN_TIMEOUT));
+                }
+            }
+        });
 }
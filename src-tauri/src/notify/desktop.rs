@@ -0,0 +1,121 @@
+//! 系统桌面通知
+//!
+//! 各平台直接调用系统通知接口，以便响应点击：有链接时用默认浏览器打开，否则显示主窗口。
+
+use crate::notify::{Notice, NotifyChannel};
+use anyhow::Result;
+use async_trait::async_trait;
+use log::warn;
+use tauri::{AppHandle, Manager};
+
+pub struct DesktopChannel {
+    app: AppHandle,
+}
+
+impl DesktopChannel {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+#[async_trait]
+impl NotifyChannel for DesktopChannel {
+    fn name(&self) -> &str {
+        "desktop"
+    }
+
+    async fn send(&self, notice: &Notice) -> Result<()> {
+        let app = self.app.clone();
+        let notice = notice.clone();
+        // 等待点击会阻塞线程，放到单独的线程中
+        std::thread::spawn(move || {
+            if let Err(e) = show(&app, &notice) {
+                warn!("桌面通知“{}”显示失败：{e}", notice.title);
+            }
+        });
+        Ok(())
+    }
+}
+
+/// 点击通知：打开链接或显示主窗口
+fn on_click(app: &AppHandle, url: Option<&str>) {
+    if let Some(url) = url {
+        if let Err(e) = open::that(url) {
+            warn!("打开链接 {url} 失败：{e}");
+        }
+        return;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+#[cfg(windows)]
+fn show(app: &AppHandle, notice: &Notice) -> Result<()> {
+    use tauri_winrt_notification::Toast;
+
+    // 开发模式下应用未安装，借用 PowerShell 的 AppUserModelID
+    let app_id = if tauri::is_dev() {
+        Toast::POWERSHELL_APP_ID.to_string()
+    } else {
+        app.config().identifier.clone()
+    };
+    let handle = app.clone();
+    let url = notice.url.clone();
+    Toast::new(&app_id)
+        .title(&notice.title)
+        .text1(&notice.body)
+        .on_activated(move |_| {
+            on_click(&handle, url.as_deref());
+            Ok(())
+        })
+        .show()?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn show(app: &AppHandle, notice: &Notice) -> Result<()> {
+    use mac_notification_sys::{set_application, Notification, NotificationResponse};
+    use std::sync::Once;
+
+    static SET_APPLICATION: Once = Once::new();
+    SET_APPLICATION.call_once(|| {
+        // 开发模式下应用未安装，借用终端的 bundle id
+        let bundle = if tauri::is_dev() {
+            "com.apple.Terminal".to_string()
+        } else {
+            app.config().identifier.clone()
+        };
+        if let Err(e) = set_application(&bundle) {
+            warn!("设置通知所属应用失败：{e}");
+        }
+    });
+
+    let resp = Notification::new()
+        .title(&notice.title)
+        .message(&notice.body)
+        .wait_for_click(true)
+        .send()?;
+    if matches!(resp, NotificationResponse::Click) {
+        on_click(app, notice.url.as_deref());
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show(app: &AppHandle, notice: &Notice) -> Result<()> {
+    let handle = notify_rust::Notification::new()
+        .appname(&app.package_info().name)
+        .summary(&notice.title)
+        .body(&notice.body)
+        .action("default", "打开")
+        .show()?;
+    handle.wait_for_action(|action| {
+        if action == "default" {
+            on_click(app, notice.url.as_deref());
+        }
+    });
+    Ok(())
+}
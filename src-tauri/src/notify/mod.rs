@@ -0,0 +1,687 @@
+pub mod desktop;
+pub mod smtp;
+pub mod telegram;
+pub mod template;
+pub mod webhook;
+
+use crate::command::platforms::find_platform;
+use crate::configuration::{NotificationConfig, PushChannelConfig};
+use crate::db::common::ge_db_pool;
+use crate::db::notification::{
+    episode_key, insert_notify_delivery, list_muted_series, list_notified_episodes, mark_notified,
+};
+use crate::db::po::Ani;
+use crate::db::sqlite::list_unwatched_follow_ani_between;
+use crate::state::AppState;
+use crate::utils::date_utils::{date_to_millis, Clock};
+use crate::utils::http_client::HttpClient;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveTime};
+use log::{debug, info, warn};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+
+/// 批量通知中最多列出的番剧数
+const BATCH_PREVIEW: usize = 5;
+
+/// 推送请求的超时时间
+pub const PUSH_TIMEOUT: StdDuration = StdDuration::from_secs(15);
+
+/// 第一次重试前的等待时间，之后每次翻倍
+const RETRY_BASE_DELAY: StdDuration = StdDuration::from_secs(2);
+
+/// 一条待发送的通知
+#[derive(Debug, Clone)]
+pub struct Notice {
+    pub title: String,
+    pub body: String,
+    /// 点击通知后打开的链接，为空时打开主窗口
+    pub url: Option<String>,
+    /// 通知涉及的更新，推送渠道据此渲染模板
+    pub items: Vec<Ani>,
+}
+
+/// 通知渠道
+#[async_trait]
+pub trait NotifyChannel: Send + Sync {
+    /// 渠道名称，用于日志和投递记录
+    fn name(&self) -> &str;
+
+    /// 是否接收合并后的通知，为 false 时每集单独发送
+    fn batched(&self) -> bool {
+        true
+    }
+
+    async fn send(&self, notice: &Notice) -> Result<()>;
+}
+
+/// 免打扰时段，可跨越零点（如 23:00-08:00）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// 解析 "HH:MM-HH:MM"，空字符串表示不启用
+    pub fn parse(s: &str) -> Result<Option<Self>, String> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(None);
+        }
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("免打扰时段格式错误：{s}，应为 HH:MM-HH:MM"))?;
+        let parse = |t: &str| {
+            NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                .map_err(|e| format!("免打扰时段 {s} 中的时间 {t} 无效：{e}"))
+        };
+        Ok(Some(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        }))
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// 关注番剧的更新通知：过滤已看、已通知、关闭通知的番剧，处理免打扰和批量合并
+pub struct Notifier {
+    config: NotificationConfig,
+    quiet_hours: Option<QuietHours>,
+    channels: Vec<Arc<dyn NotifyChannel>>,
+    retry_base_delay: StdDuration,
+    /// 同一时间只进行一轮检查，避免多个任务同时保存后重复发送
+    round: Mutex<()>,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationConfig, channels: Vec<Arc<dyn NotifyChannel>>) -> Self {
+        let quiet_hours = QuietHours::parse(&config.quiet_hours).unwrap_or_else(|e| {
+            warn!("{e}，将不启用免打扰");
+            None
+        });
+        Self {
+            config,
+            quiet_hours,
+            channels,
+            retry_base_delay: RETRY_BASE_DELAY,
+            round: Mutex::new(()),
+        }
+    }
+
+    /// 检查关注番剧的新更新并发送通知，返回本轮发送成功的剧集数
+    ///
+    /// 每个渠道分别记录已发送的剧集，发送失败的剧集在下一轮检查时重发；各渠道并发发送，互不影响。
+    /// 免打扰期间不发送也不记录，结束后的下一次检查会补发（只补发昨天和今天的更新）
+    pub async fn notify_new_updates(&self, state: &AppState) -> Result<usize> {
+        if !self.config.enabled || self.channels.is_empty() {
+            return Ok(0);
+        }
+        let _round = self.round.lock().await;
+        let now = state.clock.now();
+        if let Some(quiet) = self.quiet_hours {
+            if quiet.contains(now.time()) {
+                debug!("免打扰时段内，暂不发送更新通知");
+                return Ok(0);
+            }
+        }
+
+        // 1. 昨天和今天尚未观看的关注更新
+        let pool = ge_db_pool(&state.db);
+        let today = state.clock.today();
+        let start = date_to_millis(today - Duration::days(1))?;
+        let end = date_to_millis(today + Duration::days(1))?;
+        let candidates = list_unwatched_follow_ani_between(pool, start, end).await?;
+
+        // 2. 去掉关闭通知的番剧
+        let muted = list_muted_series(pool).await?;
+        let candidates: Arc<Vec<Ani>> = Arc::new(
+            candidates
+                .into_iter()
+                .filter(|ani| ani.series_id.is_some_and(|id| !muted.contains(&id)))
+                .collect(),
+        );
+        if candidates.is_empty() {
+            return Ok(0);
+        }
+
+        // 3. 各渠道并发发送各自尚未发送的剧集
+        let mut rounds = JoinSet::new();
+        for channel in &self.channels {
+            let round = ChannelRound {
+                channel: channel.clone(),
+                db: state.db.clone(),
+                clock: state.clock.clone(),
+                retry_times: self.config.retry_times,
+                retry_base_delay: self.retry_base_delay,
+                batch_threshold: self.config.batch_threshold,
+            };
+            rounds.spawn(round.run(candidates.clone()));
+        }
+        let mut sent = HashSet::new();
+        while let Some(result) = rounds.join_next().await {
+            match result {
+                Ok(Ok(ids)) => sent.extend(ids),
+                Ok(Err(e)) => warn!("发送更新通知失败：{e:#}"),
+                Err(e) => warn!("发送更新通知的任务异常退出：{e}"),
+            }
+        }
+        if !sent.is_empty() {
+            info!("已发送 {} 集关注番剧的更新通知", sent.len());
+        }
+        Ok(sent.len())
+    }
+}
+
+/// 一个渠道一轮的发送
+struct ChannelRound {
+    channel: Arc<dyn NotifyChannel>,
+    db: Arc<SqlitePool>,
+    clock: Arc<dyn Clock>,
+    retry_times: u8,
+    retry_base_delay: StdDuration,
+    batch_threshold: usize,
+}
+
+impl ChannelRound {
+    /// 发送该渠道尚未发送的剧集，返回发送成功的更新记录 ID
+    ///
+    /// 某条通知重试后仍失败时，本轮不再发送该渠道的其余通知，留到下一轮
+    async fn run(self, candidates: Arc<Vec<Ani>>) -> Result<Vec<i64>> {
+        let pool = self.db.as_ref();
+        let name = self.channel.name();
+        let notified = list_notified_episodes(pool, name).await?;
+        let pending: Vec<Ani> = candidates
+            .iter()
+            .filter(|ani| {
+                let series_id = ani.series_id.unwrap_or_default();
+                !notified.contains(&(series_id, episode_key(ani)))
+            })
+            .cloned()
+            .collect();
+
+        // 桌面通知按阈值合并，推送渠道每集一条
+        let threshold = if self.channel.batched() {
+            self.batch_threshold
+        } else {
+            usize::MAX
+        };
+        let notices = build_notices(&pending, threshold);
+        let mut sent = Vec::new();
+        for (i, notice) in notices.iter().enumerate() {
+            if !self.deliver(notice).await {
+                let rest = notices.len() - i - 1;
+                if rest > 0 {
+                    warn!("{name} 暂时无法发送，其余 {rest} 条通知留到下一轮");
+                }
+                break;
+            }
+            let now = self.clock.now_millis();
+            for ani in &notice.items {
+                let series_id = ani.series_id.unwrap_or_default();
+                mark_notified(pool, name, series_id, &episode_key(ani), now).await?;
+                sent.push(ani.id);
+            }
+        }
+        Ok(sent)
+    }
+
+    /// 发送一条通知，失败后按指数退避重试，并记录最终结果，返回是否发送成功
+    async fn deliver(&self, notice: &Notice) -> bool {
+        let channel = self.channel.as_ref();
+        let mut attempts = 0;
+        let mut delay = self.retry_base_delay;
+        let error = loop {
+            attempts += 1;
+            match channel.send(notice).await {
+                Ok(()) => break None,
+                Err(e) if attempts <= self.retry_times as u32 => {
+                    warn!(
+                        "{} 通知“{}”发送失败：{e}，{}秒后重试 {attempts}/{}",
+                        channel.name(),
+                        notice.title,
+                        delay.as_secs(),
+                        self.retry_times
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(e) => {
+                    warn!("{} 通知“{}”发送失败：{e:#}", channel.name(), notice.title);
+                    break Some(format!("{e:#}"));
+                }
+            }
+        };
+
+        let ani_item_id = match notice.items.as_slice() {
+            [ani] => Some(ani.id),
+            _ => None,
+        };
+        if let Err(e) = insert_notify_delivery(
+            &self.db,
+            channel.name(),
+            ani_item_id,
+            &notice.title,
+            error.as_deref(),
+            attempts,
+            self.clock.now_millis(),
+        )
+        .await
+        {
+            warn!("{e}");
+        }
+        error.is_none()
+    }
+}
+
+/// 按配置创建推送渠道，配置有误的渠道跳过；推送请求使用共享的 HTTP 客户端
+pub fn build_push_channels(
+    configs: &[PushChannelConfig],
+    http: &Arc<HttpClient>,
+) -> Vec<Arc<dyn NotifyChannel>> {
+    let mut channels: Vec<Arc<dyn NotifyChannel>> = Vec::new();
+    for config in configs {
+        let channel: Result<Arc<dyn NotifyChannel>> = match config {
+            PushChannelConfig::Webhook(c) => {
+                webhook::WebhookChannel::new(c.clone(), http.clone()).map(|v| Arc::new(v) as _)
+            }
+            PushChannelConfig::Telegram(c) => {
+                Ok(Arc::new(telegram::TelegramChannel::new(c.clone(), http.clone())) as _)
+            }
+            PushChannelConfig::Smtp(c) => {
+                smtp::SmtpChannel::new(c.clone(), http).map(|v| Arc::new(v) as _)
+            }
+        };
+        match channel {
+            Ok(v) => {
+                info!("已启用 {} 推送渠道", v.name());
+                channels.push(v);
+            }
+            Err(e) => warn!("推送渠道配置有误，已跳过：{e:#}"),
+        }
+    }
+    channels
+}
+
+/// 生成通知：数量超过 batch_threshold 时合并为一条
+pub fn build_notices(list: &[Ani], batch_threshold: usize) -> Vec<Notice> {
+    if list.len() <= batch_threshold.max(1) {
+        return list
+            .iter()
+            .map(|ani| Notice {
+                title: format!("《{}》更新了", ani.title),
+                body: format!("{} · {}", ani.update_info, platform_name(&ani.platform)),
+                url: Some(ani.detail_url.clone()).filter(|u| !u.is_empty()),
+                items: vec![ani.clone()],
+            })
+            .collect();
+    }
+
+    let mut lines: Vec<String> = list
+        .iter()
+        .take(BATCH_PREVIEW)
+        .map(|ani| {
+            format!(
+                "《{}》{}（{}）",
+                ani.title,
+                ani.update_info,
+                platform_name(&ani.platform)
+            )
+        })
+        .collect();
+    if list.len() > BATCH_PREVIEW {
+        lines.push(format!("等 {} 部", list.len()));
+    }
+    vec![Notice {
+        title: format!("关注的番剧有 {} 集更新", list.len()),
+        body: lines.join("\n"),
+        url: None,
+        items: list.to_vec(),
+    }]
+}
+
+/// 平台展示名称
+pub fn platform_name(id: &str) -> String {
+    find_platform(id)
+        .map(|p| p.name().to_string())
+        .unwrap_or_else(|| id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures::now;
+    use crate::db::notification::{list_notify_delivery, set_series_notify_muted};
+    use crate::db::po::AniColl;
+    use crate::db::sqlite::{upsert_ani_collect, upsert_ani_info, MIGRATOR};
+    use crate::types::AniItem;
+    use crate::utils::date_utils::FixedClock;
+    use crate::utils::http_client::HttpClient;
+    use chrono::NaiveDate;
+    use sqlx::SqlitePool;
+    use std::sync::Mutex;
+
+    /// 记录发送内容的渠道
+    #[derive(Default)]
+    struct RecordChannel(Mutex<Vec<Notice>>);
+
+    #[async_trait]
+    impl NotifyChannel for RecordChannel {
+        fn name(&self) -> &str {
+            "record"
+        }
+
+        async fn send(&self, notice: &Notice) -> Result<()> {
+            self.0.lock().unwrap().push(notice.clone());
+            Ok(())
+        }
+    }
+
+    /// 每集单独推送，前 fail_times 次发送失败的渠道
+    #[derive(Default)]
+    struct FlakyChannel {
+        fail_times: usize,
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl NotifyChannel for FlakyChannel {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn batched(&self) -> bool {
+            false
+        }
+
+        async fn send(&self, notice: &Notice) -> Result<()> {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(notice.title.clone());
+            if calls.len() <= self.fail_times {
+                return Err(anyhow::anyhow!("connection refused"));
+            }
+            Ok(())
+        }
+    }
+
+    fn item(title: &str, count: &str, external_id: &str) -> AniItem {
+        AniItem {
+            title: title.to_string(),
+            update_count: count.to_string(),
+            update_info: format!("更新至第{count}话"),
+            image_url: String::new(),
+            detail_url: format!("https://www.bilibili.com/bangumi/play/ss{external_id}"),
+            update_time: "2025/07/13".to_string(),
+            platform: "bilibili".to_string(),
+            external_id: external_id.to_string(),
+            category: String::new(),
+        }
+    }
+
+    /// 关注 titles 中的番剧，每部今天更新一集
+    async fn init_state(titles: &[&str]) -> AppState {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        for (i, title) in titles.iter().enumerate() {
+            upsert_ani_info(&pool, &item(title, "12", &i.to_string()), now())
+                .await
+                .unwrap();
+            upsert_ani_collect(
+                &pool,
+                &AniColl {
+                    user_id: "".to_string(),
+                    ani_item_id: i as i64 + 1,
+                    ani_title: title.to_string(),
+                    collect_time: "2025/07/13".to_string(),
+                    is_watched: false,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        AppState {
+            db: Arc::new(pool),
+            clock: Arc::new(FixedClock::at_date(
+                NaiveDate::from_ymd_opt(2025, 7, 13).unwrap(),
+            )),
+            http: Arc::new(HttpClient::default()),
+        }
+    }
+
+    fn new_notifier(config: NotificationConfig) -> (Notifier, Arc<RecordChannel>) {
+        let channel = Arc::new(RecordChannel::default());
+        let notifier = Notifier::new(config, vec![channel.clone() as Arc<dyn NotifyChannel>]);
+        (notifier, channel)
+    }
+
+    #[tokio::test]
+    async fn test_push_retry_and_delivery_log() {
+        let state = init_state(&["凡人修仙传", "间谍过家家", "鬼灭之刃", "迷宫饭"]).await;
+        let flaky = Arc::new(FlakyChannel {
+            fail_times: 2,
+            ..Default::default()
+        });
+        let mut notifier = Notifier::new(
+            NotificationConfig {
+                retry_times: 1,
+                ..Default::default()
+            },
+            vec![flaky.clone() as Arc<dyn NotifyChannel>],
+        );
+        notifier.retry_base_delay = StdDuration::ZERO;
+
+        // 第一集两次都失败，本轮不再发送该渠道的其余通知
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 0);
+        let calls = flaky.calls.lock().unwrap().clone();
+        assert_eq!(calls, vec!["《凡人修仙传》更新了"; 2]);
+        let log = list_notify_delivery(ge_db_pool(&state.db), 10)
+            .await
+            .unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].ani_item_id, Some(1));
+        assert_eq!(log[0].channel, "flaky");
+        assert_eq!(log[0].status, "failed");
+        assert_eq!(log[0].attempts, 2);
+        assert_eq!(log[0].error.as_deref(), Some("connection refused"));
+
+        // 下一轮重发：超过合并阈值，推送渠道仍逐集发送
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 4);
+        assert_eq!(flaky.calls.lock().unwrap().len(), 6);
+        let log = list_notify_delivery(ge_db_pool(&state.db), 10)
+            .await
+            .unwrap();
+        assert_eq!(log.len(), 5);
+        assert_eq!(log.iter().filter(|d| d.status == "ok").count(), 4);
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 0);
+    }
+
+    /// 等待另一个渠道发送后才能发送的渠道
+    struct WaitChannel(Arc<tokio::sync::Notify>);
+
+    #[async_trait]
+    impl NotifyChannel for WaitChannel {
+        fn name(&self) -> &str {
+            "wait"
+        }
+
+        async fn send(&self, _notice: &Notice) -> Result<()> {
+            self.0.notified().await;
+            Ok(())
+        }
+    }
+
+    /// 发送时唤醒 WaitChannel 的渠道
+    struct WakeChannel(Arc<tokio::sync::Notify>);
+
+    #[async_trait]
+    impl NotifyChannel for WakeChannel {
+        fn name(&self) -> &str {
+            "wake"
+        }
+
+        async fn send(&self, _notice: &Notice) -> Result<()> {
+            self.0.notify_one();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_channels_send_concurrently() {
+        let state = init_state(&["凡人修仙传"]).await;
+        let notify = Arc::new(tokio::sync::Notify::new());
+        // 逐个渠道发送时，排在前面的渠道会一直等下去
+        let notifier = Notifier::new(
+            NotificationConfig::default(),
+            vec![
+                Arc::new(WaitChannel(notify.clone())) as Arc<dyn NotifyChannel>,
+                Arc::new(WakeChannel(notify)) as Arc<dyn NotifyChannel>,
+            ],
+        );
+        let sent = tokio::time::timeout(
+            StdDuration::from_secs(5),
+            notifier.notify_new_updates(&state),
+        )
+        .await
+        .expect("渠道应并发发送");
+        assert_eq!(sent.unwrap(), 1);
+        let log = list_notify_delivery(ge_db_pool(&state.db), 10)
+            .await
+            .unwrap();
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(QuietHours::parse("").unwrap(), None);
+        assert!(QuietHours::parse("23:00").is_err());
+        assert!(QuietHours::parse("25:00-08:00").is_err());
+
+        let night = QuietHours::parse("23:00-08:00").unwrap().unwrap();
+        assert!(night.contains(t(23, 30)));
+        assert!(night.contains(t(7, 59)));
+        assert!(!night.contains(t(8, 0)));
+        assert!(!night.contains(t(12, 0)));
+
+        let noon = QuietHours::parse(" 11:30 - 13:00 ").unwrap().unwrap();
+        assert!(noon.contains(t(12, 0)));
+        assert!(!noon.contains(t(13, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_notify_once_per_episode() {
+        let state = init_state(&["凡人修仙传"]).await;
+        let (notifier, channel) = new_notifier(NotificationConfig::default());
+
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 1);
+        // 已通知过的不再通知
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 0);
+
+        let sent = channel.0.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].title, "《凡人修仙传》更新了");
+        assert_eq!(sent[0].body, "更新至第12话 · 哔哩哔哩");
+        assert_eq!(
+            sent[0].url.as_deref(),
+            Some("https://www.bilibili.com/bangumi/play/ss0")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notify_without_episode_count() {
+        let state = init_state(&[]).await;
+        let pool = ge_db_pool(&state.db);
+        // mikanani 没有集数：昨天和今天的两次更新都要通知
+        for (title, date) in [
+            ("琉璃的宝石", "2025/07/12"),
+            ("琉璃的宝石（中配）", "2025/07/13"),
+        ] {
+            upsert_ani_info(
+                pool,
+                &AniItem {
+                    update_count: String::new(),
+                    update_time: date.to_string(),
+                    platform: "mikanani".to_string(),
+                    ..item(title, "", "3663")
+                },
+                now(),
+            )
+            .await
+            .unwrap();
+        }
+        upsert_ani_collect(
+            pool,
+            &AniColl {
+                user_id: "".to_string(),
+                ani_item_id: 1,
+                ani_title: "琉璃的宝石".to_string(),
+                collect_time: "2025/07/12".to_string(),
+                is_watched: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        let (notifier, channel) = new_notifier(NotificationConfig::default());
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 2);
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 0);
+        assert_eq!(channel.0.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_notify_muted_and_quiet_hours() {
+        let state = init_state(&["凡人修仙传", "间谍过家家"]).await;
+        let pool = ge_db_pool(&state.db);
+        set_series_notify_muted(pool, 1, true).await.unwrap();
+        assert!(set_series_notify_muted(pool, 99, true).await.is_err());
+
+        // FixedClock 固定在中午 12 点
+        let (notifier, channel) = new_notifier(NotificationConfig {
+            quiet_hours: "11:00-13:00".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 0);
+        assert!(channel.0.lock().unwrap().is_empty());
+
+        // 免打扰结束后补发，关闭通知的番剧不发送
+        let (notifier, channel) = new_notifier(NotificationConfig::default());
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 1);
+        assert_eq!(channel.0.lock().unwrap()[0].title, "《间谍过家家》更新了");
+    }
+
+    #[tokio::test]
+    async fn test_notify_batch() {
+        let titles = [
+            "凡人修仙传",
+            "间谍过家家",
+            "鬼灭之刃",
+            "葬送的芙莉莲",
+            "药屋少女的呢喃",
+            "迷宫饭",
+        ];
+        let state = init_state(&titles).await;
+        let (notifier, channel) = new_notifier(NotificationConfig::default());
+
+        assert_eq!(notifier.notify_new_updates(&state).await.unwrap(), 6);
+        let sent = channel.0.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].title, "关注的番剧有 6 集更新");
+        assert_eq!(sent[0].url, None);
+        assert!(sent[0]
+            .body
+            .starts_with("《凡人修仙传》更新至第12话（哔哩哔哩）\n"));
+        assert!(sent[0].body.ends_with("等 6 部"));
+    }
+}
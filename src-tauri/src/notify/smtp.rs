@@ -0,0 +1,168 @@
+use crate::configuration::SmtpConfig;
+use crate::notify::template::{plain, render, to_ani_item};
+use crate::notify::{Notice, NotifyChannel, PUSH_TIMEOUT};
+use crate::utils::http_client::HttpClient;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Certificate, Tls, TlsParameters};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+/// 邮件：通过 SMTP 服务器发送纯文本邮件
+pub struct SmtpChannel {
+    config: SmtpConfig,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpChannel {
+    /// 加密连接信任 http_client 中配置的根证书（SMTP 不经过 HTTP 代理）
+    pub fn new(config: SmtpConfig, http: &HttpClient) -> Result<Self> {
+        let from = config
+            .from
+            .parse::<Mailbox>()
+            .context(format!("发件人地址无效：{}", config.from))?;
+        let to = config
+            .to
+            .iter()
+            .map(|addr| {
+                addr.parse::<Mailbox>()
+                    .context(format!("收件人地址无效：{addr}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if to.is_empty() {
+            return Err(anyhow!("邮件渠道 {} 没有配置收件人", config.name));
+        }
+
+        let tls_parameters = || -> Result<TlsParameters> {
+            let mut builder = TlsParameters::builder(config.host.clone());
+            if let Some(pem) = http.ca_cert() {
+                builder = builder.add_root_certificate(Certificate::from_pem(pem)?);
+            }
+            Ok(builder.build()?)
+        };
+        let builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host);
+        let builder = match config.security.as_str() {
+            "starttls" => builder.tls(Tls::Required(tls_parameters()?)),
+            "tls" => builder.tls(Tls::Wrapper(tls_parameters()?)),
+            "none" => builder,
+            other => return Err(anyhow!("不支持的 SMTP 加密方式：{other}")),
+        };
+        let mut builder = builder.port(config.port).timeout(Some(PUSH_TIMEOUT));
+        if !config.username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.username.clone(),
+                config.password.clone(),
+            ));
+        }
+        Ok(Self {
+            from,
+            to,
+            transport: builder.build(),
+            config,
+        })
+    }
+
+    /// 生成邮件
+    pub fn message(&self, notice: &Notice) -> Result<Message> {
+        let item = notice
+            .items
+            .first()
+            .map(to_ani_item)
+            .ok_or_else(|| anyhow!("通知中没有更新信息"))?;
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(render(&self.config.subject_template, &item, plain))
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        Ok(builder.body(render(&self.config.body_template, &item, plain))?)
+    }
+}
+
+#[async_trait]
+impl NotifyChannel for SmtpChannel {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn batched(&self) -> bool {
+        false
+    }
+
+    async fn send(&self, notice: &Notice) -> Result<()> {
+        self.transport.send(self.message(notice)?).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::po::Ani;
+
+    fn config() -> SmtpConfig {
+        SmtpConfig {
+            name: "smtp".to_string(),
+            host: "smtp.example.com".to_string(),
+            port: 587,
+            security: "starttls".to_string(),
+            username: "bot".to_string(),
+            password: "secret".to_string(),
+            from: "AniTodo <bot@example.com>".to_string(),
+            to: vec!["me@example.com".to_string()],
+            subject_template: "《{{title}}》{{update_info}}".to_string(),
+            body_template: "{{platform_name}} {{detail_url}}".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_smtp_message() {
+        let channel = SmtpChannel::new(config(), &HttpClient::default()).unwrap();
+        let notice = Notice {
+            title: "《凡人修仙传》更新了".to_string(),
+            body: String::new(),
+            url: None,
+            items: vec![Ani {
+                id: 1,
+                title: "凡人修仙传".to_string(),
+                update_count: "152".to_string(),
+                update_info: "更新至第152话".to_string(),
+                image_url: String::new(),
+                detail_url: "https://www.bilibili.com/bangumi/play/ep152".to_string(),
+                update_time: 0,
+                platform: "bilibili".to_string(),
+                series_id: Some(1),
+                category: "anime".to_string(),
+            }],
+        };
+        let message = channel.message(&notice).unwrap();
+        let envelope = message.envelope();
+        assert_eq!(envelope.from().unwrap().to_string(), "bot@example.com");
+        assert_eq!(envelope.to()[0].to_string(), "me@example.com");
+        let text = String::from_utf8(message.formatted()).unwrap();
+        assert!(text.contains("To: me@example.com"));
+
+        // 配置有误
+        assert!(SmtpChannel::new(
+            SmtpConfig {
+                to: vec![],
+                ..config()
+            },
+            &HttpClient::default()
+        )
+        .is_err());
+        assert!(SmtpChannel::new(
+            SmtpConfig {
+                security: "ssl3".to_string(),
+                ..config()
+            },
+            &HttpClient::default()
+        )
+        .is_err());
+    }
+}
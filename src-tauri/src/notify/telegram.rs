@@ -0,0 +1,151 @@
+use crate::configuration::TelegramConfig;
+use crate::notify::template::{plain, render, to_ani_item};
+use crate::notify::{Notice, NotifyChannel, PUSH_TIMEOUT};
+use crate::utils::http_client::HttpClient;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Method;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Telegram 机器人：调用 sendMessage 发送文本消息
+pub struct TelegramChannel {
+    config: TelegramConfig,
+    http: Arc<HttpClient>,
+}
+
+impl TelegramChannel {
+    /// 通过共享的 HTTP 客户端发送，使用配置的代理、证书和 User-Agent
+    pub fn new(config: TelegramConfig, http: Arc<HttpClient>) -> Self {
+        Self { config, http }
+    }
+}
+
+#[async_trait]
+impl NotifyChannel for TelegramChannel {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn batched(&self) -> bool {
+        false
+    }
+
+    async fn send(&self, notice: &Notice) -> Result<()> {
+        let item = notice
+            .items
+            .first()
+            .map(to_ani_item)
+            .ok_or_else(|| anyhow!("通知中没有更新信息"))?;
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            self.config.api_base.trim_end_matches('/'),
+            self.config.bot_token
+        );
+        let body = json!({
+            "chat_id": self.config.chat_id,
+            "text": render(&self.config.template, &item, plain),
+        });
+        let resp = self
+            .http
+            .request(Method::POST, &url)
+            .timeout(PUSH_TIMEOUT)
+            .json(&body)
+            .send()
+            .await?;
+        let status = resp.status();
+        // 接口出错时返回 {"ok": false, "description": "..."}
+        let result: Value = resp.json().await.unwrap_or_default();
+        if !status.is_success() || result["ok"] != Value::Bool(true) {
+            return Err(anyhow!(
+                "Telegram 接口返回错误（{status}）：{}",
+                result["description"].as_str().unwrap_or("未知错误")
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::po::Ani;
+    use axum::extract::{Path, State};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use std::sync::Mutex;
+    use tokio::net::TcpListener;
+
+    type Received = Arc<Mutex<Vec<(String, Value)>>>;
+
+    /// 模拟 Telegram 接口：token 为 "bad" 时返回错误
+    async fn spawn_api(received: Received) -> String {
+        async fn send_message(
+            State(received): State<Received>,
+            Path(bot): Path<String>,
+            Json(body): Json<Value>,
+        ) -> Json<Value> {
+            received.lock().unwrap().push((bot.clone(), body));
+            if bot == "botbad" {
+                Json(json!({ "ok": false, "description": "Unauthorized" }))
+            } else {
+                Json(json!({ "ok": true }))
+            }
+        }
+        let app = Router::new()
+            .route("/{bot}/sendMessage", post(send_message))
+            .with_state(received);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{addr}/")
+    }
+
+    fn channel(api_base: &str, bot_token: &str) -> TelegramChannel {
+        TelegramChannel::new(
+            TelegramConfig {
+                name: "telegram".to_string(),
+                api_base: api_base.to_string(),
+                bot_token: bot_token.to_string(),
+                chat_id: "-100".to_string(),
+                template: "《{{title}}》{{update_info}}\n{{platform_name}}".to_string(),
+            },
+            Arc::new(HttpClient::default()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_telegram_send() {
+        let received = Received::default();
+        let api_base = spawn_api(received.clone()).await;
+        let notice = Notice {
+            title: "《凡人修仙传》更新了".to_string(),
+            body: String::new(),
+            url: None,
+            items: vec![Ani {
+                id: 1,
+                title: "凡人修仙传".to_string(),
+                update_count: "152".to_string(),
+                update_info: "更新至第152话".to_string(),
+                image_url: String::new(),
+                detail_url: String::new(),
+                update_time: 0,
+                platform: "bilibili".to_string(),
+                series_id: Some(1),
+                category: "anime".to_string(),
+            }],
+        };
+
+        channel(&api_base, "123:abc").send(&notice).await.unwrap();
+        let err = channel(&api_base, "bad").send(&notice).await.unwrap_err();
+        assert!(err.to_string().contains("Unauthorized"));
+
+        let received = received.lock().unwrap();
+        assert_eq!(received[0].0, "bot123:abc");
+        assert_eq!(received[0].1["chat_id"], "-100");
+        assert_eq!(
+            received[0].1["text"],
+            "《凡人修仙传》更新至第152话\n哔哩哔哩"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! 推送内容模板：把 `{{字段}}` 占位符替换为更新信息
+
+use crate::db::po::Ani;
+use crate::notify::platform_name;
+use crate::types::AniItem;
+use crate::utils::date_utils::format_timestamp_millis2;
+
+/// 数据库中的更新记录转换为抓取时的更新信息
+pub fn to_ani_item(ani: &Ani) -> AniItem {
+    AniItem {
+        title: ani.title.clone(),
+        update_count: ani.update_count.clone(),
+        update_info: ani.update_info.clone(),
+        image_url: ani.image_url.clone(),
+        detail_url: ani.detail_url.clone(),
+        update_time: format_timestamp_millis2(ani.update_time, "%Y/%m/%d"),
+        platform: ani.platform.clone(),
+        external_id: String::new(),
+        category: ani.category.clone(),
+    }
+}
+
+/// 渲染模板，占位符的值先经过 escape 处理，未知的占位符原样保留
+pub fn render(template: &str, item: &AniItem, escape: impl Fn(&str) -> String) -> String {
+    let fields = [
+        ("title", item.title.as_str()),
+        ("update_count", item.update_count.as_str()),
+        ("update_info", item.update_info.as_str()),
+        ("image_url", item.image_url.as_str()),
+        ("detail_url", item.detail_url.as_str()),
+        ("update_time", item.update_time.as_str()),
+        ("platform", item.platform.as_str()),
+    ];
+    let mut out = template.to_string();
+    for (key, value) in fields {
+        out = out.replace(&format!("{{{{{key}}}}}"), &escape(value));
+    }
+    out.replace("{{platform_name}}", &escape(&platform_name(&item.platform)))
+}
+
+/// 按原样输出
+pub fn plain(s: &str) -> String {
+    s.to_string()
+}
+
+/// 转义为 JSON 字符串的内容（不含两侧引号）
+pub fn json_escape(s: &str) -> String {
+    let quoted = serde_json::Value::String(s.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item() -> AniItem {
+        AniItem {
+            title: "Re:Zero \"第三季\"".to_string(),
+            update_count: "12".to_string(),
+            update_info: "更新至第12话".to_string(),
+            image_url: String::new(),
+            detail_url: "https://www.bilibili.com/bangumi/play/ep12".to_string(),
+            update_time: "2025/07/13".to_string(),
+            platform: "bilibili".to_string(),
+            external_id: String::new(),
+            category: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let text = render(
+            "《{{title}}》{{update_info}} {{platform_name}} {{unknown}}",
+            &item(),
+            plain,
+        );
+        assert_eq!(
+            text,
+            "《Re:Zero \"第三季\"》更新至第12话 哔哩哔哩 {{unknown}}"
+        );
+
+        let json = render(
+            r#"{"text": "{{title}}\n{{detail_url}}"}"#,
+            &item(),
+            json_escape,
+        );
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value["text"],
+            "Re:Zero \"第三季\"\nhttps://www.bilibili.com/bangumi/play/ep12"
+        );
+    }
+}
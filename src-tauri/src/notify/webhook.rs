@@ -0,0 +1,188 @@
+use crate::configuration::WebhookConfig;
+use crate::notify::template::{json_escape, render, to_ani_item};
+use crate::notify::{Notice, NotifyChannel, PUSH_TIMEOUT};
+use crate::utils::http_client::HttpClient;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use std::sync::Arc;
+
+/// 通用 Webhook：每集发送一次 JSON 请求
+pub struct WebhookChannel {
+    config: WebhookConfig,
+    method: Method,
+    http: Arc<HttpClient>,
+}
+
+impl WebhookChannel {
+    /// 通过共享的 HTTP 客户端发送，使用配置的代理、证书和 User-Agent
+    pub fn new(config: WebhookConfig, http: Arc<HttpClient>) -> Result<Self> {
+        let method = Method::from_bytes(config.method.to_uppercase().as_bytes()).context(
+            format!("Webhook {} 的请求方法无效：{}", config.name, config.method),
+        )?;
+        Ok(Self {
+            config,
+            method,
+            http,
+        })
+    }
+
+    /// 生成请求体，未配置模板时发送整条更新信息
+    pub fn payload(&self, notice: &Notice) -> Result<String> {
+        let item = notice
+            .items
+            .first()
+            .map(to_ani_item)
+            .ok_or_else(|| anyhow!("通知中没有更新信息"))?;
+        match &self.config.template {
+            Some(template) => Ok(render(template, &item, json_escape)),
+            None => Ok(serde_json::to_string(&item)?),
+        }
+    }
+}
+
+#[async_trait]
+impl NotifyChannel for WebhookChannel {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn batched(&self) -> bool {
+        false
+    }
+
+    async fn send(&self, notice: &Notice) -> Result<()> {
+        let mut req = self
+            .http
+            .request(self.method.clone(), &self.config.url)
+            .timeout(PUSH_TIMEOUT)
+            .header(CONTENT_TYPE, "application/json")
+            .body(self.payload(notice)?);
+        for (key, value) in &self.config.headers {
+            req = req.header(key, value);
+        }
+        req.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::HttpClientConfig;
+    use crate::db::po::Ani;
+    use axum::extract::State;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::Router;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tokio::net::TcpListener;
+
+    type Received = Arc<Mutex<Vec<(Option<String>, Option<String>, String)>>>;
+
+    /// 启动记录请求头和请求体的服务，返回地址
+    async fn spawn_receiver(received: Received) -> String {
+        async fn hook(State(received): State<Received>, headers: HeaderMap, body: String) {
+            let auth = headers
+                .get("authorization")
+                .map(|v| v.to_str().unwrap().to_string());
+            let agent = headers
+                .get("user-agent")
+                .map(|v| v.to_str().unwrap().to_string());
+            received.lock().unwrap().push((auth, agent, body));
+        }
+        let app = Router::new()
+            .route("/hook", post(hook))
+            .with_state(received);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{addr}/hook")
+    }
+
+    fn notice() -> Notice {
+        Notice {
+            title: "《凡人修仙传》更新了".to_string(),
+            body: String::new(),
+            url: None,
+            items: vec![Ani {
+                id: 1,
+                title: "凡人修仙传".to_string(),
+                update_count: "152".to_string(),
+                update_info: "更新至第152话".to_string(),
+                image_url: String::new(),
+                detail_url: "https://www.bilibili.com/bangumi/play/ep152".to_string(),
+                update_time: 0,
+                platform: "bilibili".to_string(),
+                series_id: Some(1),
+                category: "anime".to_string(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_send() {
+        let received = Received::default();
+        let url = spawn_receiver(received.clone()).await;
+        let http = Arc::new(
+            HttpClient::new(
+                &HttpClientConfig {
+                    user_agent: "AniTodo/1.0".to_string(),
+                    ..HttpClientConfig::default()
+                },
+                std::path::Path::new(""),
+            )
+            .unwrap(),
+        );
+        let channel = WebhookChannel::new(
+            WebhookConfig {
+                name: "team".to_string(),
+                url: url.clone(),
+                method: "post".to_string(),
+                headers: HashMap::from([("Authorization".to_string(), "Bearer t".to_string())]),
+                template: Some(r#"{"msg": "{{title}} {{update_info}}"}"#.to_string()),
+            },
+            http.clone(),
+        )
+        .unwrap();
+        channel.send(&notice()).await.unwrap();
+
+        // 未配置模板时发送整条更新信息
+        let channel = WebhookChannel::new(
+            WebhookConfig {
+                name: "raw".to_string(),
+                url,
+                method: "POST".to_string(),
+                headers: HashMap::new(),
+                template: None,
+            },
+            http.clone(),
+        )
+        .unwrap();
+        channel.send(&notice()).await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received[0].0.as_deref(), Some("Bearer t"));
+        // 使用配置的 User-Agent
+        assert_eq!(received[0].1.as_deref(), Some("AniTodo/1.0"));
+        assert_eq!(received[0].2, r#"{"msg": "凡人修仙传 更新至第152话"}"#);
+        let raw: serde_json::Value = serde_json::from_str(&received[1].2).unwrap();
+        assert_eq!(raw["update_count"], "152");
+        assert_eq!(raw["platform"], "bilibili");
+
+        // 无效的请求方法
+        assert!(WebhookChannel::new(
+            WebhookConfig {
+                name: "bad".to_string(),
+                url: "http://127.0.0.1/".to_string(),
+                method: "GE T".to_string(),
+                headers: HashMap::new(),
+                template: None,
+            },
+            http
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,526 @@
+//! 本地 HTTP 接口，供脚本、局域网设备、看板等在应用外调用
+//!
+//! 与 Tauri 命令共用同一套业务逻辑，返回同样的 `ApiResponse` 结构：
+//! - `GET  /api/ani/today`：今日更新列表
+//! - `GET  /api/ani/favorite`：关注动漫今日更新列表
+//! - `POST /api/ani/collect`：关注动漫，请求体 `{"aniId": 1, "aniTitle": "..."}`
+//! - `POST /api/ani/watch`：标记已看，请求体 `{"aniId": 1}`
+//! - `GET  /api/ani/history?page=1&pageSize=20`：历史更新列表
+//! - `GET  /calendar.ics`：关注番剧的更新日历，可在日历应用中订阅
+//! - `GET  /feeds/{today|follow}.{atom|rss}`：今日更新、关注更新的订阅源
+//!
+//! 配置了访问令牌时，请求需携带 `Authorization: Bearer <token>`；
+//! 日历、RSS 阅读器无法设置请求头，也可以在地址后加 `?token=<token>`
+
+use crate::command::service::{
+    ani_history_list, collect_ani, favorite_ani_update_list, today_update_ani_list, watch_ani,
+};
+use crate::configuration::HttpServerConfig;
+use crate::feed::ics::build_followed_calendar;
+use crate::feed::rss::{build_feed, FeedFormat, FeedKind};
+use crate::state::AppState;
+use crate::types::ApiResponse;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{FromRef, Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use log::{error, info, warn};
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+type HttpReply = (StatusCode, Json<ApiResponse>);
+
+/// 接口的共享状态
+#[derive(Clone)]
+pub struct ServerState {
+    pub app: Arc<AppState>,
+    /// 访问令牌，为空时不校验
+    pub token: Option<String>,
+    /// 关注、标记已看等修改数据后调用，用于刷新托盘菜单
+    pub on_change: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl FromRef<ServerState> for Arc<AppState> {
+    fn from_ref(state: &ServerState) -> Self {
+        state.app.clone()
+    }
+}
+
+/// 通过地址传递的访问令牌
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// 关注动漫的请求体（字段与前端调用 Tauri 命令时的参数一致）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectReq {
+    pub ani_id: i64,
+    pub ani_title: String,
+}
+
+/// 标记已看的请求体
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchReq {
+    pub ani_id: i64,
+}
+
+/// 分页查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageQuery {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_page_size")]
+    pub page_size: i64,
+}
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_page_size() -> i64 {
+    20
+}
+
+/// 业务失败时返回 500，响应体仍是 `ApiResponse`
+fn reply(resp: ApiResponse) -> HttpReply {
+    let code = if resp.status == "ok" {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    (code, Json(resp))
+}
+
+/// 请求参数有误时返回 400
+fn bad_request(msg: impl ToString) -> HttpReply {
+    (StatusCode::BAD_REQUEST, Json(ApiResponse::err(msg)))
+}
+
+/// 校验访问令牌，请求头和地址中的令牌任一匹配即可
+async fn require_token(State(server): State<ServerState>, req: Request, next: Next) -> Response {
+    let Some(token) = server.token.as_deref() else {
+        return next.run(req).await;
+    };
+    let bearer = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let query = Query::<TokenQuery>::try_from_uri(req.uri())
+        .ok()
+        .and_then(|Query(q)| q.token);
+    if token_matches(bearer, token) | token_matches(query.as_deref(), token) {
+        next.run(req).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json::<ApiResponse>(ApiResponse::err("访问令牌无效")),
+        )
+            .into_response()
+    }
+}
+
+/// 比较访问令牌：比较全部字节，耗时与不匹配的位置无关，避免通过响应时间逐位猜出令牌
+fn token_matches(given: Option<&str>, token: &str) -> bool {
+    given.is_some_and(|given| {
+        given.len() == token.len()
+            && given
+                .bytes()
+                .zip(token.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    })
+}
+
+async fn today(State(state): State<Arc<AppState>>) -> HttpReply {
+    reply(today_update_ani_list(&state).await)
+}
+
+async fn favorite(State(state): State<Arc<AppState>>) -> HttpReply {
+    reply(favorite_ani_update_list(&state).await)
+}
+
+async fn collect(
+    State(server): State<ServerState>,
+    body: Result<Json<CollectReq>, JsonRejection>,
+) -> HttpReply {
+    match body {
+        Ok(Json(req)) => {
+            let resp = collect_ani(&server.app, req.ani_id, req.ani_title).await;
+            if resp.status == "ok" {
+                (server.on_change)();
+            }
+            reply(resp)
+        }
+        Err(e) => bad_request(format!("请求参数错误：{e}")),
+    }
+}
+
+async fn watch(
+    State(server): State<ServerState>,
+    body: Result<Json<WatchReq>, JsonRejection>,
+) -> HttpReply {
+    match body {
+        Ok(Json(req)) => {
+            let resp = watch_ani(&server.app, req.ani_id).await;
+            if resp.status == "ok" {
+                (server.on_change)();
+            }
+            reply(resp)
+        }
+        Err(e) => bad_request(format!("请求参数错误：{e}")),
+    }
+}
+
+async fn history(
+    State(state): State<Arc<AppState>>,
+    query: Result<Query<PageQuery>, QueryRejection>,
+) -> HttpReply {
+    match query {
+        Ok(Query(q)) if q.page >= 1 && q.page_size >= 1 => {
+            reply(ani_history_list(&state, q.page, q.page_size).await)
+        }
+        Ok(_) => bad_request("page、pageSize 必须大于 0"),
+        Err(e) => bad_request(format!("请求参数错误：{e}")),
+    }
+}
+
+async fn calendar(State(state): State<Arc<AppState>>) -> Response {
+    match build_followed_calendar(&state).await {
+        Ok(body) => (
+            [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+            body,
+        )
+            .into_response(),
+        Err(e) => reply(ApiResponse::err(format!("生成日历失败：{e}"))).into_response(),
+    }
+}
+
+/// 订阅源，文件名形如 `today.atom`
+async fn feed(State(state): State<Arc<AppState>>, Path(file): Path<String>) -> Response {
+    let parsed = file
+        .split_once('.')
+        .and_then(|(name, ext)| Some((FeedKind::from_name(name)?, FeedFormat::from_ext(ext)?)));
+    let Some((kind, format)) = parsed else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json::<ApiResponse>(ApiResponse::err(format!("订阅源不存在：{file}"))),
+        )
+            .into_response();
+    };
+    match build_feed(&state, kind, format).await {
+        Ok(body) => ([(header::CONTENT_TYPE, format.content_type())], body).into_response(),
+        Err(e) => reply(ApiResponse::err(format!("生成订阅源失败：{e}"))).into_response(),
+    }
+}
+
+/// 构建路由
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/api/ani/today", get(today))
+        .route("/api/ani/favorite", get(favorite))
+        .route("/api/ani/collect", post(collect))
+        .route("/api/ani/watch", post(watch))
+        .route("/api/ani/history", get(history))
+        .route("/calendar.ics", get(calendar))
+        .route("/feeds/{file}", get(feed))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// 在已绑定的端口上提供服务，直到出错退出
+pub async fn serve(listener: TcpListener, state: ServerState) -> std::io::Result<()> {
+    axum::serve(listener, router(state)).await
+}
+
+/// 按配置在后台启动本地 HTTP 接口，未启用时直接返回
+///
+/// 监听本机以外的地址时必须配置访问令牌，否则不启动；on_change 在接口修改数据后调用
+pub fn start_http_server(
+    state: Arc<AppState>,
+    config: &HttpServerConfig,
+    on_change: impl Fn() + Send + Sync + 'static,
+) {
+    if !config.enabled {
+        info!("本地 HTTP 接口未启用");
+        return;
+    }
+    let is_loopback = config
+        .host
+        .parse::<IpAddr>()
+        .map(|ip| ip.is_loopback())
+        .unwrap_or(config.host == "localhost");
+    let token = config.token.clone().filter(|t| !t.is_empty());
+    if !is_loopback {
+        if token.is_none() {
+            error!(
+                "本地 HTTP 接口监听在 {}，局域网内均可访问，必须配置 http_server.token，接口未启动",
+                config.host
+            );
+            return;
+        }
+        warn!("本地 HTTP 接口监听在 {}，局域网内均可访问", config.host);
+    }
+    let state = ServerState {
+        app: state,
+        token,
+        on_change: Arc::new(on_change),
+    };
+
+    let addr = format!("{}:{}", config.host, config.port);
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind(&addr).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("本地 HTTP 接口绑定 {addr} 失败：{e}");
+                return;
+            }
+        };
+        info!("本地 HTTP 接口已启动：http://{addr}");
+        if let Err(e) = serve(listener, state).await {
+            error!("本地 HTTP 接口异常退出：{e}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures::{fanren_item, now};
+    use crate::db::sqlite::{upsert_ani_info, MIGRATOR};
+    use crate::utils::date_utils::FixedClock;
+    use crate::utils::http_client::HttpClient;
+    use chrono::NaiveDate;
+    use serde_json::{json, Value};
+    use sqlx::SqlitePool;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 接口的共享状态，返回修改数据的次数
+    fn server_state(pool: SqlitePool, token: Option<&str>) -> (ServerState, Arc<AtomicUsize>) {
+        let state = Arc::new(AppState {
+            db: Arc::new(pool),
+            clock: Arc::new(FixedClock::at_date(
+                NaiveDate::from_ymd_opt(2025, 7, 13).unwrap(),
+            )),
+            http: Arc::new(HttpClient::default()),
+        });
+        let changes = Arc::new(AtomicUsize::new(0));
+        let counter = changes.clone();
+        let state = ServerState {
+            app: state,
+            token: token.map(str::to_string),
+            on_change: Arc::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }),
+        };
+        (state, changes)
+    }
+
+    /// 启动一个监听随机端口的服务，返回基础地址和修改数据的次数
+    async fn spawn_server(token: Option<&str>) -> (String, Arc<AtomicUsize>) {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        upsert_ani_info(&pool, &fanren_item("152", "2025/07/13"), now())
+            .await
+            .unwrap();
+        let (state, changes) = server_state(pool, token);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, state));
+        (format!("http://{addr}"), changes)
+    }
+
+    #[tokio::test]
+    async fn test_http_api() {
+        let (base, changes) = spawn_server(None).await;
+        let client = reqwest::Client::new();
+
+        // 今日更新
+        let resp: Value = client
+            .get(format!("{base}/api/ani/today"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(resp["status"], "ok");
+        assert_eq!(resp["data"]["星期日"][0]["title"], "凡人修仙传");
+
+        // 今日更新订阅源
+        let resp = client
+            .get(format!("{base}/feeds/today.atom"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.headers()[header::CONTENT_TYPE],
+            "application/atom+xml; charset=utf-8"
+        );
+        assert!(resp
+            .text()
+            .await
+            .unwrap()
+            .contains("凡人修仙传 更新至第152话"));
+        let resp = client
+            .get(format!("{base}/feeds/today.json"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // 关注后出现在关注列表中
+        let resp = client
+            .post(format!("{base}/api/ani/collect"))
+            .json(&json!({ "aniId": 1, "aniTitle": "凡人修仙传" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(changes.load(Ordering::SeqCst), 1);
+        let resp: Value = client
+            .get(format!("{base}/api/ani/favorite"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(resp["data"].as_array().unwrap().len(), 1);
+
+        // 关注番剧的日历
+        let resp = client
+            .get(format!("{base}/calendar.ics"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.headers()[header::CONTENT_TYPE],
+            "text/calendar; charset=utf-8"
+        );
+        let ics = resp.text().await.unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:series-1-ep-152@ani-todo-app"));
+
+        // 标记已看后从关注列表中消失
+        let resp = client
+            .post(format!("{base}/api/ani/watch"))
+            .json(&json!({ "aniId": 1 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(changes.load(Ordering::SeqCst), 2);
+        let resp: Value = client
+            .get(format!("{base}/api/ani/favorite"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(resp["data"].as_array().unwrap().is_empty());
+
+        // 历史更新分页
+        let resp: Value = client
+            .get(format!("{base}/api/ani/history?page=1&pageSize=10"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(resp["data"]["total"], 1);
+        assert_eq!(resp["data"]["items"][0]["isWatched"], true);
+    }
+
+    #[tokio::test]
+    async fn test_http_api_bad_request() {
+        let (base, _) = spawn_server(None).await;
+        let client = reqwest::Client::new();
+
+        let resp = client
+            .post(format!("{base}/api/ani/watch"))
+            .json(&json!({ "id": 1 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["status"], "error");
+
+        let resp = client
+            .get(format!("{base}/api/ani/history?page=0"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_http_api_failed_change() {
+        // 数据库未初始化，写入失败时不刷新托盘菜单
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let (state, changes) = server_state(pool, None);
+        let req = serde_json::from_value(json!({ "aniId": 1, "aniTitle": "凡人修仙传" })).unwrap();
+        let (code, _) = collect(State(state.clone()), Ok(Json(req))).await;
+        assert_eq!(code, StatusCode::INTERNAL_SERVER_ERROR);
+        let req = serde_json::from_value(json!({ "aniId": 1 })).unwrap();
+        let (code, _) = watch(State(state), Ok(Json(req))).await;
+        assert_eq!(code, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(changes.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches(Some("secret"), "secret"));
+        assert!(!token_matches(Some("secreT"), "secret"));
+        assert!(!token_matches(Some("secret2"), "secret"));
+        assert!(!token_matches(Some(""), "secret"));
+        assert!(!token_matches(None, "secret"));
+    }
+
+    #[tokio::test]
+    async fn test_http_api_token() {
+        let (base, _) = spawn_server(Some("secret")).await;
+        let client = reqwest::Client::new();
+
+        let resp = client
+            .get(format!("{base}/api/ani/today"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = client
+            .get(format!("{base}/api/ani/today"))
+            .bearer_auth("wrong")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = client
+            .get(format!("{base}/api/ani/today"))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        // 订阅地址中携带令牌
+        let resp = client
+            .get(format!("{base}/calendar.ics?token=secret"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -1,19 +1,32 @@
-use crate::command::service::save_ani_item_data_db;
-use crate::configuration::load_configuration;
+use crate::command::service::{save_ani_item_data_db, watch_ani};
+use crate::configuration::{load_configuration, AppConfig, ConfigStatus, DataSource};
+use crate::db::common::ge_db_pool;
+use crate::db::data_source::{list_enabled_task_metas, sync_config_data_sources, ConfigSource};
+use crate::db::po::Ani;
+use crate::db::sqlite::{get_ani_info_by_id, get_app_data_dir, list_all_follow_ani_update_today};
+use crate::db::task_run::insert_task_run;
+use crate::feed::rss::{write_feeds, FEED_DIR};
+use crate::notify::Notifier;
 use crate::state::AppState;
 use crate::tasks::commands::build_cmd_map;
+use crate::tasks::config_watcher::{start_config_watcher, ConfigReloader};
 use crate::tasks::scheduler::Scheduler;
 use crate::tasks::task::{build_tasks_from_meta, TaskMeta, TaskResult};
-use crate::utils::date_utils::{format_now, DateFormat};
+use crate::utils::date_utils::{date_to_millis, format_now, DateFormat};
+use crate::validation::{invalid_sources, validate_config};
 use chrono::Local;
-use log::{info, warn, LevelFilter};
+use log::{error, info, warn, LevelFilter};
+use serde_json::json;
+use sqlx::SqlitePool;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::{fmt, fs};
-use tauri::menu::{Menu, MenuItem};
+use tauri::async_runtime::block_on;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
-use tauri::{App, AppHandle, Manager};
+use tauri::{App, AppHandle, Emitter, Manager, Wry};
 use tauri_plugin_log::{fern, Target, TargetKind};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
 
 /// 初始化日志组件
@@ -60,15 +73,29 @@ pub fn init_logger(app: &mut App) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// 托盘图标 ID
+pub const TRAY_ID: &str = "main";
+
+/// 任务执行进度事件名
+pub const TASK_PROGRESS_EVENT: &str = "task-progress";
+
+/// 配置文件修改后重新加载的结果事件名
+pub const CONFIG_RELOADED_EVENT: &str = "config-reloaded";
+
+/// 托盘菜单中最多列出的未看更新数
+const TRAY_MAX_ITEMS: usize = 10;
+
+/// 未看更新菜单项 ID 的前缀，后接更新的 id
+const TRAY_ANI_PREFIX: &str = "ani:";
+
 /// 初始化系统托盘
 pub fn init_system_tray(app: &mut App) -> anyhow::Result<()> {
-    // 定义托盘菜单
-    let show_i = MenuItem::with_id(app, "show", "主界面", true, None::<&str>)?;
-    let quit_i = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
+    // 定义托盘菜单，数据加载后会重建
+    let menu = build_tray_menu(app.handle(), &[], false)?;
 
-    let tray = TrayIconBuilder::new()
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
         //.title(app.package_info().name.clone())
+        .tooltip(app.package_info().name.clone())
         .menu(&menu)
         .show_menu_on_left_click(true)
         .icon(app.default_window_icon().unwrap().clone())
@@ -79,92 +106,332 @@ pub fn init_system_tray(app: &mut App) -> anyhow::Result<()> {
             app.exit(0);
         }
         "show" => {
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.unminimize();
-                let _ = window.show();
-                let _ = window.set_focus();
+            show_main_window(app);
+        }
+        "refresh" => {
+            if let Some(scheduler) = app.try_state::<Arc<Scheduler>>() {
+                if let Err(e) = scheduler.run_now(None) {
+                    warn!("立即刷新失败：{e}");
+                }
             }
         }
-        _ => {
-            warn!("menu item {:?} not handled", event.id);
+        "pause" => {
+            if let Some(scheduler) = app.try_state::<Arc<Scheduler>>() {
+                scheduler.set_paused(!scheduler.is_paused());
+            }
+            spawn_refresh_tray(app);
         }
+        id => match id
+            .strip_prefix(TRAY_ANI_PREFIX)
+            .and_then(|v| v.parse::<i64>().ok())
+        {
+            Some(ani_id) => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    open_and_watch(&app, ani_id).await;
+                    if let Err(e) = refresh_tray(&app).await {
+                        warn!("刷新托盘菜单失败：{e}");
+                    }
+                });
+            }
+            None => warn!("menu item {:?} not handled", event.id),
+        },
     });
     // 定义托盘的图标事件
-    tray.on_tray_icon_event(|tray, event| match event {
-        TrayIconEvent::Click {
+    tray.on_tray_icon_event(|tray, event| {
+        if let TrayIconEvent::Click {
             button: MouseButton::Left,
             button_state: MouseButtonState::Up,
             ..
-        } => {
+        } = event
+        {
             // 当点击托盘图标时，将展示并聚焦于主窗口
-            let app = tray.app_handle();
+            show_main_window(tray.app_handle());
+        }
+    });
+    Ok(())
+}
+
+/// 展示并聚焦主窗口
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// 构建托盘菜单：今日未看的关注更新、立即刷新、暂停定时更新、主界面、退出
+fn build_tray_menu(app: &AppHandle, list: &[Ani], paused: bool) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    if list.is_empty() {
+        menu.append(&MenuItem::with_id(
+            app,
+            "empty",
+            "今日关注的番剧都看完了",
+            false,
+            None::<&str>,
+        )?)?;
+    }
+    for ani in list.iter().take(TRAY_MAX_ITEMS) {
+        menu.append(&MenuItem::with_id(
+            app,
+            format!("{TRAY_ANI_PREFIX}{}", ani.id),
+            format!("《{}》{}", ani.title, ani.update_info),
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    if list.len() > TRAY_MAX_ITEMS {
+        menu.append(&MenuItem::with_id(
+            app,
+            "more",
+            format!("还有 {} 集未看…", list.len() - TRAY_MAX_ITEMS),
+            false,
+            None::<&str>,
+        )?)?;
+    }
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "refresh",
+        "立即刷新",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&CheckMenuItem::with_id(
+        app,
+        "pause",
+        "暂停定时更新",
+        true,
+        paused,
+        None::<&str>,
+    )?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "show",
+        "主界面",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?)?;
+    Ok(menu)
+}
+
+/// 按今日未看的关注更新重建托盘菜单，并在提示中显示未看数量
+pub async fn refresh_tray(app: &AppHandle) -> anyhow::Result<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let state = app.state::<Arc<AppState>>().inner().clone();
+    let today = date_to_millis(state.clock.today())?;
+    let list = list_all_follow_ani_update_today(ge_db_pool(&state.db), today).await?;
+    let paused = app
+        .try_state::<Arc<Scheduler>>()
+        .map(|s| s.is_paused())
+        .unwrap_or(false);
+
+    tray.set_menu(Some(build_tray_menu(app, &list, paused)?))?;
+    let name = app.package_info().name.clone();
+    let tooltip = if list.is_empty() {
+        name
+    } else {
+        format!("{name}：{} 集关注更新未看", list.len())
+    };
+    tray.set_tooltip(Some(tooltip))?;
+    Ok(())
+}
+
+/// 在后台重建托盘菜单
+pub fn spawn_refresh_tray(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = refresh_tray(&app).await {
+            warn!("刷新托盘菜单失败：{e}");
+        }
+    });
+}
 
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.unminimize();
-                let _ = window.show();
-                let _ = window.set_focus();
+/// 打开更新的播放页并标记为已看
+async fn open_and_watch(app: &AppHandle, ani_id: i64) {
+    let state = app.state::<Arc<AppState>>().inner().clone();
+    match get_ani_info_by_id(ge_db_pool(&state.db), ani_id).await {
+        Ok(ani) => {
+            if let Err(e) = open::that(&ani.detail_url) {
+                warn!("打开链接 {} 失败：{e}", ani.detail_url);
             }
         }
-        _ => {
-            let app = tray.app_handle();
-            let tooltip_text = app.package_info().name.clone(); // 获取应用名称并转换为 String
-            let _ = tray.set_tooltip(Some(tooltip_text)); // 传入 Some(String)
+        Err(e) => warn!("查询更新 id={ani_id} 失败：{e}"),
+    }
+    let resp = watch_ani(&state, ani_id).await;
+    if resp.status != "ok" {
+        warn!("标记已看失败：{:?}", resp.message);
+    }
+}
+
+/// 从配置文件加载所有数据源（检查结果在启动时已上报）
+pub fn load_config_sources(config_path: PathBuf) -> Result<Vec<ConfigSource>, config::ConfigError> {
+    let configuration = load_configuration(config_path)?;
+    Ok(config_sources(&configuration))
+}
+
+/// 用配置文件中的数据源更新数据库，再从数据库加载启用的数据源；
+/// 配置读取失败时保留数据库中已有的数据源（错误已在加载配置时上报）
+async fn load_data_source_metas(
+    pool: &SqlitePool,
+    config_path: PathBuf,
+    now: i64,
+) -> Vec<TaskMeta> {
+    match load_config_sources(config_path) {
+        Ok(sources) => {
+            if let Err(e) = sync_config_data_sources(pool, &sources, now).await {
+                error!("{e:#}");
+            }
         }
-    });
-    Ok(())
+        Err(e) => error!("读取数据源配置失败，使用已保存的数据源：{e}"),
+    }
+    list_enabled_task_metas(pool).await.unwrap_or_else(|e| {
+        error!("{e:#}，定时任务不会启动");
+        Vec::new()
+    })
 }
 
-///从配置文件加载定时作业的配置数据
-pub fn load_timer_tasks_config(config_path: PathBuf) -> Vec<TaskMeta> {
-    let configuration = load_configuration(config_path).expect("Failed to read configuration.");
-    let anime_sources = configuration
-        .datasource
-        .get("anime")
-        .expect("Missing anime category");
-
-    let mut tasks: Vec<TaskMeta> = Vec::new();
-    for datasource in anime_sources {
-        tasks.push(TaskMeta {
-            name: datasource.name.clone(),
-            cron_expr: datasource.cron_expr.clone(),
-            cmd: datasource.cmd.clone(),
-            arg: datasource.url.clone(),
-            retry_times: datasource.retry_times,
-        });
+/// 配置中的数据源，datasource 下的每个分类（anime、drama 等）都会加载，包括停用的数据源；
+/// 检查未通过的数据源标记为无效，数据库中保留其上次有效的记录
+pub fn config_sources(configuration: &AppConfig) -> Vec<ConfigSource> {
+    let invalid = invalid_sources(&validate_config(configuration));
+    // 按分类名排序，保证任务顺序稳定
+    let mut categories: Vec<(&String, &Vec<DataSource>)> =
+        configuration.datasource.iter().collect();
+    categories.sort_by_key(|(category, _)| *category);
+
+    let mut sources: Vec<ConfigSource> = Vec::new();
+    for (category, list) in categories {
+        for datasource in list {
+            let valid = !datasource.enabled || !invalid.contains(&datasource.name);
+            if !valid {
+                warn!("数据源 [{}] 配置有误，保留上次有效的配置", datasource.name);
+            }
+            sources.push(ConfigSource {
+                meta: TaskMeta {
+                    name: datasource.name.clone(),
+                    cron_expr: datasource.cron_expr.clone(),
+                    cmd: datasource.cmd.clone(),
+                    arg: datasource.url.clone(),
+                    retry_times: datasource.retry_times,
+                    retry_policy: datasource.retry_policy.clone(),
+                    category: category.clone(),
+                },
+                enabled: datasource.enabled,
+                valid,
+            });
+        }
     }
-    tasks
+    sources
 }
 
 /// 启动异步定时任务
-pub fn start_async_timer_task(handle: &AppHandle, config_path: PathBuf) {
-    // 1) 构造/加载配置
-    let task_metas = load_timer_tasks_config(config_path);
-    // 2) 构建命令表（CmdFn 映射）
-    let cmd_map = build_cmd_map();
-    // 3) 从 metas -> 运行时 Tasks
+pub fn start_async_timer_task(handle: &AppHandle, config_path: PathBuf, notifier: Arc<Notifier>) {
+    // 1) 从 handle 取出 Arc<AppState> （立即 clone 出 owned Arc）
+    let state_arc: Arc<AppState> = handle.state::<Arc<AppState>>().inner().clone();
+    // 2) 配置中的数据源同步到数据库后，从数据库加载定时作业的配置
+    let task_metas = block_on(load_data_source_metas(
+        &state_arc.db,
+        config_path.clone(),
+        state_arc.clock.now_millis(),
+    ));
+    // 3) 构建命令表（CmdFn 映射），共享全局时钟和 HTTP 客户端
+    let cmd_map = build_cmd_map(state_arc.clock.clone(), state_arc.http.clone());
+    // 4) 从 metas -> 运行时 Tasks
     let tasks = build_tasks_from_meta(&task_metas, &cmd_map);
-    // 4) 创建 Scheduler（内部使用 Arc<Task> 等）
-    let scheduler = Scheduler::new(tasks, None);
+    // 5) 创建 Scheduler（内部使用 Arc<Task> 等）
+    let scheduler = Scheduler::new(tasks, None, state_arc.clock.clone());
     let scheduler_arc = Arc::new(scheduler);
-    // 5) 把 Scheduler 放到 app state（使用 handle，注意这里是 AppHandle）
+    // 6) 把 Scheduler 放到 app state（使用 handle，注意这里是 AppHandle）
     handle.manage(scheduler_arc.clone());
 
-    // 6) 创建 mpsc channel 用于接收 TaskResult
-    let (tx, mut rx) = mpsc::channel::<TaskResult>(128);
+    // 监听配置文件，数据源的修改无需重启即可生效，修改有误时通知前端
+    let reloader = ConfigReloader::new(
+        config_path,
+        state_arc.db.clone(),
+        scheduler_arc.clone(),
+        cmd_map,
+        state_arc.clock.clone(),
+    );
+    start_config_watcher(reloader, {
+        let handle = handle.clone();
+        move |result| {
+            let diagnostics = match result {
+                Ok((_, diagnostics)) | Err(diagnostics) => diagnostics,
+            };
+            if let Some(status) = handle.try_state::<Arc<ConfigStatus>>() {
+                status.set_diagnostics(diagnostics.clone());
+            }
+            let payload = json!({ "diagnostics": diagnostics });
+            if let Err(e) = handle.emit(CONFIG_RELOADED_EVENT, payload) {
+                warn!("推送配置加载结果失败：{e}");
+            }
+        }
+    });
 
-    // 7) 从 handle 取出 Arc<AppState> （立即 clone 出 owned Arc）
-    let state_arc: Arc<AppState> = handle.state::<Arc<AppState>>().inner().clone();
+    // 把任务执行进度转发给前端，执行结束时记录执行结果
+    tauri::async_runtime::spawn({
+        let mut events = scheduler_arc.subscribe();
+        let handle = handle.clone();
+        let state = state_arc.clone();
+        async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        if let Err(e) = insert_task_run(ge_db_pool(&state.db), &event).await {
+                            warn!("{e:#}");
+                        }
+                        if let Err(e) = handle.emit(TASK_PROGRESS_EVENT, event) {
+                            warn!("推送任务进度失败：{e}");
+                        }
+                    }
+                    Err(RecvError::Lagged(n)) => warn!("任务进度事件积压，丢弃 {n} 条"),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    });
 
-    // 8) 启动结果接收器（异步）
+    // 7) 创建 mpsc channel 用于接收 TaskResult
+    let (tx, mut rx) = mpsc::channel::<TaskResult>(128);
+
+    // 8) 启动结果接收器（异步），保存后刷新订阅源文件、发送关注番剧的更新通知、重建托盘菜单
+    let feed_dir = get_app_data_dir(handle).join(FEED_DIR);
     tauri::async_runtime::spawn({
         let state_for_loop = state_arc.clone();
+        let handle = handle.clone();
         async move {
             while let Some(res) = rx.recv().await {
                 if let Some(ani_item_result) = res.result {
-                    let db = state_for_loop.db.clone(); // Arc<SqlitePool>
+                    let state = state_for_loop.clone();
+                    let feed_dir = feed_dir.clone();
+                    let notifier = notifier.clone();
+                    let handle = handle.clone();
                     tauri::async_runtime::spawn(async move {
-                        if let Err(e) = save_ani_item_data_db(db, ani_item_result).await {
+                        // 处理完成（包括失败返回）时丢弃，调度器停止时会等待
+                        let _pending = res.pending;
+                        let db = state.db.clone(); // Arc<SqlitePool>
+                        if let Err(e) =
+                            save_ani_item_data_db(db, ani_item_result, state.clock.now_millis())
+                                .await
+                        {
                             warn!("task {} 保存失败：{}", res.name, e);
+                            return;
+                        }
+                        if let Err(e) = write_feeds(&state, &feed_dir).await {
+                            warn!("task {} 刷新订阅源失败：{}", res.name, e);
+                        }
+                        if let Err(e) = notifier.notify_new_updates(&state).await {
+                            warn!("task {} 发送更新通知失败：{}", res.name, e);
+                        }
+                        if let Err(e) = refresh_tray(&handle).await {
+                            warn!("task {} 刷新托盘菜单失败：{}", res.name, e);
                         }
                     });
                 }
@@ -179,17 +446,30 @@ pub fn start_async_timer_task(handle: &AppHandle, config_path: PathBuf) {
             scheduler_run.run(tx).await;
         }
     });
+
+    // 10) 用已有数据初始化托盘菜单
+    spawn_refresh_tray(handle);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn enabled_metas(sources: Vec<ConfigSource>) -> Vec<TaskMeta> {
+        sources
+            .into_iter()
+            .filter(|s| s.enabled && s.valid)
+            .map(|s| s.meta)
+            .collect()
+    }
+
     #[test]
     fn test_get_task_metas() {
         let tmp = PathBuf::from("tmp");
-        let task_metas = load_timer_tasks_config(tmp);
-        assert_eq!(task_metas.len(), 6);
+        let task_metas = enabled_metas(load_config_sources(tmp).unwrap());
+        // anime 和 drama 两个分类都会加载
+        assert_eq!(task_metas.len(), 7);
+        assert_eq!(task_metas[6].category, "drama");
         assert_eq!(task_metas[0].name, "哔哩哔哩国创");
         assert_eq!(task_metas[0].cmd, "fetch_bilibili_ani_data");
         assert_eq!(
@@ -201,4 +481,46 @@ mod tests {
             "https://api.bilibili.com/pgc/web/timeline?types=4&before=6&after=6"
         );
     }
+
+    #[test]
+    fn test_load_all_categories() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("config.yaml"),
+            r#"
+datasource:
+  drama:
+    - name: "腾讯视频"
+      url: "https://v.qq.com/channel/drama"
+      cmd: "tencent_parser"
+      cron_expr: "0 0 * * * * *"
+      retry_times: 0
+  anime:
+    - name: "哔哩哔哩番剧"
+      url: "https://api.bilibili.com/pgc/web/timeline"
+      cmd: "fetch_bilibili_ani_data"
+      cron_expr: "0 0 * * * * *"
+      retry_times: 1
+    - name: "优酷动漫"
+      url: "https://www.youku.com/ku/webcomic"
+      cmd: "fetch_youku_ani_data"
+      cron_expr: "0 0 * * * * *"
+      retry_times: 0
+      enabled: false
+"#,
+        )
+        .unwrap();
+        // 停用的数据源也会加载，但不创建任务
+        let sources = load_config_sources(dir.path().to_path_buf()).unwrap();
+        assert_eq!(sources.len(), 3);
+        let task_metas = enabled_metas(sources);
+        let names: Vec<(&str, &str)> = task_metas
+            .iter()
+            .map(|m| (m.category.as_str(), m.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("anime", "哔哩哔哩番剧"), ("drama", "腾讯视频")]
+        );
+    }
 }
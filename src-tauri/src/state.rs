@@ -1,7 +1,13 @@
+use crate::utils::date_utils::Clock;
+use crate::utils::http_client::HttpClient;
 use sqlx::SqlitePool;
 use std::sync::Arc;
 
 /// tauri 的全局App状态
 pub struct AppState {
     pub db: Arc<SqlitePool>,
+    /// 全局时钟，调试时可通过环境变量覆盖“今天”
+    pub clock: Arc<dyn Clock>,
+    /// 抓取数据源和图片共用的 HTTP 客户端
+    pub http: Arc<HttpClient>,
 }
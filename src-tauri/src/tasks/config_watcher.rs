@@ -0,0 +1,262 @@
+//! 监听配置目录：配置文件变化后重新加载数据源，同步到数据库后增删、重新排期调度器中的任务
+
+use crate::configuration::{DEFAULT_CONFIG_FILE, USER_CONFIG_FILE};
+use crate::db::data_source::{list_enabled_task_metas, sync_config_data_sources};
+use crate::startup::config_sources;
+use crate::tasks::commands::CmdFn;
+use crate::tasks::scheduler::Scheduler;
+use crate::tasks::task::TaskDiff;
+use crate::utils::date_utils::Clock;
+use crate::validation::{load_checked_configuration, ConfigDiagnostic};
+use log::{info, warn};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// 检查配置文件是否变化的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 重新加载的结果：生效时为差异和告警，被拒绝时为检查结果
+pub type ReloadResult = Result<(TaskDiff, Vec<ConfigDiagnostic>), Vec<ConfigDiagnostic>>;
+
+/// 按数据库中启用的数据源更新调度器：新增的数据源立即执行一次，修改过的按新配置重新排期
+pub async fn apply_data_sources(
+    pool: &SqlitePool,
+    scheduler: &Scheduler,
+    cmd_map: &HashMap<String, CmdFn>,
+) -> anyhow::Result<TaskDiff> {
+    let metas = list_enabled_task_metas(pool).await?;
+    Ok(scheduler.sync_tasks(&metas, cmd_map))
+}
+
+/// 把配置文件中的数据源同步到数据库和运行中的调度器
+pub struct ConfigReloader {
+    config_path: PathBuf,
+    db: Arc<SqlitePool>,
+    scheduler: Arc<Scheduler>,
+    cmd_map: HashMap<String, CmdFn>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ConfigReloader {
+    pub fn new(
+        config_path: PathBuf,
+        db: Arc<SqlitePool>,
+        scheduler: Arc<Scheduler>,
+        cmd_map: HashMap<String, CmdFn>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self {
+            config_path,
+            db,
+            scheduler,
+            cmd_map,
+            clock,
+        }
+    }
+
+    /// 重新加载配置并应用到调度器，检查出错误时整体拒绝，原有任务保持不变
+    ///
+    /// 在应用中修改过的数据源不受配置文件影响；只同步数据源，其它配置仍需重启生效
+    pub async fn reload(&self) -> ReloadResult {
+        let (config, diagnostics) = load_checked_configuration(&self.config_path, None);
+        let config = match config {
+            Some(config) if !diagnostics.iter().any(ConfigDiagnostic::is_error) => config,
+            _ => return Err(diagnostics),
+        };
+        let sources = config_sources(&config);
+        let now = self.clock.now_millis();
+        let applied = match sync_config_data_sources(&self.db, &sources, now).await {
+            Ok(()) => apply_data_sources(&self.db, &self.scheduler, &self.cmd_map).await,
+            Err(e) => Err(e),
+        };
+        match applied {
+            Ok(diff) => Ok((diff, diagnostics)),
+            Err(e) => Err(vec![ConfigDiagnostic::error(
+                "",
+                None,
+                format!("保存数据源失败：{e:#}"),
+            )]),
+        }
+    }
+}
+
+/// 配置文件的修改时间和大小，用于判断是否变化
+fn config_fingerprint(config_path: &Path) -> Vec<Option<(SystemTime, u64)>> {
+    [USER_CONFIG_FILE, DEFAULT_CONFIG_FILE]
+        .iter()
+        .map(|file| {
+            let meta = fs::metadata(config_path.join(file)).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        })
+        .collect()
+}
+
+/// 在后台轮询配置目录，文件变化且稳定一个周期后（避免读到写了一半的文件）重新加载，
+/// 每次加载的结果交给 on_reload
+pub fn start_config_watcher<F>(reloader: ConfigReloader, on_reload: F)
+where
+    F: Fn(ReloadResult) + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        let mut last = config_fingerprint(&reloader.config_path);
+        let mut pending = false;
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let current = config_fingerprint(&reloader.config_path);
+            if current != last {
+                last = current;
+                pending = true;
+                continue;
+            }
+            if !pending {
+                continue;
+            }
+            pending = false;
+            let result = reloader.reload().await;
+            match &result {
+                Ok((diff, _)) if diff.is_empty() => info!("配置文件已修改，数据源没有变化"),
+                Ok((diff, _)) => info!(
+                    "配置已重新加载：新增 {} 个、修改 {} 个、移除 {} 个数据源",
+                    diff.added.len(),
+                    diff.changed.len(),
+                    diff.removed.len()
+                ),
+                Err(diagnostics) => {
+                    for diagnostic in diagnostics {
+                        warn!("配置修改未生效：{diagnostic}");
+                    }
+                }
+            }
+            on_reload(result);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::data_source::{list_data_source, update_data_source};
+    use crate::db::sqlite::MIGRATOR;
+    use crate::tasks::commands::build_cmd_map;
+    use crate::utils::date_utils::SystemClock;
+    use crate::utils::http_client::HttpClient;
+
+    fn source(name: &str, cron_expr: &str) -> String {
+        format!(
+            r#"
+    - name: "{name}"
+      url: "https://example.com/{name}"
+      cmd: "fetch_bilibili_ani_data"
+      cron_expr: "{cron_expr}"
+      retry_times: 0"#
+        )
+    }
+
+    fn write_config(dir: &Path, sources: &[String]) {
+        fs::write(
+            dir.join(USER_CONFIG_FILE),
+            format!("datasource:\n  anime:{}\n", sources.concat()),
+        )
+        .unwrap();
+    }
+
+    fn cron_of(scheduler: &Scheduler, name: &str) -> Option<String> {
+        scheduler
+            .tasks()
+            .iter()
+            .find(|t| t.name == name)
+            .map(|t| t.cron_expr.clone())
+    }
+
+    #[tokio::test]
+    async fn test_config_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config(
+            dir.path(),
+            &[source("A", "0 0 * * * * *"), source("B", "0 0 * * * * *")],
+        );
+        let pool = Arc::new(SqlitePool::connect("sqlite::memory:").await.unwrap());
+        MIGRATOR.run(&*pool).await.unwrap();
+        let cmd_map = build_cmd_map(Arc::new(SystemClock), Arc::new(HttpClient::default()));
+        let scheduler = Arc::new(Scheduler::new(Vec::new(), Some(1), Arc::new(SystemClock)));
+        let reloader = ConfigReloader::new(
+            dir.path().to_path_buf(),
+            pool.clone(),
+            scheduler.clone(),
+            cmd_map,
+            Arc::new(SystemClock),
+        );
+
+        // 首次加载创建所有任务，再次加载没有变化
+        assert_eq!(reloader.reload().await.unwrap().0.added.len(), 2);
+        assert!(reloader.reload().await.unwrap().0.is_empty());
+
+        // 修改 A 的 cron，删除 B，新增 C
+        write_config(
+            dir.path(),
+            &[source("A", "0 30 * * * * *"), source("C", "0 0 * * * * *")],
+        );
+        let (diff, _) = reloader.reload().await.unwrap();
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.removed, vec!["B".to_string()]);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(cron_of(&scheduler, "A").as_deref(), Some("0 30 * * * * *"));
+        assert_eq!(cron_of(&scheduler, "B"), None);
+        assert!(cron_of(&scheduler, "C").is_some());
+
+        // 无效的修改被拒绝，任务保持不变
+        write_config(
+            dir.path(),
+            &[source("A", "every minute"), source("C", "0 0 * * * * *")],
+        );
+        let diagnostics = reloader.reload().await.unwrap_err();
+        assert_eq!(diagnostics[0].path, "datasource.anime[0].cron_expr");
+        write_config(
+            dir.path(),
+            &[source("A", "0 0 * * * * *"), source("A", "0 0 * * * * *")],
+        );
+        assert_eq!(
+            reloader.reload().await.unwrap_err()[0].path,
+            "datasource.anime[1].name"
+        );
+        fs::write(dir.path().join(USER_CONFIG_FILE), "datasource: [\n").unwrap();
+        assert!(reloader.reload().await.is_err());
+        assert_eq!(cron_of(&scheduler, "A").as_deref(), Some("0 30 * * * * *"));
+        assert_eq!(scheduler.tasks().len(), 2);
+
+        // 在应用中修改过的数据源不再跟随配置文件
+        let c = list_data_source(&pool).await.unwrap()[1].clone();
+        let mut edited = crate::configuration::DataSource {
+            name: c.name,
+            url: c.url,
+            cmd: c.cmd,
+            cron_expr: "0 15 * * * * *".to_string(),
+            retry_times: 0,
+            retry_policy: Default::default(),
+            enabled: true,
+        };
+        update_data_source(&pool, c.id, &c.category, &edited, 0)
+            .await
+            .unwrap();
+        write_config(
+            dir.path(),
+            &[source("A", "0 30 * * * * *"), source("C", "0 45 * * * * *")],
+        );
+        assert_eq!(reloader.reload().await.unwrap().0.changed.len(), 1);
+        assert_eq!(cron_of(&scheduler, "C").as_deref(), Some("0 15 * * * * *"));
+        edited.enabled = false;
+        update_data_source(&pool, c.id, &c.category, &edited, 0)
+            .await
+            .unwrap();
+        let diff = apply_data_sources(&pool, &scheduler, &reloader.cmd_map)
+            .await
+            .unwrap();
+        assert_eq!(diff.removed, vec!["C".to_string()]);
+        assert_eq!(scheduler.tasks().len(), 1);
+    }
+}
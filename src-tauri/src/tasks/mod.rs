@@ -1,3 +1,5 @@
 pub mod commands;
+pub mod config_watcher;
 pub mod scheduler;
 pub mod task;
+pub mod timer_queue;
@@ -1,21 +1,58 @@
-use crate::tasks::task::{Task, TaskResult};
-use chrono::Local;
+use crate::tasks::task::{
+    build_tasks_from_meta, diff_task_metas, CmdFn, Task, TaskDiff, TaskEvent, TaskMeta, TaskResult,
+    TaskStatus,
+};
+use crate::tasks::timer_queue::{TimerQueue, CLOCK_JUMP_TOLERANCE};
+use crate::types::{AniItemResult, ApiResponse};
+use crate::utils::date_utils::Clock;
+use chrono::{DateTime, Local};
 use log::{info, warn};
-use std::sync::{Arc, Mutex};
-use tokio::sync::{mpsc, Notify, Semaphore};
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc, watch, Notify, Semaphore};
 use tokio::task::JoinHandle;
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, timeout, Duration};
+
+/// 调度循环单次最长等待时间
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// 任务的调度信息
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    pub name: String,
+    pub cron_expr: String,
+    /// 下次执行时间（毫秒时间戳）
+    pub next_run: Option<i64>,
+    /// 单独暂停了该任务
+    pub paused: bool,
+}
 
 #[derive(Clone)]
 pub struct Scheduler {
-    pub tasks: Vec<Arc<Task>>,
+    tasks: Arc<RwLock<Vec<Arc<Task>>>>,
     shutdown: Arc<Notify>,
     task_handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
-    semaphore: Arc<Semaphore>, // 控制并发的信号量
+    semaphore: Arc<Semaphore>,                 // 控制并发的信号量
+    paused: Arc<AtomicBool>,                   // 暂停定时执行（手动刷新不受影响）
+    paused_tasks: Arc<Mutex<HashSet<String>>>, // 单独暂停的任务
+    changed: Arc<Notify>,                      // 任务配置变化，通知调度循环重新计算
+    stopped: Arc<watch::Sender<bool>>,         // 已停止，不再接受新的执行，并取消重试等待
+    sender: Arc<Mutex<Option<mpsc::Sender<TaskResult>>>>, // run 时保存，供手动刷新使用
+    events: broadcast::Sender<TaskEvent>,      // 任务执行进度
+    clock: Arc<dyn Clock>,                     // 计算执行时间使用的时钟
 }
 
 impl Scheduler {
-    pub fn new(tasks: Vec<Task>, max_concurrent_tasks: Option<usize>) -> Self {
+    pub fn new(
+        tasks: Vec<Task>,
+        max_concurrent_tasks: Option<usize>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
         // 获取系统的 CPU 核心数
         let default_max_concurrent_tasks = num_cpus::get();
 
@@ -23,78 +60,354 @@ impl Scheduler {
         let max_concurrent_tasks = max_concurrent_tasks.unwrap_or(default_max_concurrent_tasks);
 
         Self {
-            tasks: tasks.into_iter().map(Arc::new).collect(),
+            tasks: Arc::new(RwLock::new(tasks.into_iter().map(Arc::new).collect())),
             shutdown: Arc::new(Notify::new()),
             task_handles: Arc::new(Mutex::new(vec![])),
             semaphore: Arc::new(Semaphore::new(max_concurrent_tasks)), // 限制并发任务数
+            paused: Arc::new(AtomicBool::new(false)),
+            paused_tasks: Arc::new(Mutex::new(HashSet::new())),
+            changed: Arc::new(Notify::new()),
+            stopped: Arc::new(watch::channel(false).0),
+            sender: Arc::new(Mutex::new(None)),
+            events: broadcast::channel(64).0,
+            clock,
+        }
+    }
+
+    /// 当前所有任务
+    pub fn tasks(&self) -> Vec<Arc<Task>> {
+        self.tasks.read().unwrap().clone()
+    }
+
+    /// 所有任务的调度信息，暂停（包括暂停定时更新）期间没有下次执行时间
+    pub fn task_infos(&self) -> Vec<TaskInfo> {
+        let now = self.clock.now();
+        self.tasks()
+            .iter()
+            .map(|task| {
+                let paused = self.is_task_paused(&task.name);
+                let next_run = match task.schedule() {
+                    _ if paused || self.is_paused() => None,
+                    Ok(schedule) => schedule.after(&now).next().map(|t| t.timestamp_millis()),
+                    Err(e) => {
+                        warn!("任务 [{}] 的 cron 表达式无效：{e}", task.name);
+                        None
+                    }
+                };
+                TaskInfo {
+                    name: task.name.clone(),
+                    cron_expr: task.cron_expr.clone(),
+                    next_run,
+                    paused,
+                }
+            })
+            .collect()
+    }
+
+    /// 订阅任务执行进度事件
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskEvent> {
+        self.events.subscribe()
+    }
+
+    /// 暂停或恢复定时执行
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+        info!("定时更新已{}", if paused { "暂停" } else { "恢复" });
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    fn is_stopped(&self) -> bool {
+        *self.stopped.borrow()
+    }
+
+    /// 单独暂停或恢复某个任务的定时执行
+    pub fn set_task_paused(&self, name: &str, paused: bool) -> Result<(), String> {
+        self.find_task(name)?;
+        let mut paused_tasks = self.paused_tasks.lock().unwrap();
+        if paused {
+            paused_tasks.insert(name.to_string());
+        } else {
+            paused_tasks.remove(name);
+        }
+        self.changed.notify_one();
+        info!("任务 [{name}] 已{}", if paused { "暂停" } else { "恢复" });
+        Ok(())
+    }
+
+    pub fn is_task_paused(&self, name: &str) -> bool {
+        self.paused_tasks.lock().unwrap().contains(name)
+    }
+
+    fn paused_tasks(&self) -> HashSet<String> {
+        self.paused_tasks.lock().unwrap().clone()
+    }
+
+    /// 添加任务，同名任务已存在时替换为新任务并按新配置重新排期
+    pub fn upsert_task(&self, task: Task) {
+        let name = task.name.clone();
+        let mut tasks = self.tasks.write().unwrap();
+        match tasks.iter_mut().find(|t| t.name == name) {
+            Some(old) => *old = Arc::new(task),
+            None => tasks.push(Arc::new(task)),
+        }
+        drop(tasks);
+        self.changed.notify_one();
+        info!("任务 [{name}] 已更新");
+    }
+
+    /// 移除任务，执行中的任务会继续执行完
+    pub fn remove_task(&self, name: &str) -> Result<(), String> {
+        let mut tasks = self.tasks.write().unwrap();
+        let len = tasks.len();
+        tasks.retain(|t| t.name != name);
+        if tasks.len() == len {
+            return Err(format!("任务不存在：{name}"));
+        }
+        drop(tasks);
+        self.paused_tasks.lock().unwrap().remove(name);
+        self.changed.notify_one();
+        info!("任务 [{name}] 已移除");
+        Ok(())
+    }
+
+    /// 按新的任务配置增删、更新任务，返回与当前任务的差异，新增的任务立即执行一次
+    pub fn sync_tasks(&self, metas: &[TaskMeta], cmd_map: &HashMap<String, CmdFn>) -> TaskDiff {
+        let current: Vec<TaskMeta> = self.tasks().iter().map(|t| t.meta()).collect();
+        let diff = diff_task_metas(&current, metas);
+        for name in &diff.removed {
+            if let Err(e) = self.remove_task(name) {
+                warn!("{e}");
+            }
+        }
+        let tasks = build_tasks_from_meta(&diff.changed, cmd_map)
+            .into_iter()
+            .chain(build_tasks_from_meta(&diff.added, cmd_map));
+        for task in tasks {
+            self.upsert_task(task);
         }
+        for meta in &diff.added {
+            if let Err(e) = self.run_now(Some(&meta.name)) {
+                warn!("新数据源 [{}] 暂未执行：{e}", meta.name);
+            }
+        }
+        diff
+    }
+
+    fn find_task(&self, name: &str) -> Result<Arc<Task>, String> {
+        self.tasks()
+            .into_iter()
+            .find(|t| t.name == name)
+            .ok_or_else(|| format!("任务不存在：{name}"))
+    }
+
+    /// 立即执行指定任务（为空时执行所有任务），返回启动的任务数
+    pub fn run_now(&self, name: Option<&str>) -> Result<usize, String> {
+        if self.is_stopped() {
+            return Err("调度器已停止".to_string());
+        }
+        let sender = self
+            .sender
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "调度器尚未启动".to_string())?;
+        let tasks = match name {
+            Some(name) => vec![self.find_task(name)?],
+            None => self.tasks(),
+        };
+        for task in &tasks {
+            self.spawn_task(task.clone(), sender.clone(), true);
+        }
+        info!("手动触发 {} 个任务", tasks.len());
+        Ok(tasks.len())
+    }
+
+    /// 停止调度，取消等待中的重试，等待执行中的任务及其结果保存完成，超时返回 false
+    pub async fn shutdown(&self, wait: Duration) -> bool {
+        self.stopped.send_replace(true);
+        self.shutdown.notify_one();
+        let handles = std::mem::take(&mut *self.task_handles.lock().unwrap());
+        let running = handles.iter().filter(|h| !h.is_finished()).count();
+        info!("调度器停止中，等待 {running} 个执行中的任务");
+        let finished = timeout(wait, async {
+            for handle in handles {
+                let _ = handle.await;
+            }
+        })
+        .await
+        .is_ok();
+        if !finished {
+            warn!("等待执行中的任务超时，强制停止");
+        }
+        finished
+    }
+
+    /// 在后台执行任务，执行前获取并发许可，执行后等待结果处理完成
+    fn spawn_task(&self, task: Arc<Task>, sender: mpsc::Sender<TaskResult>, manual: bool) {
+        let events = self.events.clone();
+        let semaphore = self.semaphore.clone();
+        let stopped = self.stopped.subscribe();
+        let handle = tokio::spawn(async move {
+            let permit = semaphore.acquire_owned().await.unwrap(); // 确保在任务执行期间保持许可有效
+            let pending = Self::execute_task(task, sender, events, manual, stopped).await;
+            drop(permit);
+            // 结果处理（保存、通知等）不占用并发许可，停止调度时据此等待结果保存完成
+            if let Some(mut pending) = pending {
+                pending.recv().await;
+            }
+        });
+        let mut handles = self.task_handles.lock().unwrap();
+        handles.retain(|h| !h.is_finished());
+        handles.push(handle);
     }
 
     /// 运行任务调度器，将 TaskResult 通过 mpsc::Sender 发出
     pub async fn run(&self, sender: mpsc::Sender<TaskResult>) {
-        // 启动时立即执行任务
-        for task in &self.tasks {
-            let t = task.clone();
-            let s = sender.clone();
-            let handle = tokio::spawn(async move {
-                Self::execute_task(t, s).await;
-            });
-            self.task_handles.lock().unwrap().push(handle);
-        }
+        *self.sender.lock().unwrap() = Some(sender.clone());
+        self.run_on_startup(&sender);
+
+        let mut queue = TimerQueue::default();
+        self.rebuild_queue(&mut queue, self.clock.now());
+        let mut last_check = self.clock.now();
 
-        loop {
-            let mut next_runs: Vec<(chrono::DateTime<Local>, Arc<Task>)> = vec![];
-            for task in &self.tasks {
-                let schedule = task.schedule();
-                if let Some(next) = schedule.upcoming(Local).next() {
-                    next_runs.push((next.with_timezone(&Local), task.clone()));
+        while !self.is_stopped() {
+            let now = self.clock.now();
+            if now < last_check - CLOCK_JUMP_TOLERANCE {
+                warn!("系统时间回拨（{last_check} -> {now}），重新计算任务执行时间");
+                queue.reset(&self.tasks(), &self.paused_tasks(), now);
+            }
+            last_check = now;
+
+            // 休眠时单调时钟可能停走，最多等待 MAX_WAIT 后按墙上时间重新检查
+            let wait = queue
+                .next_at()
+                .map(|at| (at - now).to_std().unwrap_or(Duration::ZERO))
+                .unwrap_or(MAX_WAIT)
+                .min(MAX_WAIT);
+
+            tokio::select! {
+                _ = sleep(wait) => {
+                    self.fire_due(&mut queue, self.clock.now(), &sender);
+                }
+                _ = self.changed.notified() => {
+                    // 任务配置变化，重新计算下次执行时间
+                    self.rebuild_queue(&mut queue, self.clock.now());
+                }
+                _ = self.shutdown.notified() => {
+                    // 收到停止通知，退出调度
+                    warn!("调度器已收到停止通知");
+                    break;
                 }
             }
+        }
+    }
 
-            if next_runs.is_empty() {
-                break;
+    /// 启动时立即执行一次任务，跳过暂停的任务，返回启动的任务数
+    fn run_on_startup(&self, sender: &mpsc::Sender<TaskResult>) -> usize {
+        if self.is_paused() {
+            info!("定时更新已暂停，启动时不执行任务");
+            return 0;
+        }
+        let mut started = 0;
+        for task in self.tasks() {
+            if self.is_task_paused(&task.name) {
+                info!("任务 [{}] 已暂停，启动时不执行", task.name);
+                continue;
             }
+            self.spawn_task(task, sender.clone(), false);
+            started += 1;
+        }
+        started
+    }
 
-            next_runs.sort_by_key(|(time, _)| *time); // 按时间升序排序
-                                                      // 遍历所有已排序的任务
-            for (next_time, task) in next_runs {
-                let duration = (next_time - Local::now())
-                    .to_std()
-                    .unwrap_or(Duration::from_secs(0)); // 计算任务等待时间
-
-                tokio::select! {
-                    _ = sleep(duration) => {
-                        // 获取信号量许可
-                        let permit = self.semaphore.clone().acquire_owned().await.unwrap();
-                        // 执行任务
-                        let t = task.clone();
-                        let s = sender.clone();
-                        tokio::spawn(async move {
-                            let _permit = permit; // 确保在任务执行期间保持许可有效
-                            Self::execute_task(t, s).await;
-                        });
-                    }
-                    _ = self.shutdown.notified() => {
-                        // 收到停止通知，退出调度
-                        warn!("调度器已收到停止通知");
-                        break;
-                    }
-                }
+    /// 按当前任务和单独暂停的任务重建触发队列
+    fn rebuild_queue(&self, queue: &mut TimerQueue, now: DateTime<Local>) {
+        queue.rebuild(&self.tasks(), &self.paused_tasks(), now);
+    }
+
+    /// 执行 now 时已到期的任务并重新排期，返回启动的任务数
+    fn fire_due(
+        &self,
+        queue: &mut TimerQueue,
+        now: DateTime<Local>,
+        sender: &mpsc::Sender<TaskResult>,
+    ) -> usize {
+        let tasks = self.tasks();
+        let mut started = 0;
+        for (at, name) in queue.pop_due(now) {
+            // 任务已被移除时不再排期
+            let Some(task) = tasks.iter().find(|t| t.name == name) else {
+                continue;
+            };
+            if self.is_paused() {
+                info!("定时更新已暂停，跳过任务 [{name}] 在 {at} 的执行");
+            } else {
+                self.spawn_task(task.clone(), sender.clone(), false);
+                started += 1;
             }
+            queue.arm(task, now);
         }
+        started
     }
 
-    async fn execute_task(task: Arc<Task>, sender: mpsc::Sender<TaskResult>) {
+    /// 执行任务并发送结果，返回结果处理完成的回执（所有 `TaskResult::pending` 丢弃后结束等待）
+    async fn execute_task(
+        task: Arc<Task>,
+        sender: mpsc::Sender<TaskResult>,
+        events: broadcast::Sender<TaskEvent>,
+        manual: bool,
+        mut stopped: watch::Receiver<bool>,
+    ) -> Option<mpsc::Receiver<()>> {
+        let started_time = Local::now().timestamp_millis();
+        // 没有订阅者时发送失败，忽略即可
+        let emit = |status, attempts, count, message, warnings| {
+            let _ = events.send(TaskEvent {
+                task_name: task.name.clone(),
+                status,
+                manual,
+                count,
+                message,
+                attempts,
+                warnings,
+                started_time,
+                time: Local::now().timestamp_millis(),
+            });
+        };
+        emit(TaskStatus::Started, 0, None, None, vec![]);
         for attempt in 0..=task.retry_times {
             match task.action.run().await {
                 Ok(resp) => {
                     info!("任务 [{}] 执行成功", task.name);
+                    let warnings = parse_warnings(&resp);
+                    for warning in &warnings {
+                        warn!("任务 [{}] 解析告警：{warning}", task.name);
+                    }
+                    let mut data = resp.data.unwrap_or_default();
+                    // 标记更新所属的数据源分类
+                    for item in data.values_mut().flatten() {
+                        if item.category.is_empty() {
+                            item.category = task.category.clone();
+                        }
+                    }
+                    let count = data.values().map(Vec::len).sum();
+                    let (pending, done) = mpsc::channel(1);
                     let result = TaskResult {
                         name: task.name.clone(),
-                        result: Some(resp.data.unwrap_or_default()),
+                        result: Some(data),
+                        pending,
                     };
                     let _ = sender.send(result).await;
-                    break;
+                    emit(
+                        TaskStatus::Success,
+                        attempt as u32 + 1,
+                        Some(count),
+                        None,
+                        warnings,
+                    );
+                    return Some(done);
                 }
                 Err(e) => {
                     info!(
@@ -104,20 +417,74 @@ impl Scheduler {
                         attempt + 1,
                         task.retry_times
                     );
-                    if attempt < task.retry_times {
-                        sleep(Duration::from_secs(5)).await;
+                    // 重试次数用完，或错误不是临时性的（如 4xx、解析失败），不再重试
+                    if attempt >= task.retry_times || !e.is_transient() {
+                        if attempt < task.retry_times {
+                            warn!("任务 [{}] 的错误不可重试，放弃重试", task.name);
+                        }
+                        emit(
+                            TaskStatus::Failed,
+                            attempt as u32 + 1,
+                            None,
+                            Some(e.to_string()),
+                            vec![],
+                        );
+                        return None;
+                    }
+                    let policy = &task.retry_policy;
+                    let delay = match e.retry_after() {
+                        Some(delay) => policy.cap(delay),
+                        None => policy.backoff(attempt as u32, jitter_sample()),
+                    };
+                    info!("任务 [{}] {:.1} 秒后重试", task.name, delay.as_secs_f64());
+                    tokio::select! {
+                        _ = sleep(delay) => {}
+                        Ok(_) = stopped.wait_for(|stopped| *stopped) => {
+                            warn!("调度器已停止，任务 [{}] 取消重试", task.name);
+                            emit(
+                                TaskStatus::Failed,
+                                attempt as u32 + 1,
+                                None,
+                                Some(format!("{e}（调度器已停止，取消重试）")),
+                                vec![],
+                            );
+                            return None;
+                        }
                     }
                 }
             }
         }
+        None
+    }
+}
+
+/// [0, 1) 之间的随机数，用于重试等待时间的抖动
+fn jitter_sample() -> f64 {
+    let hash = RandomState::new().hash_one(Local::now().timestamp_nanos_opt());
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// 从抓取结果中提取解析告警：解析器返回的错误信息，以及没有解析到任何更新
+fn parse_warnings(resp: &ApiResponse<AniItemResult>) -> Vec<String> {
+    let mut warnings: Vec<String> = resp.message.iter().cloned().collect();
+    if resp
+        .data
+        .as_ref()
+        .is_none_or(|data| data.values().all(Vec::is_empty))
+    {
+        warnings.push("没有解析到任何更新，页面结构可能已变化".to_string());
     }
+    warnings
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tasks::commands::{build_cmd_map, CmdFn};
+    use crate::configuration::RetryPolicy;
+    use crate::tasks::commands::CmdFn;
     use crate::tasks::task::{build_tasks_from_meta, TaskMeta};
+    use crate::utils::date_utils::{FixedClock, SystemClock};
+    use chrono::TimeZone;
     use std::collections::HashMap;
     use tokio::sync::mpsc;
 
@@ -126,37 +493,347 @@ mod tests {
         let metas = vec![
             TaskMeta {
                 name: "任务A".into(),
-                cmd: "fetch_agedm_ani_data".into(),
+                cmd: "stub".into(),
                 arg: "https://example.com/a".into(),
-                cron_expr: "0/10 * * * * * *".into(), // 每10s
+                cron_expr: "0 0 0 1 1 * *".into(),
                 retry_times: 1,
+                retry_policy: RetryPolicy::default(),
+                category: "anime".into(),
             },
             TaskMeta {
                 name: "任务B".into(),
                 cmd: "unknown_cmd".into(),
                 arg: "https://example.com/b".into(),
-                cron_expr: "0/15 * * * * * *".into(),
+                cron_expr: "0 0 0 1 1 * *".into(),
                 retry_times: 0,
+                retry_policy: RetryPolicy::default(),
+                category: "anime".into(),
             },
         ];
 
-        let cmd_map: HashMap<String, CmdFn> = build_cmd_map();
+        // 桩命令记录收到的参数，不访问网络
+        let args = Arc::new(Mutex::new(Vec::new()));
+        let received = args.clone();
+        let stub: CmdFn = Arc::new(move |arg: String| {
+            received.lock().unwrap().push(arg);
+            Box::pin(async { Ok(ApiResponse::ok(HashMap::new())) })
+        });
+        let cmd_map: HashMap<String, CmdFn> = HashMap::from([("stub".to_string(), stub)]);
         let tasks = build_tasks_from_meta(&metas, &cmd_map);
-        let scheduler = Scheduler::new(tasks, Some(2)); // 限制最大并发任务数为 2
+        let scheduler = Scheduler::new(tasks, Some(2), Arc::new(SystemClock)); // 限制最大并发任务数为 2
+        let mut events = scheduler.subscribe();
+        let (tx, mut rx) = mpsc::channel(100);
+        let scheduler_run = scheduler.clone();
+        tokio::spawn(async move { scheduler_run.run(tx).await });
+
+        // 启动时执行一次：找到命令的任务带着参数执行，找不到命令的任务执行失败
+        let res = timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.name, "任务A");
+        drop(res);
+        assert_eq!(*args.lock().unwrap(), vec!["https://example.com/a"]);
+        let mut failed = Vec::new();
+        while failed.is_empty() {
+            let event = timeout(Duration::from_secs(5), events.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            if event.status == TaskStatus::Failed {
+                failed.push(event.task_name);
+            }
+        }
+        assert_eq!(failed, vec!["任务B"]);
+        assert!(scheduler.shutdown(Duration::from_secs(5)).await);
+    }
+
+    /// 构造计数任务，每次执行前等待 delay
+    fn counting_task(
+        name: &str,
+        cron_expr: &str,
+        delay: Duration,
+    ) -> (Task, Arc<std::sync::atomic::AtomicUsize>) {
+        let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = runs.clone();
+        let meta = TaskMeta {
+            name: name.into(),
+            cmd: "count".into(),
+            arg: String::new(),
+            cron_expr: cron_expr.into(),
+            retry_times: 0,
+            retry_policy: RetryPolicy::default(),
+            category: "anime".into(),
+        };
+        let task = Task::new(&meta, move || {
+            let counter = counter.clone();
+            async move {
+                sleep(delay).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(ApiResponse::ok(HashMap::new()))
+            }
+        });
+        (task, runs)
+    }
+
+    /// 触发队列使用的计划时间，与墙上时间无关
+    fn at(s: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 7, 13, 8, 0, s).unwrap()
+    }
+
+    /// 不启动调度循环，只设置结果通道，由测试按指定时间触发
+    fn attach(scheduler: &Scheduler) -> mpsc::Receiver<TaskResult> {
+        let (tx, rx) = mpsc::channel(100);
+        *scheduler.sender.lock().unwrap() = Some(tx);
+        rx
+    }
+
+    /// 执行 now 时到期的任务，返回启动的任务数
+    fn fire(scheduler: &Scheduler, queue: &mut TimerQueue, now: DateTime<Local>) -> usize {
+        let sender = scheduler.sender.lock().unwrap().clone().unwrap();
+        scheduler.fire_due(queue, now, &sender)
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_pause_and_run_now() {
+        let (task, runs) = counting_task("每秒任务", "* * * * * * *", Duration::ZERO);
+        let scheduler = Scheduler::new(vec![task], Some(1), Arc::new(SystemClock));
+        // 未启动时不能手动触发
+        assert!(scheduler.run_now(None).is_err());
+        let mut rx = attach(&scheduler);
+        let mut queue = TimerQueue::default();
+        scheduler.rebuild_queue(&mut queue, at(0));
+
+        // 暂停期间启动时和到期的任务都不执行，但仍按时重新排期
+        scheduler.set_paused(true);
+        let sender = scheduler.sender.lock().unwrap().clone().unwrap();
+        assert_eq!(scheduler.run_on_startup(&sender), 0);
+        assert_eq!(scheduler.task_infos()[0].next_run, None);
+        assert_eq!(fire(&scheduler, &mut queue, at(1)), 0);
+        assert_eq!(queue.next_at(), Some(at(2)));
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+
+        // 暂停不影响手动触发，并推送进度事件
+        let mut events = scheduler.subscribe();
+        assert!(scheduler.run_now(Some("不存在")).is_err());
+        assert_eq!(scheduler.run_now(Some("每秒任务")).unwrap(), 1);
+        rx.recv().await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        let started = events.recv().await.unwrap();
+        assert_eq!(started.status, TaskStatus::Started);
+        assert!(started.manual);
+        let done = events.recv().await.unwrap();
+        assert_eq!(done.status, TaskStatus::Success);
+        assert_eq!(done.count, Some(0));
+
+        // 恢复后到期的任务按时执行
+        scheduler.set_paused(false);
+        assert_eq!(fire(&scheduler, &mut queue, at(2)), 1);
+        rx.recv().await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert!(!events.recv().await.unwrap().manual);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_task_control() {
+        let (yearly, yearly_runs) = counting_task("每年任务", "0 0 0 1 1 * *", Duration::ZERO);
+        let (secondly, secondly_runs) = counting_task("每秒任务", "* * * * * * *", Duration::ZERO);
+        let scheduler =
+            Scheduler::new(vec![yearly, secondly], Some(2), Arc::new(FixedClock(at(0))));
+        let mut rx = attach(&scheduler);
+        let mut queue = TimerQueue::default();
+        scheduler.rebuild_queue(&mut queue, at(0));
+
+        // 下次执行时间按注入的时钟计算
+        let infos = scheduler.task_infos();
+        assert_eq!(infos.len(), 2);
+        assert!(infos.iter().all(|i| !i.paused));
+        assert_eq!(infos[1].next_run, Some(at(1).timestamp_millis()));
+
+        // 单独暂停后通知调度循环重新排期，不再按时执行
+        assert!(scheduler.set_task_paused("不存在", true).is_err());
+        scheduler.set_task_paused("每秒任务", true).unwrap();
+        scheduler.changed.notified().await;
+        scheduler.rebuild_queue(&mut queue, at(0));
+        assert_eq!(fire(&scheduler, &mut queue, at(5)), 0);
+        let infos = scheduler.task_infos();
+        assert!(infos[1].paused);
+        assert_eq!(infos[1].next_run, None);
+        assert!(infos[0].next_run.is_some());
+
+        // 单独暂停的任务启动时也不执行
+        let sender = scheduler.sender.lock().unwrap().clone().unwrap();
+        assert_eq!(scheduler.run_on_startup(&sender), 1);
+        rx.recv().await.unwrap();
+        assert_eq!(yearly_runs.load(Ordering::SeqCst), 1);
+        assert_eq!(secondly_runs.load(Ordering::SeqCst), 0);
+
+        // 修改 cron 表达式后按新表达式排期
+        let yearly = scheduler.find_task("每年任务").unwrap();
+        scheduler.upsert_task(Task {
+            cron_expr: "* * * * * * *".to_string(),
+            ..Task::clone(&yearly)
+        });
+        scheduler.changed.notified().await;
+        scheduler.rebuild_queue(&mut queue, at(5));
+        assert_eq!(queue.next_at(), Some(at(6)));
+        assert_eq!(fire(&scheduler, &mut queue, at(6)), 1);
+        rx.recv().await.unwrap();
+        assert_eq!(yearly_runs.load(Ordering::SeqCst), 2);
+        let info = scheduler
+            .task_infos()
+            .into_iter()
+            .find(|i| i.name == "每年任务")
+            .unwrap();
+        assert_eq!(info.cron_expr, "* * * * * * *");
+
+        // 恢复后两个任务都按时执行
+        scheduler.set_task_paused("每秒任务", false).unwrap();
+        scheduler.changed.notified().await;
+        scheduler.rebuild_queue(&mut queue, at(6));
+        assert_eq!(fire(&scheduler, &mut queue, at(7)), 2);
+        rx.recv().await.unwrap();
+        rx.recv().await.unwrap();
+        assert_eq!(yearly_runs.load(Ordering::SeqCst), 3);
+        assert_eq!(secondly_runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_shutdown_waits_for_running_tasks() {
+        let (task, runs) = counting_task("慢任务", "0 0 0 1 1 * *", Duration::from_millis(300));
+        let scheduler = Scheduler::new(vec![task], Some(1), Arc::new(SystemClock));
         let (tx, mut rx) = mpsc::channel(100);
+        let scheduler_run = scheduler.clone();
+        let run = tokio::spawn(async move { scheduler_run.run(tx).await });
+        // 结果处理（保存）耗时 300 毫秒
+        let saved = Arc::new(AtomicBool::new(false));
+        tokio::spawn({
+            let saved = saved.clone();
+            async move {
+                while let Some(res) = rx.recv().await {
+                    sleep(Duration::from_millis(300)).await;
+                    saved.store(true, Ordering::SeqCst);
+                    drop(res);
+                }
+            }
+        });
+        sleep(Duration::from_millis(50)).await;
+
+        // 启动时的执行尚未结束，停止时等待其完成并等待结果保存
+        assert!(scheduler.shutdown(Duration::from_secs(5)).await);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(saved.load(Ordering::SeqCst));
+        timeout(Duration::from_secs(1), run).await.unwrap().unwrap();
+        assert!(scheduler.run_now(None).is_err());
+    }
 
-        let scheduler_clone = scheduler.clone();
-        tokio::spawn(async move {
-            scheduler_clone.run(tx).await;
+    #[tokio::test]
+    async fn test_scheduler_shutdown_cancels_retry_wait() {
+        use crate::command::platforms::error::FetchError;
+
+        let meta = TaskMeta {
+            name: "网络故障".into(),
+            cmd: "down".into(),
+            arg: String::new(),
+            cron_expr: "0 0 0 1 1 * *".into(),
+            retry_times: 3,
+            retry_policy: RetryPolicy {
+                base_delay_secs: 300,
+                jitter: 0.0,
+                ..RetryPolicy::default()
+            },
+            category: "anime".into(),
+        };
+        let task = Task::new(&meta, || async {
+            Err(FetchError::Network("connection refused".into()))
         });
+        let scheduler = Scheduler::new(vec![task], Some(1), Arc::new(SystemClock));
+        let mut events = scheduler.subscribe();
+        let (tx, _rx) = mpsc::channel(10);
+        let scheduler_run = scheduler.clone();
+        tokio::spawn(async move { scheduler_run.run(tx).await });
+        sleep(Duration::from_millis(50)).await;
+
+        // 第一次失败后等待 300 秒重试，停止时不再等待
+        assert!(scheduler.shutdown(Duration::from_secs(5)).await);
+        assert_eq!(events.recv().await.unwrap().status, TaskStatus::Started);
+        let failed = events.recv().await.unwrap();
+        assert_eq!(failed.status, TaskStatus::Failed);
+        assert_eq!(failed.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_execute_task_retry_classification() {
+        use crate::command::platforms::error::FetchError;
+        use std::sync::atomic::AtomicUsize;
 
-        tokio::spawn(async move {
-            while let Some(res) = rx.recv().await {
-                println!("收到 TaskResult: {:?}", res.name);
+        let policy = RetryPolicy {
+            base_delay_secs: 0,
+            ..RetryPolicy::default()
+        };
+        // 前两次网络错误，第三次成功
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let meta = TaskMeta {
+            name: "网络抖动".into(),
+            cmd: "flaky".into(),
+            arg: String::new(),
+            cron_expr: "0 0 0 1 1 * *".into(),
+            retry_times: 3,
+            retry_policy: policy.clone(),
+            category: "anime".into(),
+        };
+        let flaky = Task::new(&meta, move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if n < 2 {
+                    Err(FetchError::Network("connection reset".into()))
+                } else {
+                    Ok(ApiResponse::ok(HashMap::new()))
+                }
             }
         });
+        // 解析失败不重试
+        let parse_calls = Arc::new(AtomicUsize::new(0));
+        let counter = parse_calls.clone();
+        let meta = TaskMeta {
+            name: "页面改版".into(),
+            retry_times: 3,
+            ..meta
+        };
+        let broken = Task::new(&meta, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { Err(FetchError::Parse("未找到更新列表".into())) }
+        });
+
+        let (tx, _rx) = mpsc::channel(10);
+        let (events, mut rx) = broadcast::channel(16);
+        let (_stop, stopped) = watch::channel(false);
+        Scheduler::execute_task(
+            Arc::new(flaky),
+            tx.clone(),
+            events.clone(),
+            true,
+            stopped.clone(),
+        )
+        .await;
+        Scheduler::execute_task(Arc::new(broken), tx, events, true, stopped).await;
 
-        // 等待 25 秒观察若干次触发
-        sleep(Duration::from_secs(25)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(parse_calls.load(Ordering::SeqCst), 1);
+        let mut finished = vec![];
+        while let Ok(event) = rx.try_recv() {
+            if event.status != TaskStatus::Started {
+                finished.push(event);
+            }
+        }
+        assert_eq!(finished[0].status, TaskStatus::Success);
+        assert_eq!(finished[0].attempts, 3);
+        assert_eq!(finished[1].status, TaskStatus::Failed);
+        assert_eq!(finished[1].attempts, 1);
+        assert_eq!(
+            finished[1].message.as_deref(),
+            Some("解析失败：未找到更新列表")
+        );
     }
 }
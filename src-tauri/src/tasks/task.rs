@@ -1,22 +1,29 @@
+use crate::command::platforms::error::FetchError;
+use crate::configuration::RetryPolicy;
 use crate::types::{AniItemResult, ApiResponse};
 use async_trait::async_trait;
 use cron::Schedule;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 
 /// -----------------
 /// 配置层 TaskMeta
 /// -----------------
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TaskMeta {
     pub name: String,
     pub cmd: String,
     pub arg: String,
     pub cron_expr: String,
     pub retry_times: u8,
+    pub retry_policy: RetryPolicy,
+    /// 数据源分类，来自配置文件 datasource 下的分组名
+    pub category: String,
 }
 
 /// -----------------
@@ -24,16 +31,16 @@ pub struct TaskMeta {
 /// -----------------
 #[async_trait]
 pub trait TaskAction: Send + Sync {
-    async fn run(&self) -> Result<ApiResponse<AniItemResult>, String>;
+    async fn run(&self) -> Result<ApiResponse<AniItemResult>, FetchError>;
 }
 
 #[async_trait]
 impl<F, Fut> TaskAction for F
 where
     F: Fn() -> Fut + Send + Sync + 'static,
-    Fut: Future<Output = Result<ApiResponse<AniItemResult>, String>> + Send,
+    Fut: Future<Output = Result<ApiResponse<AniItemResult>, FetchError>> + Send,
 {
-    async fn run(&self) -> Result<ApiResponse<AniItemResult>, String> {
+    async fn run(&self) -> Result<ApiResponse<AniItemResult>, FetchError> {
         self().await
     }
 }
@@ -44,27 +51,49 @@ where
 #[derive(Clone)]
 pub struct Task {
     pub name: String,
+    pub cmd: String,
+    pub arg: String,
     pub cron_expr: String,
     pub action: Arc<dyn TaskAction>,
     pub retry_times: u8,
+    pub retry_policy: RetryPolicy,
+    pub category: String,
 }
 
 impl Task {
     pub fn new<F, Fut>(meta: &TaskMeta, action: F) -> Self
     where
         F: Fn() -> Fut + Send + Sync + 'static,
-        Fut: Future<Output = Result<ApiResponse<AniItemResult>, String>> + Send + 'static,
+        Fut: Future<Output = Result<ApiResponse<AniItemResult>, FetchError>> + Send + 'static,
     {
         Self {
             name: meta.name.clone(),
+            cmd: meta.cmd.clone(),
+            arg: meta.arg.clone(),
             cron_expr: meta.cron_expr.clone(),
             action: Arc::new(action),
             retry_times: meta.retry_times,
+            retry_policy: meta.retry_policy.clone(),
+            category: meta.category.clone(),
+        }
+    }
+
+    /// 生成该任务的配置，用于和新的任务配置对比
+    pub fn meta(&self) -> TaskMeta {
+        TaskMeta {
+            name: self.name.clone(),
+            cmd: self.cmd.clone(),
+            arg: self.arg.clone(),
+            cron_expr: self.cron_expr.clone(),
+            retry_times: self.retry_times,
+            retry_policy: self.retry_policy.clone(),
+            category: self.category.clone(),
         }
     }
 
-    pub fn schedule(&self) -> Schedule {
-        Schedule::from_str(&self.cron_expr).expect("Invalid cron expression")
+    /// 解析 cron 表达式，表达式无效时返回错误，由调用方跳过该任务
+    pub fn schedule(&self) -> Result<Schedule, cron::error::Error> {
+        Schedule::from_str(&self.cron_expr)
     }
 }
 
@@ -73,7 +102,7 @@ pub type CmdFn = Arc<
     dyn Fn(
             String,
         )
-            -> Pin<Box<dyn Future<Output = Result<ApiResponse<AniItemResult>, String>> + Send>>
+            -> Pin<Box<dyn Future<Output = Result<ApiResponse<AniItemResult>, FetchError>> + Send>>
         + Send
         + Sync,
 >;
@@ -105,6 +134,8 @@ pub fn build_tasks_from_meta(metas: &[TaskMeta], cmd_map: &HashMap<String, CmdFn
                     arg: arg.clone(),
                     cron_expr: cron_expr.clone(),
                     retry_times,
+                    retry_policy: meta.retry_policy.clone(),
+                    category: meta.category.clone(),
                 },
                 move || {
                     let cmd_fn = cmd_fn.clone();
@@ -127,11 +158,17 @@ pub fn build_tasks_from_meta(metas: &[TaskMeta], cmd_map: &HashMap<String, CmdFn
                     arg: arg.clone(),
                     cron_expr: cron_expr.clone(),
                     retry_times,
+                    retry_policy: meta.retry_policy.clone(),
+                    category: meta.category.clone(),
                 },
                 move || {
                     let missing_cmd = missing_cmd.clone();
                     let name = name.clone();
-                    async move { Err(format!("cmd '{missing_cmd}' not found for task '{name}'")) }
+                    async move {
+                        Err(FetchError::Other(format!(
+                            "cmd '{missing_cmd}' not found for task '{name}'"
+                        )))
+                    }
                 },
             );
 
@@ -142,8 +179,73 @@ pub fn build_tasks_from_meta(metas: &[TaskMeta], cmd_map: &HashMap<String, CmdFn
     tasks
 }
 
+/// 两次加载的任务配置之间的差异，按任务名对比
+#[derive(Debug, Default)]
+pub struct TaskDiff {
+    pub added: Vec<TaskMeta>,
+    pub removed: Vec<String>,
+    pub changed: Vec<TaskMeta>,
+}
+
+impl TaskDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// 对比新旧任务配置
+pub fn diff_task_metas(old: &[TaskMeta], new: &[TaskMeta]) -> TaskDiff {
+    let mut diff = TaskDiff::default();
+    for meta in new {
+        match old.iter().find(|m| m.name == meta.name) {
+            None => diff.added.push(meta.clone()),
+            Some(old_meta) if old_meta != meta => diff.changed.push(meta.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old
+        .iter()
+        .filter(|m| !new.iter().any(|n| n.name == m.name))
+        .map(|m| m.name.clone())
+        .collect();
+    diff
+}
+
 #[derive(Clone, Debug)]
 pub struct TaskResult {
     pub name: String,
     pub result: Option<AniItemResult>,
+    /// 结果处理完成前持有，全部丢弃后调度器才认为本次执行结束，停止调度时据此等待结果保存完成
+    pub pending: mpsc::Sender<()>,
+}
+
+/// 任务执行状态
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Started,
+    Success,
+    Failed,
+}
+
+/// 任务执行进度事件，推送给前端展示
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskEvent {
+    pub task_name: String,
+    pub status: TaskStatus,
+    /// 是否为手动触发
+    pub manual: bool,
+    /// 成功时为抓取到的更新数
+    pub count: Option<usize>,
+    /// 失败原因
+    pub message: Option<String>,
+    /// 已尝试次数（含重试）
+    pub attempts: u32,
+    /// 解析告警，如页面结构变化导致没有解析到更新
+    pub warnings: Vec<String>,
+    /// 本次执行的开始时间（毫秒时间戳）
+    pub started_time: i64,
+    /// 事件发生时间（毫秒时间戳）
+    pub time: i64,
 }
@@ -0,0 +1,179 @@
+//! 定时任务的触发队列：按下次触发时间排序的小顶堆，每个任务触发后单独重新排期
+
+use crate::tasks::task::Task;
+use chrono::{DateTime, Local, TimeDelta};
+use log::warn;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// 墙上时间回拨超过该值时重建队列
+pub const CLOCK_JUMP_TOLERANCE: TimeDelta = TimeDelta::seconds(5);
+
+#[derive(Default)]
+pub struct TimerQueue {
+    /// (触发时间, 任务名)，同一时间的任务按名称排序
+    heap: BinaryHeap<Reverse<(DateTime<Local>, String)>>,
+    /// 每个任务最近一次触发对应的计划时间，避免同一时间点重复触发
+    last_fired: HashMap<String, DateTime<Local>>,
+}
+
+impl TimerQueue {
+    /// 按当前任务列表重建队列，单独暂停的任务不排期
+    pub fn rebuild(&mut self, tasks: &[Arc<Task>], paused: &HashSet<String>, now: DateTime<Local>) {
+        self.heap.clear();
+        // 已删除的任务不再保留触发记录
+        self.last_fired
+            .retain(|name, _| tasks.iter().any(|t| &t.name == name));
+        for task in tasks {
+            if !paused.contains(&task.name) {
+                self.arm(task, now);
+            }
+        }
+    }
+
+    /// 墙上时间回拨后，之前的触发记录已不可信，全部清空后重建
+    pub fn reset(&mut self, tasks: &[Arc<Task>], paused: &HashSet<String>, now: DateTime<Local>) {
+        self.last_fired.clear();
+        self.rebuild(tasks, paused, now);
+    }
+
+    /// 计算任务在 now 之后（且晚于上次触发）的下一次触发时间并入队，cron 表达式无效的任务不排期
+    pub fn arm(&mut self, task: &Task, now: DateTime<Local>) {
+        let schedule = match task.schedule() {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                warn!("任务 [{}] 的 cron 表达式无效，跳过排期：{e}", task.name);
+                return;
+            }
+        };
+        let from = match self.last_fired.get(&task.name) {
+            Some(last) if *last > now => *last,
+            _ => now,
+        };
+        if let Some(next) = schedule.after(&from).next() {
+            self.heap.push(Reverse((next, task.name.clone())));
+        }
+    }
+
+    /// 最早的触发时间
+    pub fn next_at(&self) -> Option<DateTime<Local>> {
+        self.heap.peek().map(|Reverse((at, _))| *at)
+    }
+
+    /// 取出所有已到期的任务（同一任务只取一次），并记录触发时间
+    ///
+    /// 休眠唤醒后错过的多次触发会合并为一次
+    pub fn pop_due(&mut self, now: DateTime<Local>) -> Vec<(DateTime<Local>, String)> {
+        let mut due: Vec<(DateTime<Local>, String)> = vec![];
+        while let Some(Reverse((at, _))) = self.heap.peek() {
+            if *at > now {
+                break;
+            }
+            let Reverse((at, name)) = self.heap.pop().unwrap();
+            if self.last_fired.get(&name).is_some_and(|last| *last >= at)
+                || due.iter().any(|(_, n)| *n == name)
+            {
+                continue;
+            }
+            self.last_fired.insert(name.clone(), at);
+            due.push((at, name));
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::RetryPolicy;
+    use crate::tasks::task::TaskMeta;
+    use crate::types::ApiResponse;
+    use chrono::TimeZone;
+    use std::collections::HashMap as Map;
+
+    fn task(name: &str, cron_expr: &str) -> Arc<Task> {
+        let meta = TaskMeta {
+            name: name.into(),
+            cmd: "noop".into(),
+            arg: String::new(),
+            cron_expr: cron_expr.into(),
+            retry_times: 0,
+            retry_policy: RetryPolicy::default(),
+            category: "anime".into(),
+        };
+        Arc::new(Task::new(&meta, || async {
+            Ok(ApiResponse::ok(Map::new()))
+        }))
+    }
+
+    fn at(h: u32, m: u32, s: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 7, 13, h, m, s).unwrap()
+    }
+
+    #[test]
+    fn test_timer_queue_rearm_each_task() {
+        let tasks = vec![
+            task("每10秒", "0/10 * * * * * *"),
+            task("每15秒", "0/15 * * * * * *"),
+            task("同时A", "0/30 * * * * * *"),
+        ];
+        let mut queue = TimerQueue::default();
+        queue.rebuild(&tasks, &HashSet::new(), at(8, 0, 1));
+        assert_eq!(queue.next_at(), Some(at(8, 0, 10)));
+
+        // 只取出到期的任务，其余任务保持原排期
+        let due = queue.pop_due(at(8, 0, 10));
+        assert_eq!(due, vec![(at(8, 0, 10), "每10秒".to_string())]);
+        queue.arm(&tasks[0], at(8, 0, 10));
+        assert_eq!(queue.next_at(), Some(at(8, 0, 15)));
+
+        let due = queue.pop_due(at(8, 0, 15));
+        assert_eq!(due, vec![(at(8, 0, 15), "每15秒".to_string())]);
+        queue.arm(&tasks[1], at(8, 0, 15));
+        assert_eq!(queue.pop_due(at(8, 0, 20)).len(), 1);
+        queue.arm(&tasks[0], at(8, 0, 20));
+
+        // 同一时间点的多个任务一起取出，按名称排序
+        let due = queue.pop_due(at(8, 0, 30));
+        let names: Vec<_> = due.iter().map(|(_, n)| n.as_str()).collect();
+        assert_eq!(names, vec!["同时A", "每10秒", "每15秒"]);
+    }
+
+    #[test]
+    fn test_timer_queue_no_double_firing() {
+        let tasks = vec![task("每10秒", "0/10 * * * * * *")];
+        let mut queue = TimerQueue::default();
+        queue.rebuild(&tasks, &HashSet::new(), at(8, 0, 1));
+        assert_eq!(queue.pop_due(at(8, 0, 10)).len(), 1);
+
+        // 触发后在同一时间点重建（如修改配置），不会再次触发同一时间点
+        queue.rebuild(&tasks, &HashSet::new(), at(8, 0, 9));
+        assert_eq!(queue.next_at(), Some(at(8, 0, 20)));
+        assert!(queue.pop_due(at(8, 0, 10)).is_empty());
+
+        // 休眠唤醒后错过的多次触发合并为一次，并从当前时间重新排期
+        let due = queue.pop_due(at(9, 30, 5));
+        assert_eq!(due, vec![(at(8, 0, 20), "每10秒".to_string())]);
+        queue.arm(&tasks[0], at(9, 30, 5));
+        assert_eq!(queue.next_at(), Some(at(9, 30, 10)));
+
+        // 时间回拨后重置触发记录，按新的时间排期
+        queue.reset(&tasks, &HashSet::new(), at(7, 0, 0));
+        assert_eq!(queue.next_at(), Some(at(7, 0, 10)));
+        assert_eq!(queue.pop_due(at(7, 0, 10)).len(), 1);
+
+        // 单独暂停的任务不排期
+        queue.rebuild(&tasks, &HashSet::from(["每10秒".to_string()]), at(7, 0, 10));
+        assert_eq!(queue.next_at(), None);
+
+        // cron 表达式无效的任务跳过，不影响其它任务
+        let tasks = vec![task("无效", "每小时"), task("每10秒", "0/10 * * * * * *")];
+        queue.reset(&tasks, &HashSet::new(), at(7, 0, 0));
+        assert_eq!(
+            queue.pop_due(at(7, 0, 10)),
+            vec![(at(7, 0, 10), "每10秒".to_string())]
+        );
+        assert_eq!(queue.next_at(), None);
+    }
+}
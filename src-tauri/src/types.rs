@@ -38,7 +38,7 @@ pub struct PageData<T> {
     pub page_size: i64, // 每页数量
 }
 
-/// 定义结果类型：星期字符串 -> 番剧更新列表
+/// 定义结果类型：更新日期（YYYY/MM/DD） -> 番剧更新列表
 pub type AniItemResult = HashMap<String, Vec<AniItem>>;
 
 #[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
@@ -50,4 +50,15 @@ pub struct AniItem {
     pub detail_url: String,
     pub update_time: String,
     pub platform: String,
+    /// 番剧在平台上的唯一标识（如 B 站 season_id），为空表示未知
+    #[serde(default)]
+    #[sqlx(default)]
+    pub external_id: String,
+    /// 数据源分类（anime / drama 等），由抓取任务按配置填写，为空时按动漫保存
+    #[serde(default)]
+    #[sqlx(default)]
+    pub category: String,
 }
+
+/// 未指定分类时的默认分类
+pub const DEFAULT_CATEGORY: &str = "anime";
@@ -1,8 +1,99 @@
-use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Utc};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc};
+use log::warn;
 use once_cell::sync::Lazy;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use thiserror::Error;
 
+/// 用于调试时覆盖“今天”的环境变量，格式为 `YYYY-MM-DD` 或 `YYYY/MM/DD`
+pub const TODAY_OVERRIDE_ENV: &str = "ANI_TODO_TODAY";
+
+/// 时钟抽象：所有需要“现在/今天”的地方都通过它获取，便于测试和复现问题
+pub trait Clock: Send + Sync {
+    /// 当前本地时间
+    fn now(&self) -> DateTime<Local>;
+
+    /// 今天的日期
+    fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    /// 当前时间戳（毫秒）
+    fn now_millis(&self) -> i64 {
+        self.now().timestamp_millis()
+    }
+}
+
+/// 系统时钟，直接读取墙上时间
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// 固定时钟，始终返回同一时刻，用于测试
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Local>);
+
+impl FixedClock {
+    /// 固定在指定日期的中午 12 点（避开夏令时切换导致的本地时间歧义）
+    pub fn at_date(date: NaiveDate) -> Self {
+        let noon = date.and_hms_opt(12, 0, 0).expect("invalid time");
+        let now = Local
+            .from_local_datetime(&noon)
+            .earliest()
+            .expect("invalid local time");
+        Self(now)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Local> {
+        self.0
+    }
+}
+
+/// 偏移时钟：把“今天”假装成指定日期，但时间仍正常流逝，用于调试
+#[derive(Debug, Clone, Copy)]
+pub struct ShiftedClock {
+    offset: Duration,
+}
+
+impl ShiftedClock {
+    pub fn pretend_today(date: NaiveDate) -> Self {
+        let offset = date.signed_duration_since(Local::now().date_naive());
+        Self { offset }
+    }
+}
+
+impl Clock for ShiftedClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now() + self.offset
+    }
+}
+
+/// 根据环境变量 [`TODAY_OVERRIDE_ENV`] 创建时钟，未设置或格式不正确时使用系统时钟
+pub fn clock_from_env() -> Arc<dyn Clock> {
+    let Ok(value) = std::env::var(TODAY_OVERRIDE_ENV) else {
+        return Arc::new(SystemClock);
+    };
+    let value = value.trim();
+    match NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y/%m/%d"))
+    {
+        Ok(date) => {
+            warn!("已通过 {TODAY_OVERRIDE_ENV} 将今天设置为 {date}");
+            Arc::new(ShiftedClock::pretend_today(date))
+        }
+        Err(e) => {
+            warn!("{TODAY_OVERRIDE_ENV}={value} 格式不正确，将使用系统时钟: {e}");
+            Arc::new(SystemClock)
+        }
+    }
+}
+
 /// 常用日期格式枚举
 pub enum DateFormat {
     Iso,       // "%Y-%m-%d"
@@ -29,6 +120,11 @@ pub fn format_now(fmt: DateFormat) -> String {
     Local::now().format(get_format_str(fmt)).to_string()
 }
 
+/// 格式化指定日期为指定格式
+pub fn format_date(date: NaiveDate, fmt: DateFormat) -> String {
+    date.format(get_format_str(fmt)).to_string()
+}
+
 /// 将时间戳（秒）转为字符串
 pub fn timestamp_to_date_string(t: i64, fmt: DateFormat) -> String {
     let dt = unix_seconds_to_timestamp(t);
@@ -103,7 +199,12 @@ pub struct WeekdayInfo {
 
 /// 获取今天是星期几（中文名 + 索引）
 pub fn get_today_weekday() -> WeekdayInfo {
-    let today = Local::now().weekday();
+    get_weekday(Local::now().date_naive())
+}
+
+/// 获取指定日期是星期几（中文名 + 索引）
+pub fn get_weekday(date: NaiveDate) -> WeekdayInfo {
+    let today = date.weekday();
 
     // 按照 Monday=0 排列的中文星期名称数组
     const WEEKDAY_CN: [&str; 7] = [
@@ -127,6 +228,23 @@ pub fn get_today_weekday() -> WeekdayInfo {
     }
 }
 
+/// 一天的毫秒数
+pub const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// 获取 `today` 当天或之前最近一个星期 `num_from_mon`（星期一为 0）的日期
+///
+/// 平台按星期列出的追番表展示的是各星期最近一次的更新，用它还原真实的更新日期
+pub fn latest_weekday_on_or_before(today: NaiveDate, num_from_mon: u32) -> NaiveDate {
+    let today_idx = get_weekday(today).num_from_mon as i64;
+    let back = (today_idx - num_from_mon as i64).rem_euclid(7);
+    today - Duration::days(back)
+}
+
+/// 获取指定日期所在周的星期一
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(get_weekday(date).num_from_mon as i64)
+}
+
 /// 错误类型：日期解析
 #[derive(Debug, Error)]
 pub enum DateParseError {
@@ -158,6 +276,11 @@ pub fn parse_date_to_millis(s: &str, use_local: bool) -> Result<i64, DateParseEr
     Ok(millis)
 }
 
+/// 将日期转换为当天本地零点的 Unix 毫秒时间戳
+pub fn date_to_millis(date: NaiveDate) -> Result<i64, DateParseError> {
+    parse_date_to_millis(&format_date(date, DateFormat::Slash), true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -193,6 +316,55 @@ mod tests {
         assert!(w.name_cn.starts_with("星期"));
     }
 
+    #[test]
+    fn test_fixed_clock() {
+        let date = NaiveDate::from_ymd_opt(2025, 7, 13).unwrap();
+        let clock = FixedClock::at_date(date);
+        assert_eq!(clock.today(), date);
+        assert_eq!(clock.now(), clock.now());
+        assert_eq!(get_weekday(clock.today()).name_cn, "星期日");
+    }
+
+    #[test]
+    fn test_shifted_clock() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let clock = ShiftedClock::pretend_today(date);
+        assert_eq!(clock.today(), date);
+        // 时间仍会正常流逝
+        assert!(clock.now() <= clock.now());
+    }
+
+    #[test]
+    fn test_get_weekday() {
+        let date = NaiveDate::from_ymd_opt(2025, 7, 13).unwrap();
+        let w = get_weekday(date);
+        assert_eq!(w.name_cn, "星期日");
+        assert_eq!(w.num_from_mon, 6);
+        assert_eq!(w.num_from_sun, 0);
+        assert_eq!(format_date(date, DateFormat::Slash), "2025/07/13");
+    }
+
+    #[test]
+    fn test_latest_weekday_on_or_before() {
+        // 2025/07/13 是星期日
+        let today = NaiveDate::from_ymd_opt(2025, 7, 13).unwrap();
+        assert_eq!(latest_weekday_on_or_before(today, 6), today);
+        assert_eq!(
+            latest_weekday_on_or_before(today, 0),
+            NaiveDate::from_ymd_opt(2025, 7, 7).unwrap()
+        );
+        // 星期一时，星期二对应的是上周二
+        let monday = NaiveDate::from_ymd_opt(2025, 7, 14).unwrap();
+        assert_eq!(
+            latest_weekday_on_or_before(monday, 1),
+            NaiveDate::from_ymd_opt(2025, 7, 8).unwrap()
+        );
+        assert_eq!(
+            week_start(today),
+            NaiveDate::from_ymd_opt(2025, 7, 7).unwrap()
+        );
+    }
+
     #[test]
     fn test_timestamp_to_date_string() {
         let s = timestamp_to_date_string(1752768000, DateFormat::Slash);
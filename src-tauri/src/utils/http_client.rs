@@ -1,44 +1,302 @@
-use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::Client;
-
-/// 构造带通用请求头的 Reqwest 客户端
-/// 返回错误时，会转换为字符串
-pub fn http_client() -> Result<Client, String> {
-    let mut headers = HeaderMap::new();
-    headers.insert("Accept", HeaderValue::from_static(
-        "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7"
-    ));
-    // headers.insert("Accept-Encoding", HeaderValue::from_static("gzip, deflate, br, zstd"));
-    headers.insert(
-        "Accept-Language",
-        HeaderValue::from_static("zh-CN,zh;q=0.9,en;q=0.8"),
-    );
-    headers.insert("Cache-Control", HeaderValue::from_static("no-cache"));
-    headers.insert("Cookie", HeaderValue::from_static("cleanMode=0"));
-    headers.insert("DNT", HeaderValue::from_static("1"));
-    headers.insert("Pragma", HeaderValue::from_static("no-cache"));
-    headers.insert("priority", HeaderValue::from_static("u=0, i"));
-    headers.insert(
-        "Sec-Ch-Ua",
-        HeaderValue::from_static(
-            "Not)A;Brand\";v=\"8\", \"Chromium\";v=\"138\", \"Google Chrome\";v=\"138\"",
-        ),
-    );
-    headers.insert(
-        "sec-ch-ua-platform",
-        HeaderValue::from_static("\"Windows\""),
-    );
-    headers.insert("Sec-Fetch-Dest", HeaderValue::from_static("document"));
-    headers.insert("Sec-Fetch-Mode", HeaderValue::from_static("navigate"));
-    headers.insert("Sec-Fetch-Site", HeaderValue::from_static("none"));
-    headers.insert("Sec-Fetch-User", HeaderValue::from_static("?1"));
-    headers.insert("Upgrade-Insecure-Requests", HeaderValue::from_static("1"));
-    headers.insert("User-Agent", HeaderValue::from_static(
-        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/138.0.0.0 Safari/537.36"
-    ));
-
-    Client::builder()
-        .default_headers(headers)
-        .build()
-        .map_err(|e| e.to_string())
+use crate::command::platforms::error::{check_response, response_retry_after, FetchError};
+use crate::configuration::{HttpClientConfig, RateLimitConfig};
+use crate::utils::rate_limit::RateLimiter;
+use anyhow::{anyhow, Context, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use reqwest::{Certificate, Client, Method, Proxy, RequestBuilder, Response, StatusCode};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// 默认的 User-Agent，可在配置文件的 http_client.user_agent 中修改
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/138.0.0.0 Safari/537.36";
+
+/// 请求头模板：不同平台、不同资源按各自的方式请求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderProfile {
+    /// 只携带 User-Agent
+    Minimal,
+    /// 请求 JSON 接口
+    Api,
+    /// 像浏览器一样打开页面
+    Browser,
+    /// 请求图片
+    Image,
+}
+
+impl HeaderProfile {
+    fn headers(self) -> Vec<(&'static str, &'static str)> {
+        match self {
+            HeaderProfile::Minimal => vec![],
+            HeaderProfile::Api => vec![
+                ("Accept", "application/json, text/plain, */*"),
+                ("Accept-Language", "zh-CN,zh;q=0.9,en;q=0.8"),
+            ],
+            HeaderProfile::Browser => vec![
+                ("Accept", "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8"),
+                ("Accept-Language", "zh-CN,zh;q=0.9,en;q=0.8"),
+                ("Cache-Control", "no-cache"),
+                ("Cookie", "cleanMode=0"),
+                ("DNT", "1"),
+                ("Pragma", "no-cache"),
+                ("Sec-Fetch-Dest", "document"),
+                ("Sec-Fetch-Mode", "navigate"),
+                ("Sec-Fetch-Site", "none"),
+                ("Sec-Fetch-User", "?1"),
+                ("Upgrade-Insecure-Requests", "1"),
+            ],
+            HeaderProfile::Image => vec![
+                ("Accept", "image/avif,image/webp,image/apng,image/*,*/*;q=0.8"),
+                ("Accept-Language", "zh-CN,zh;q=0.9,en;q=0.8"),
+                ("Sec-Fetch-Dest", "image"),
+                ("Sec-Fetch-Mode", "no-cors"),
+                ("Sec-Fetch-Site", "cross-site"),
+            ],
+        }
+    }
+}
+
+/// 全局共享的 HTTP 客户端：复用连接池，统一超时、代理和证书，按平台生成请求头，按站点限流
+pub struct HttpClient {
+    client: Client,
+    user_agent: HeaderValue,
+    /// 各平台追加或覆盖的请求头，值为 None 时不发送该请求头
+    overrides: HashMap<String, Vec<(HeaderName, Option<HeaderValue>)>>,
+    rate_limit: RateLimitConfig,
+    rate_limits: HashMap<String, RateLimitConfig>,
+    limiter: RateLimiter,
+    /// 配置的根证书（PEM），邮件推送等不经过 reqwest 的连接同样信任
+    ca_cert: Option<Vec<u8>>,
+}
+
+impl HttpClient {
+    /// 按配置创建客户端，config_dir 用于解析证书的相对路径
+    pub fn new(config: &HttpClientConfig, config_dir: &Path) -> Result<Self> {
+        let mut builder = Client::builder()
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(config.read_timeout_secs));
+        if !config.proxy.trim().is_empty() {
+            let proxy = Proxy::all(config.proxy.trim())
+                .with_context(|| format!("代理地址无效：{}", config.proxy))?;
+            builder = builder.proxy(proxy);
+        }
+        let mut ca_cert = None;
+        if !config.ca_cert.trim().is_empty() {
+            let path = config_dir.join(config.ca_cert.trim());
+            let pem = fs::read(&path).with_context(|| format!("读取证书 {path:?} 失败"))?;
+            let certs = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("证书 {path:?} 格式无效"))?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+            ca_cert = Some(pem);
+        }
+        let client = builder.build().context("创建 HTTP 客户端失败")?;
+
+        let user_agent = HeaderValue::from_str(&config.user_agent)
+            .map_err(|_| anyhow!("User-Agent 无效：{}", config.user_agent))?;
+        let mut overrides = HashMap::new();
+        for (platform, headers) in &config.headers {
+            let mut list = Vec::new();
+            for (name, value) in headers {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| anyhow!("平台 {platform} 的请求头名称无效：{name}"))?;
+                let value = if value.is_empty() {
+                    None
+                } else {
+                    Some(
+                        HeaderValue::from_str(value)
+                            .map_err(|_| anyhow!("平台 {platform} 的请求头 {name} 的值无效"))?,
+                    )
+                };
+                list.push((name, value));
+            }
+            overrides.insert(platform.clone(), list);
+        }
+        Ok(Self {
+            client,
+            user_agent,
+            overrides,
+            rate_limit: config.rate_limit.clone(),
+            rate_limits: config.rate_limits.clone(),
+            limiter: RateLimiter::default(),
+            ca_cert,
+        })
+    }
+
+    /// 配置的根证书（PEM）
+    pub fn ca_cert(&self) -> Option<&[u8]> {
+        self.ca_cert.as_deref()
+    }
+
+    /// 按请求头模板和平台配置生成请求头
+    pub fn headers(&self, platform: &str, profile: HeaderProfile) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, self.user_agent.clone());
+        for (name, value) in profile.headers() {
+            headers.insert(name, HeaderValue::from_static(value));
+        }
+        for (name, value) in self.overrides.get(platform).into_iter().flatten() {
+            match value {
+                Some(value) => headers.insert(name.clone(), value.clone()),
+                None => headers.remove(name),
+            };
+        }
+        headers
+    }
+
+    /// 构造 GET 请求，携带该平台的请求头
+    pub fn get(&self, platform: &str, profile: HeaderProfile, url: &str) -> RequestBuilder {
+        self.client
+            .get(url)
+            .headers(self.headers(platform, profile))
+    }
+
+    /// 构造不属于任何平台的请求（如推送通知），只携带 User-Agent，不限流
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .header(USER_AGENT, self.user_agent.clone())
+    }
+
+    /// 平台的限流配置，没有单独配置时使用默认配置
+    fn rate_limit(&self, platform: &str) -> &RateLimitConfig {
+        self.rate_limits.get(platform).unwrap_or(&self.rate_limit)
+    }
+
+    /// 按站点限流后发送请求并检查状态码；服务器返回 429、503 并带有 Retry-After 时，
+    /// 在要求的时间内暂停请求该站点（包括其它任务和图片请求）
+    pub async fn send(
+        &self,
+        platform: &str,
+        request: RequestBuilder,
+    ) -> Result<Response, FetchError> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let config = self.rate_limit(platform);
+        let _permit = self.limiter.acquire(&host, config).await;
+        let resp = client.execute(request).await?;
+        if matches!(
+            resp.status(),
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+        ) {
+            if let Some(delay) = response_retry_after(&resp) {
+                self.limiter.pause(&host, delay);
+            }
+        }
+        check_response(resp)
+    }
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new(&HttpClientConfig::default(), Path::new("")).expect("创建默认 HTTP 客户端失败")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode as AxumStatus;
+    use axum::routing::get;
+    use axum::Router;
+    use reqwest::header::{ACCEPT, ACCEPT_LANGUAGE};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_http_client_headers() {
+        let mut config = HttpClientConfig {
+            user_agent: "AniTodo/1.0".to_string(),
+            ..HttpClientConfig::default()
+        };
+        config.headers.insert(
+            "youku".to_string(),
+            HashMap::from([
+                ("Cookie".to_string(), String::new()),
+                ("X-Test".to_string(), "1".to_string()),
+            ]),
+        );
+        let http = HttpClient::new(&config, Path::new("")).unwrap();
+
+        let headers = http.headers("bilibili", HeaderProfile::Api);
+        assert_eq!(headers[USER_AGENT], "AniTodo/1.0");
+        assert_eq!(headers[ACCEPT], "application/json, text/plain, */*");
+        assert!(headers.contains_key(ACCEPT_LANGUAGE));
+        assert_eq!(http.headers("tencent", HeaderProfile::Minimal).len(), 1);
+
+        // 平台配置覆盖模板：值为空时不发送
+        let headers = http.headers("youku", HeaderProfile::Browser);
+        assert!(!headers.contains_key("Cookie"));
+        assert_eq!(headers["X-Test"], "1");
+        assert!(http
+            .headers("agedm", HeaderProfile::Browser)
+            .contains_key("Cookie"));
+
+        // 代理、证书、请求头无效时创建失败
+        for config in [
+            HttpClientConfig {
+                proxy: "not a proxy".to_string(),
+                ..HttpClientConfig::default()
+            },
+            HttpClientConfig {
+                ca_cert: "missing.pem".to_string(),
+                ..HttpClientConfig::default()
+            },
+            HttpClientConfig {
+                user_agent: "bad\nagent".to_string(),
+                ..HttpClientConfig::default()
+            },
+        ] {
+            assert!(HttpClient::new(&config, Path::new("")).is_err());
+        }
+        assert!(HttpClient::new(
+            &HttpClientConfig {
+                proxy: "socks5h://127.0.0.1:1080".to_string(),
+                ..HttpClientConfig::default()
+            },
+            Path::new("")
+        )
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_classify() {
+        let app = Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route(
+                "/limited",
+                get(|| async {
+                    let mut headers = HeaderMap::new();
+                    headers.insert("Retry-After", "7".parse().unwrap());
+                    (AxumStatus::TOO_MANY_REQUESTS, headers, "")
+                }),
+            )
+            .route("/down", get(|| async { AxumStatus::BAD_GATEWAY }))
+            .route("/missing", get(|| async { AxumStatus::NOT_FOUND }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let http = HttpClient::default();
+        let send = |url: String| http.send("test", http.get("test", HeaderProfile::Minimal, &url));
+
+        assert!(send(format!("http://{addr}/ok")).await.is_ok());
+        let err = send(format!("http://{addr}/down")).await.unwrap_err();
+        assert!(matches!(err, FetchError::Status { status: 502, .. }));
+        assert!(err.is_transient());
+        let err = send(format!("http://{addr}/missing")).await.unwrap_err();
+        assert!(!err.is_transient());
+
+        // 连接被拒绝属于网络错误
+        let err = send("http://127.0.0.1:1/".to_string()).await.unwrap_err();
+        assert!(matches!(err, FetchError::Network(_)));
+        assert!(err.is_transient());
+
+        // 被限流时读取 Retry-After（之后该站点暂停请求，放在最后）
+        let err = send(format!("http://{addr}/limited")).await.unwrap_err();
+        assert!(err.is_transient());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(7)));
+        assert!(!FetchError::Parse("layout".into()).is_transient());
+    }
 }
@@ -1,5 +1,7 @@
 pub mod date_utils;
 pub mod http_client;
+pub mod rate_limit;
+pub mod title;
 
 /// 从文本中提取第一个连续数字序列，解析为 i32，若没有则返回 None。
 pub fn extract_number(text: &str) -> Option<i32> {
@@ -0,0 +1,268 @@
+use crate::configuration::RateLimitConfig;
+use log::info;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{sleep, Duration, Instant};
+
+/// 服务器要求暂停的最长时间，避免异常的 Retry-After 让站点长时间不可用
+const MAX_PAUSE: Duration = Duration::from_secs(600);
+
+/// 令牌桶状态
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(config: &RateLimitConfig, now: Instant) -> Self {
+        Self {
+            tokens: config.burst.max(1) as f64,
+            updated: now,
+        }
+    }
+
+    /// 尝试取出一个令牌，取不到时返回需要等待的时间
+    fn try_take(&mut self, config: &RateLimitConfig, now: Instant) -> Option<Duration> {
+        if config.requests_per_second <= 0.0 {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        let burst = config.burst.max(1) as f64;
+        self.tokens = (self.tokens + elapsed * config.requests_per_second).min(burst);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) / config.requests_per_second,
+            ))
+        }
+    }
+}
+
+/// 同一站点的令牌桶和并发上限
+struct Limiter {
+    config: RateLimitConfig,
+    bucket: Mutex<Bucket>,
+    concurrency: Arc<Semaphore>,
+}
+
+/// 单个站点的限流状态
+#[derive(Default)]
+struct HostState {
+    /// 服务器要求暂停（Retry-After）到该时刻，对该站点的所有请求生效
+    paused_until: Option<Instant>,
+    /// 站点的限流器，所有平台共用
+    limiter: Option<Arc<Limiter>>,
+}
+
+/// 按站点（域名）限流：同一站点的请求共用令牌桶和并发上限，定时抓取和图片请求共用
+#[derive(Default)]
+pub struct RateLimiter {
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl RateLimiter {
+    /// 取得该站点的限流器，首次使用时按 config 创建
+    ///
+    /// 多个平台请求同一站点时共用一个限流器，使用最先请求的平台的配置，保证站点承受的总请求量不超过该配置
+    fn limiter(&self, host: &str, config: &RateLimitConfig) -> Arc<Limiter> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_default();
+        state
+            .limiter
+            .get_or_insert_with(|| {
+                Arc::new(Limiter {
+                    config: config.clone(),
+                    bucket: Mutex::new(Bucket::new(config, Instant::now())),
+                    concurrency: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+                })
+            })
+            .clone()
+    }
+
+    /// 该站点还需暂停的时间
+    fn paused_for(&self, host: &str, now: Instant) -> Option<Duration> {
+        let hosts = self.hosts.lock().unwrap();
+        let until = hosts.get(host)?.paused_until?;
+        (now < until).then(|| until - now)
+    }
+
+    /// 等待该站点的暂停结束、并发名额和令牌，名额在返回值释放时归还
+    ///
+    /// 暂停和等待令牌期间不占用并发名额，避免站点暂停时其它请求也拿不到名额
+    pub async fn acquire(&self, host: &str, config: &RateLimitConfig) -> OwnedSemaphorePermit {
+        let limiter = self.limiter(host, config);
+        loop {
+            if let Some(wait) = self.paused_for(host, Instant::now()) {
+                sleep(wait).await;
+                continue;
+            }
+            let permit = limiter
+                .concurrency
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("限流信号量不会被关闭");
+            // 等待名额期间站点可能要求暂停，归还名额后重新等待
+            if self.paused_for(host, Instant::now()).is_some() {
+                continue;
+            }
+            let wait = limiter
+                .bucket
+                .lock()
+                .unwrap()
+                .try_take(&limiter.config, Instant::now());
+            match wait {
+                None => return permit,
+                Some(wait) => {
+                    drop(permit);
+                    sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// 按服务器要求（Retry-After）暂停请求该站点，较短的暂停不会覆盖较长的
+    pub fn pause(&self, host: &str, delay: Duration) {
+        let delay = delay.min(MAX_PAUSE);
+        info!("站点 {host} 要求 {} 秒后再请求", delay.as_secs());
+        let until = Instant::now() + delay;
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_default();
+        if state.paused_until.is_none_or(|current| current < until) {
+            state.paused_until = Some(until);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let config = RateLimitConfig {
+            requests_per_second: 2.0,
+            burst: 2,
+            max_concurrent: 1,
+        };
+        let start = Instant::now();
+        let mut bucket = Bucket::new(&config, start);
+
+        // 先用完突发的令牌，之后每 0.5 秒补充一个
+        assert_eq!(bucket.try_take(&config, start), None);
+        assert_eq!(bucket.try_take(&config, start), None);
+        assert_eq!(
+            bucket.try_take(&config, start),
+            Some(Duration::from_millis(500))
+        );
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.try_take(&config, later), None);
+        assert!(bucket.try_take(&config, later).is_some());
+
+        // 长时间空闲后令牌不超过桶容量
+        let idle = later + Duration::from_secs(60);
+        assert_eq!(bucket.try_take(&config, idle), None);
+        assert_eq!(bucket.try_take(&config, idle), None);
+        assert!(bucket.try_take(&config, idle).is_some());
+
+        // 不限速时总能取到令牌
+        let unlimited = RateLimitConfig {
+            requests_per_second: 0.0,
+            ..config
+        };
+        let mut bucket = Bucket::new(&unlimited, start);
+        for _ in 0..10 {
+            assert_eq!(bucket.try_take(&unlimited, start), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap() {
+        let config = RateLimitConfig {
+            requests_per_second: 0.0,
+            burst: 1,
+            max_concurrent: 2,
+        };
+        let limiter = RateLimiter::default();
+        let first = limiter.acquire("example.com", &config).await;
+        let _second = limiter.acquire("example.com", &config).await;
+        // 不同站点互不影响
+        let _other = limiter.acquire("example.org", &config).await;
+        let third = tokio::time::timeout(
+            Duration::from_millis(50),
+            limiter.acquire("example.com", &config),
+        );
+        assert!(third.await.is_err());
+        drop(first);
+        let third = tokio::time::timeout(
+            Duration::from_millis(50),
+            limiter.acquire("example.com", &config),
+        );
+        assert!(third.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_host_shared_across_platforms() {
+        let strict = RateLimitConfig {
+            requests_per_second: 0.0,
+            burst: 1,
+            max_concurrent: 1,
+        };
+        let loose = RateLimitConfig {
+            max_concurrent: 2,
+            ..strict.clone()
+        };
+        let limiter = RateLimiter::default();
+        // 同一站点的所有平台共用最先使用的配置，宽松的配置不会增加站点的并发
+        let first = limiter.acquire("example.com", &strict).await;
+        let second = tokio::time::timeout(
+            Duration::from_millis(50),
+            limiter.acquire("example.com", &loose),
+        );
+        assert!(second.await.is_err());
+        drop(first);
+        let _second = limiter.acquire("example.com", &loose).await;
+        let third = tokio::time::timeout(
+            Duration::from_millis(50),
+            limiter.acquire("example.com", &loose),
+        );
+        assert!(third.await.is_err());
+
+        // 其它站点按各自首次使用的配置
+        let _first = limiter.acquire("example.org", &loose).await;
+        let _second = limiter.acquire("example.org", &strict).await;
+    }
+
+    #[tokio::test]
+    async fn test_pause_releases_concurrency() {
+        let config = RateLimitConfig {
+            requests_per_second: 0.0,
+            burst: 1,
+            max_concurrent: 1,
+        };
+        let limiter = Arc::new(RateLimiter::default());
+        // 较短的暂停不会覆盖较长的
+        limiter.pause("example.com", Duration::from_millis(300));
+        limiter.pause("example.com", Duration::from_millis(100));
+
+        // 暂停期间等待的请求不占用并发名额
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            let config = config.clone();
+            async move {
+                let start = Instant::now();
+                let _permit = limiter.acquire("example.com", &config).await;
+                start.elapsed()
+            }
+        });
+        sleep(Duration::from_millis(50)).await;
+        let concurrency = limiter.limiter("example.com", &config).concurrency.clone();
+        assert_eq!(concurrency.available_permits(), 1);
+        assert!(waiting.await.unwrap() >= Duration::from_millis(200));
+        assert_eq!(limiter.paused_for("example.com", Instant::now()), None);
+    }
+}
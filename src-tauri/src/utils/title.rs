@@ -0,0 +1,233 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// 繁体到简体的单字对照表
+static T2S_TABLE: Lazy<HashMap<char, char>> = Lazy::new(|| {
+    include_str!("t2s.txt")
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .filter_map(|pair| {
+            let mut chars = pair.chars();
+            Some((chars.next()?, chars.next()?))
+        })
+        .collect()
+});
+
+/// 带内容一起去掉的括号，如“（中配）”“[1080P]”“【独家】”
+static BRACKET_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\([^()]*\)|\[[^\[\]]*\]|【[^【】]*】|〔[^〔〕]*〕").unwrap());
+
+/// 标题末尾不影响番剧身份的标注，如“年番”（全年连续播出，集数不重新计数）
+static LABEL_SUFFIX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\s:：\-—·]*年番$").unwrap());
+
+/// 标题末尾的季度、分部后缀，如“第二季”“Season 2”“Part 2”“下篇”
+///
+/// 不同季的集数各自计数，是不同的番剧，只在宽松匹配关注时去掉
+static SEASON_SUFFIX_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)[\s:：\-—·]*(第[0-9一二三四五六七八九十百零〇两]+[季期部章篇]|[0-9]+(st|nd|rd|th)\s*season|season\s*[0-9]+|\bpart\s*[0-9ivx]+|\bs[0-9]+|最终季|最终章|[上下]篇|[上下]半)$",
+    )
+    .unwrap()
+});
+
+/// 归一化规则的版本，规则改动后需递增，启动时据此判断是否要重建番剧匹配键
+pub const NORMALIZER_VERSION: u32 = 2;
+
+/// 用户自定义的别名规则：归一化后的别名 -> (归一化后的标题, 宽松匹配键)
+static ALIAS_RULES: Lazy<RwLock<HashMap<String, (String, String)>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 标题别名规则：`aliases` 中的标题都会被视为 `title` 对应的番剧
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct TitleAliasRule {
+    pub title: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// 替换当前生效的别名规则
+pub fn set_alias_rules(rules: &[TitleAliasRule]) {
+    let map: HashMap<String, (String, String)> = rules
+        .iter()
+        .flat_map(|rule| {
+            let target = (
+                fold_title(&rule.title, false),
+                fold_title(&rule.title, true),
+            );
+            rule.aliases
+                .iter()
+                .map(move |alias| (fold_title(alias, false), target.clone()))
+        })
+        .filter(|(alias, (target, _))| !alias.is_empty() && alias != target)
+        .collect();
+    *ALIAS_RULES.write().unwrap() = map;
+}
+
+/// 当前标题规则的指纹：归一化规则版本 + 别名规则的哈希，两者都没变时无需重建匹配键
+pub fn title_rules_fingerprint() -> String {
+    let rules = ALIAS_RULES.read().unwrap();
+    let mut pairs: Vec<_> = rules.iter().collect();
+    pairs.sort();
+    // FNV-1a：结果不随程序版本变化，可以保存到数据库
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (alias, (target, _)) in pairs {
+        for byte in alias.bytes().chain([0]).chain(target.bytes()).chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{NORMALIZER_VERSION}-{hash:016x}")
+}
+
+/// 归一化标题，作为跨平台匹配番剧的键
+///
+/// 依次做全角转半角、繁转简、去括号注释、去“年番”等标注、去空白和标点、转小写，最后应用别名规则；
+/// 季度、分部后缀会保留，不同季是不同的番剧
+pub fn normalize_title(title: &str) -> String {
+    let key = fold_title(title, false);
+    ALIAS_RULES
+        .read()
+        .unwrap()
+        .get(&key)
+        .map(|(target, _)| target.clone())
+        .unwrap_or(key)
+}
+
+/// 宽松匹配键：在 [`normalize_title`] 的基础上再去掉季度、分部后缀，
+/// 用于关注某一季后匹配同一部番剧的其它季，不能作为番剧的身份
+pub fn base_title(title: &str) -> String {
+    let rules = ALIAS_RULES.read().unwrap();
+    if let Some((_, base)) = rules.get(&fold_title(title, false)) {
+        return base.clone();
+    }
+    let key = fold_title(title, true);
+    rules.get(&key).map(|(_, base)| base.clone()).unwrap_or(key)
+}
+
+/// 不含别名规则的归一化，strip_season 为 true 时去掉季度后缀
+fn fold_title(title: &str, strip_season: bool) -> String {
+    // 1. 全角转半角、繁转简
+    let folded: String = title.chars().map(fold_width).map(to_simplified).collect();
+
+    // 2. 去掉括号注释，书名号等只去掉符号本身
+    let mut text = BRACKET_RE.replace_all(&folded, " ").into_owned();
+
+    // 3. 反复去掉末尾的标注（及季度后缀），如“第二季 下篇”
+    loop {
+        let trimmed = text.trim_end();
+        let mut stripped = LABEL_SUFFIX_RE.replace(trimmed, "").into_owned();
+        if strip_season {
+            stripped = SEASON_SUFFIX_RE.replace(&stripped, "").into_owned();
+        }
+        if stripped == trimmed || stripped.trim().is_empty() {
+            break;
+        }
+        text = stripped;
+    }
+
+    // 4. 只保留字母数字（含汉字、假名）并转小写
+    let key: String = text
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    // 标题全是符号时退回到仅去空白的写法，避免得到空键
+    if key.is_empty() {
+        folded
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect()
+    } else {
+        key
+    }
+}
+
+/// 全角字符转半角
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// 繁体字转简体
+fn to_simplified(c: char) -> char {
+    T2S_TABLE.get(&c).copied().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_title() {
+        // 空白、大小写、标点
+        assert_eq!(normalize_title(" Re:Zero "), "rezero");
+        assert_eq!(
+            normalize_title("凡人修仙传"),
+            normalize_title("凡人 修仙传")
+        );
+        // 全角转半角
+        assert_eq!(
+            normalize_title("ＳＰＹ×ＦＡＭＩＬＹ"),
+            normalize_title("SPY×FAMILY")
+        );
+        // 繁转简
+        assert_eq!(normalize_title("鬥破蒼穹"), normalize_title("斗破苍穹"));
+        assert_eq!(normalize_title("進擊的巨人"), "进击的巨人");
+        // 年番等标注去掉，季度后缀保留
+        assert_eq!(normalize_title("凡人修仙传年番"), "凡人修仙传");
+        assert_eq!(normalize_title("凡人修仙传 第二季"), "凡人修仙传第二季");
+        assert_eq!(normalize_title("Overlord Season 4"), "overlordseason4");
+        assert_ne!(
+            normalize_title("鬼灭之刃 第3季 下篇"),
+            normalize_title("鬼灭之刃 第3季 上篇")
+        );
+        // 宽松匹配键去掉季度后缀
+        assert_eq!(base_title("凡人修仙传 第二季"), "凡人修仙传");
+        assert_eq!(base_title("Overlord Season 4"), "overlord");
+        assert_eq!(base_title("鬼灭之刃 第3季 下篇"), "鬼灭之刃");
+        assert_eq!(base_title("间谍过家家 Part 2"), "间谍过家家");
+        // 括号
+        assert_eq!(normalize_title("间谍过家家（中配）"), "间谍过家家");
+        assert_eq!(normalize_title("【独家】《琉璃的宝石》"), "琉璃的宝石");
+        // 标题中间的数字不受影响
+        assert_eq!(normalize_title("斗罗大陆2绝世唐门"), "斗罗大陆2绝世唐门");
+        // 全是后缀或符号时不会得到空键
+        assert_eq!(normalize_title("年番"), "年番");
+        assert_eq!(base_title("第二季"), "第二季");
+        assert_eq!(normalize_title("♪♪"), "♪♪");
+    }
+
+    #[test]
+    fn test_alias_rules() {
+        let empty = title_rules_fingerprint();
+        set_alias_rules(&[TitleAliasRule {
+            title: "凡人修仙传".to_string(),
+            aliases: vec!["A Record of Mortal's Journey to Immortality".to_string()],
+        }]);
+        assert_eq!(
+            normalize_title("A Record of Mortal's Journey to Immortality"),
+            "凡人修仙传"
+        );
+        assert_eq!(
+            base_title("A Record of Mortal's Journey to Immortality Season 2"),
+            "凡人修仙传"
+        );
+        // 别名规则变化后指纹随之变化
+        assert_ne!(title_rules_fingerprint(), empty);
+        set_alias_rules(&[]);
+        assert_eq!(title_rules_fingerprint(), empty);
+        assert_eq!(
+            normalize_title("A Record of Mortal's Journey to Immortality"),
+            "arecordofmortalsjourneytoimmortality"
+        );
+    }
+}
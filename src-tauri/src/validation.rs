@@ -0,0 +1,362 @@
+//! 配置检查：在创建定时任务、启动推送之前发现配置错误，返回结构化的检查结果
+
+use crate::command::platforms::{cmd_names, registry};
+use crate::configuration::{
+    load_configuration, load_configuration_with, AppConfig, DataSource, PushChannelConfig,
+};
+use crate::notify::QuietHours;
+use cron::Schedule;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use url::Url;
+
+/// 检查结果的级别：error 会导致对应的数据源不被调度（热加载时整体拒绝），warning 只提示
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+}
+
+/// 一条配置检查结果
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDiagnostic {
+    pub level: DiagnosticLevel,
+    /// 出错的配置项，如 datasource.anime[0].cron_expr，整个文件出错时为空
+    pub path: String,
+    /// 出错的数据源名称
+    pub source: Option<String>,
+    pub message: String,
+}
+
+impl ConfigDiagnostic {
+    pub fn error(
+        path: impl Into<String>,
+        source: Option<&str>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            level: DiagnosticLevel::Error,
+            path: path.into(),
+            source: source.map(str::to_string),
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(
+        path: impl Into<String>,
+        source: Option<&str>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            level: DiagnosticLevel::Warning,
+            ..Self::error(path, source, message)
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == DiagnosticLevel::Error
+    }
+}
+
+impl std::fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}：{}", self.path, self.message)
+        }
+    }
+}
+
+/// 读取并检查配置，content 不为空时用它代替 config.yaml；读取失败时配置为 None
+pub fn load_checked_configuration(
+    config_path: &Path,
+    content: Option<&str>,
+) -> (Option<AppConfig>, Vec<ConfigDiagnostic>) {
+    let loaded = match content {
+        Some(content) => load_configuration_with(config_path, content),
+        None => load_configuration(config_path.to_path_buf()),
+    };
+    match loaded {
+        Ok(config) => {
+            let diagnostics = validate_config(&config);
+            (Some(config), diagnostics)
+        }
+        Err(e) => (
+            None,
+            vec![ConfigDiagnostic::error(
+                "",
+                None,
+                format!("读取配置失败：{e}"),
+            )],
+        ),
+    }
+}
+
+/// 检查配置：cron 表达式、命令名、URL、数据源名称是否重复，以及代理、限流、推送渠道和免打扰时段
+pub fn validate_config(config: &AppConfig) -> Vec<ConfigDiagnostic> {
+    let cmds: HashSet<String> = cmd_names().into_iter().collect();
+    let mut diagnostics = Vec::new();
+    let mut names = HashSet::new();
+
+    // 按分类名排序，与创建任务的顺序一致
+    let mut categories: Vec<(&String, &Vec<DataSource>)> = config.datasource.iter().collect();
+    categories.sort_by_key(|(category, _)| *category);
+    for (category, sources) in categories {
+        for (i, source) in sources.iter().enumerate() {
+            // 停用的数据源不会创建任务，不检查
+            if !source.enabled {
+                continue;
+            }
+            let path = format!("datasource.{category}[{i}]");
+            if !source.name.trim().is_empty() && !names.insert(source.name.as_str()) {
+                diagnostics.push(ConfigDiagnostic::error(
+                    format!("{path}.name"),
+                    Some(source.name.as_str()),
+                    format!("数据源名称重复：{}", source.name),
+                ));
+            }
+            diagnostics.extend(validate_data_source(&path, source, &cmds));
+        }
+    }
+
+    let proxy = config.http_client.proxy.trim();
+    if !proxy.is_empty() {
+        let scheme = Url::parse(proxy).map(|url| url.scheme().to_string());
+        if !matches!(
+            scheme.as_deref(),
+            Ok("http" | "https" | "socks5" | "socks5h")
+        ) {
+            diagnostics.push(ConfigDiagnostic::error(
+                "http_client.proxy",
+                None,
+                "代理地址只支持 http://、https://、socks5://、socks5h://",
+            ));
+        }
+    }
+
+    // 默认的限流配置和按平台覆盖的限流配置
+    let http_client = &config.http_client;
+    let platform_ids: HashSet<&str> = registry().iter().map(|p| p.id()).collect();
+    let mut platforms: Vec<&String> = http_client.rate_limits.keys().collect();
+    platforms.sort();
+    let limits = std::iter::once((None, &http_client.rate_limit)).chain(
+        platforms
+            .into_iter()
+            .map(|platform| (Some(platform), &http_client.rate_limits[platform])),
+    );
+    for (platform, limit) in limits {
+        let path = match platform {
+            Some(platform) => format!("http_client.rate_limits.{platform}"),
+            None => "http_client.rate_limit".to_string(),
+        };
+        if let Some(platform) = platform.filter(|p| !platform_ids.contains(p.as_str())) {
+            diagnostics.push(ConfigDiagnostic::warning(
+                path.clone(),
+                None,
+                format!("未知的平台：{platform}"),
+            ));
+        }
+        if limit.max_concurrent == 0 {
+            diagnostics.push(ConfigDiagnostic::warning(
+                format!("{path}.max_concurrent"),
+                None,
+                "并发上限至少为 1，将按 1 处理",
+            ));
+        }
+    }
+
+    let notification = &config.notification;
+    if let Err(e) = QuietHours::parse(&notification.quiet_hours) {
+        diagnostics.push(ConfigDiagnostic::warning(
+            "notification.quiet_hours",
+            None,
+            format!("{e}，将不启用免打扰"),
+        ));
+    }
+    for (i, channel) in notification.push.iter().enumerate() {
+        let (field, url) = match channel {
+            PushChannelConfig::Webhook(c) => ("url", &c.url),
+            PushChannelConfig::Telegram(c) => ("api_base", &c.api_base),
+            PushChannelConfig::Smtp(_) => continue,
+        };
+        if let Err(e) = check_http_url(url) {
+            diagnostics.push(ConfigDiagnostic::error(
+                format!("notification.push[{i}].{field}"),
+                None,
+                e,
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// 检查单个数据源：名称、cron 表达式、命令名、URL 和重试策略，path 为该数据源的配置项路径
+pub fn validate_data_source(
+    path: &str,
+    source: &DataSource,
+    cmds: &HashSet<String>,
+) -> Vec<ConfigDiagnostic> {
+    let field = |name: &str| {
+        if path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{path}.{name}")
+        }
+    };
+    let name = Some(source.name.as_str());
+    let mut diagnostics = Vec::new();
+    if source.name.trim().is_empty() {
+        diagnostics.push(ConfigDiagnostic::error(
+            field("name"),
+            None,
+            "数据源名称不能为空",
+        ));
+    }
+    if let Err(e) = Schedule::from_str(&source.cron_expr) {
+        diagnostics.push(ConfigDiagnostic::error(
+            field("cron_expr"),
+            name,
+            format!("cron 表达式无效：{e}"),
+        ));
+    }
+    if !cmds.contains(&source.cmd) {
+        diagnostics.push(ConfigDiagnostic::error(
+            field("cmd"),
+            name,
+            format!("未知的命令：{}", source.cmd),
+        ));
+    }
+    if let Err(e) = check_http_url(&source.url) {
+        diagnostics.push(ConfigDiagnostic::error(field("url"), name, e));
+    }
+    let policy = &source.retry_policy;
+    if !(0.0..=1.0).contains(&policy.jitter) {
+        diagnostics.push(ConfigDiagnostic::warning(
+            field("retry_policy.jitter"),
+            name,
+            "抖动比例应在 0~1 之间，超出部分将被忽略",
+        ));
+    }
+    if policy.multiplier < 1.0 {
+        diagnostics.push(ConfigDiagnostic::warning(
+            field("retry_policy.multiplier"),
+            name,
+            "倍数小于 1 时重试等待时间会越来越短",
+        ));
+    }
+    diagnostics
+}
+
+/// 有错误的数据源名称
+pub fn invalid_sources(diagnostics: &[ConfigDiagnostic]) -> HashSet<String> {
+    diagnostics
+        .iter()
+        .filter(|d| d.is_error())
+        .filter_map(|d| d.source.clone())
+        .collect()
+}
+
+/// URL 必须是 http 或 https 地址
+fn check_http_url(url: &str) -> Result<(), String> {
+    let parsed = Url::parse(url).map_err(|e| format!("URL 无效：{e}"))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(format!("URL 只支持 http 和 https：{scheme}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::USER_CONFIG_FILE;
+    use std::fs;
+
+    const CONFIG: &str = r#"
+datasource:
+  anime:
+    - name: "哔哩哔哩"
+      url: "https://api.bilibili.com/pgc/web/timeline"
+      cmd: "fetch_bilibili_ani_data"
+      cron_expr: "0 0 * * * * *"
+      retry_times: 0
+    - name: "哔哩哔哩"
+      url: "ftp://example.com"
+      cmd: "fetch_unknown_ani_data"
+      cron_expr: "every hour"
+      retry_times: 0
+    - name: "停用"
+      url: "not a url"
+      cmd: "unknown"
+      cron_expr: "bad"
+      retry_times: 0
+      enabled: false
+  drama:
+    - name: "腾讯视频"
+      url: "https://v.qq.com/channel/drama"
+      cmd: "tencent_parser"
+      cron_expr: "0 0 * * * * *"
+      retry_times: 0
+      retry_policy:
+        jitter: 1.5
+http_client:
+  proxy: "ftp://127.0.0.1:21"
+  rate_limits:
+    bilibili:
+      max_concurrent: 0
+    unknown:
+      requests_per_second: 1
+notification:
+  quiet_hours: "23:00"
+  push:
+    - type: webhook
+      url: "example.com/hook"
+"#;
+
+    #[test]
+    fn test_validate_config() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(USER_CONFIG_FILE), CONFIG).unwrap();
+        let (config, diagnostics) = load_checked_configuration(dir.path(), None);
+        assert!(config.is_some());
+
+        let paths: Vec<(DiagnosticLevel, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.level, d.path.as_str()))
+            .collect();
+        use DiagnosticLevel::*;
+        assert_eq!(
+            paths,
+            vec![
+                (Error, "datasource.anime[1].name"),
+                (Error, "datasource.anime[1].cron_expr"),
+                (Error, "datasource.anime[1].cmd"),
+                (Error, "datasource.anime[1].url"),
+                (Warning, "datasource.drama[0].retry_policy.jitter"),
+                (Error, "http_client.proxy"),
+                (Warning, "http_client.rate_limits.bilibili.max_concurrent"),
+                (Warning, "http_client.rate_limits.unknown"),
+                (Warning, "notification.quiet_hours"),
+                (Error, "notification.push[0].url"),
+            ]
+        );
+        assert_eq!(
+            invalid_sources(&diagnostics),
+            HashSet::from(["哔哩哔哩".to_string()])
+        );
+
+        // 保存前检查给定的内容，读取失败也作为检查结果返回
+        let (_, diagnostics) =
+            load_checked_configuration(dir.path(), Some("datasource:\n  anime: []\n"));
+        assert!(diagnostics.is_empty());
+        let (config, diagnostics) = load_checked_configuration(dir.path(), Some("datasource: ["));
+        assert!(config.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_error());
+        assert!(diagnostics[0].to_string().contains("读取配置失败"));
+    }
+}
@@ -1,48 +1,76 @@
-use app_lib::command::platforms::agedm::fetch_agedm_ani_data;
-use app_lib::command::platforms::bilibili::fetch_bilibili_ani_data;
-use app_lib::command::platforms::iqiyi::fetch_iqiyi_ani_data;
-use app_lib::command::platforms::mikanani::fetch_mikanani_ani_data;
-use app_lib::command::platforms::tencent::fetch_qq_ani_data;
-use app_lib::command::platforms::youku::fetch_youku_ani_data;
+//! 访问线上站点的抓取测试，默认忽略，可通过 `cargo test -- --ignored` 手动运行
+use app_lib::command::platforms::agedm::Agedm;
+use app_lib::command::platforms::bilibili::Bilibili;
+use app_lib::command::platforms::iqiyi::Iqiyi;
+use app_lib::command::platforms::mikanani::Mikanani;
+use app_lib::command::platforms::tencent::Tencent;
+use app_lib::command::platforms::youku::Youku;
+use app_lib::command::platforms::Platform;
+use app_lib::utils::date_utils::SystemClock;
+use app_lib::utils::http_client::HttpClient;
 
 #[tokio::test]
+#[ignore = "需要访问线上站点"]
 async fn test_fetch_bilibili_ani_data() {
-    let url = "https://api.bilibili.com/pgc/web/timeline?types=4&before=6&after=6".to_string();
-    let res = fetch_bilibili_ani_data(url).await.unwrap();
+    let url = "https://api.bilibili.com/pgc/web/timeline?types=4&before=6&after=6";
+    let res = Bilibili
+        .fetch_schedule(&HttpClient::default(), url, &SystemClock)
+        .await
+        .unwrap();
     println!("{:?}", res);
 }
 
 #[tokio::test]
+#[ignore = "需要访问线上站点"]
 async fn test_iqiyi_ani_data() {
-    let url = "https://mesh.if.iqiyi.com/portal/lw/v7/channel/cartoon".to_string();
-    let res = fetch_iqiyi_ani_data(url).await.unwrap();
+    let url = "https://mesh.if.iqiyi.com/portal/lw/v7/channel/cartoon";
+    let res = Iqiyi
+        .fetch_schedule(&HttpClient::default(), url, &SystemClock)
+        .await
+        .unwrap();
     println!("{:?}", res);
 }
 
 #[tokio::test]
+#[ignore = "需要访问线上站点"]
 async fn test_mikanani_ani_data() {
-    let url = "https://mikanani.me".to_string();
-    let res = fetch_mikanani_ani_data(url).await.unwrap();
+    let url = "https://mikanani.me";
+    let res = Mikanani
+        .fetch_schedule(&HttpClient::default(), url, &SystemClock)
+        .await
+        .unwrap();
     println!("{:?}", res);
 }
 
 #[tokio::test]
+#[ignore = "需要访问线上站点"]
 async fn test_qq_cartoon_data() {
-    let url = "https://v.qq.com/channel/cartoon".to_string();
-    let res = fetch_qq_ani_data(url).await.unwrap();
+    let url = "https://v.qq.com/channel/cartoon";
+    let res = Tencent
+        .fetch_schedule(&HttpClient::default(), url, &SystemClock)
+        .await
+        .unwrap();
     println!("{:?}", res);
 }
 
 #[tokio::test]
+#[ignore = "需要访问线上站点"]
 async fn test_youku_cartoon_data() {
-    let url = "https://www.youku.com/ku/webcomic".to_string();
-    let res = fetch_youku_ani_data(url).await.unwrap();
+    let url = "https://www.youku.com/ku/webcomic";
+    let res = Youku
+        .fetch_schedule(&HttpClient::default(), url, &SystemClock)
+        .await
+        .unwrap();
     println!("{:?}", res);
 }
 
 #[tokio::test]
+#[ignore = "需要访问线上站点"]
 async fn test_agedm_data() {
-    let url = "https://www.agedm.tv/update".to_string();
-    let res = fetch_agedm_ani_data(url).await.unwrap();
+    let url = "https://www.agedm.tv/update";
+    let res = Agedm
+        .fetch_schedule(&HttpClient::default(), url, &SystemClock)
+        .await
+        .unwrap();
     println!("{:?}", res);
 }
@@ -0,0 +1,286 @@
+//! 基于离线样本（tests/fixtures）的各平台解析测试，无需联网
+use app_lib::command::platforms::agedm::parse_agedm_html;
+use app_lib::command::platforms::bilibili;
+use app_lib::command::platforms::iqiyi;
+use app_lib::command::platforms::mikanani::parse_mikanani_html;
+use app_lib::command::platforms::tencent::{
+    extract_vikor_json, find_daily_card, parse_tencent_html,
+};
+use app_lib::command::platforms::youku::{
+    extract_initial_data, parse_youku_html, process_module_list,
+};
+use chrono::NaiveDate;
+use serde_json::Value;
+use url::Url;
+
+/// 样本抓取当天：2025/07/13（星期日）
+fn fixture_day() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 7, 13).unwrap()
+}
+
+#[test]
+fn test_parse_bilibili_timeline() {
+    let json: Value =
+        serde_json::from_str(include_str!("fixtures/bilibili_timeline.json")).unwrap();
+    let result = bilibili::process_json_value(&json, fixture_day());
+
+    let items = result.get("2025/07/13").expect("缺少今日数据");
+    // 今天未发布（published = 0）的剧集不应出现
+    assert_eq!(items.len(), 2);
+
+    let first = &items[0];
+    assert_eq!(first.title, "凡人修仙传");
+    assert_eq!(first.update_count, "152");
+    assert_eq!(first.update_info, "更新至第152话");
+    assert_eq!(
+        first.image_url,
+        "http://i0.hdslb.com/bfs/bangumi/image/f0e1d2c3b4a5.png"
+    );
+    assert_eq!(
+        first.detail_url,
+        "https://www.bilibili.com/bangumi/play/ep1240331"
+    );
+    assert_eq!(first.update_time, "2025/07/13");
+    assert_eq!(first.platform, "bilibili");
+    assert_eq!(first.external_id, "28770");
+
+    // 没有 square_cover 时回退到 cover
+    assert_eq!(
+        items[1].image_url,
+        "http://i0.hdslb.com/bfs/bangumi/image/1a2b3c4d5e6f.png"
+    );
+
+    // 其余日期按与今天的偏移还原真实日期，明天的剧集即使未发布也保留
+    assert_eq!(result.len(), 3);
+    let yesterday = &result["2025/07/12"];
+    assert!(yesterday.iter().all(|i| i.update_time == "2025/07/12"));
+    let tomorrow = &result["2025/07/14"];
+    assert!(!tomorrow.is_empty());
+    assert!(tomorrow.iter().all(|i| i.update_time == "2025/07/14"));
+}
+
+#[test]
+fn test_parse_bilibili_error_code() {
+    let json: Value = serde_json::json!({ "code": -400, "message": "请求错误" });
+    let result = bilibili::process_json_value(&json, fixture_day());
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_parse_iqiyi_cartoon() {
+    let json: Value = serde_json::from_str(include_str!("fixtures/iqiyi_cartoon.json")).unwrap();
+    let result = iqiyi::process_json_value(&json, fixture_day());
+
+    // 追番表七天都会返回
+    assert_eq!(result.len(), 7);
+    let items = result.get("2025/07/13").expect("缺少今日数据");
+    // “即将上线”没有集数，会被过滤
+    assert_eq!(items.len(), 2);
+
+    let first = &items[0];
+    assert_eq!(first.title, "灵剑山 第三季");
+    assert_eq!(first.update_count, "8");
+    assert_eq!(first.update_info, "更新至8集");
+    assert_eq!(
+        first.image_url,
+        "http://pic0.iqiyipic.com/image/20250713/1777/ca/a_100551777_579_772.jpg"
+    );
+    assert_eq!(first.detail_url, "http://www.iqiyi.com/a_100551777.html");
+    assert_eq!(first.update_time, "2025/07/13");
+    assert_eq!(first.platform, "iqiyi");
+    assert_eq!(first.external_id, "10055177709");
+
+    // 星期一对应追番表的第一个标签页，日期为本周一
+    let items = result.get("2025/07/07").unwrap();
+    assert_eq!(items[0].title, "斗罗大陆2绝世唐门");
+    assert_eq!(items[0].update_time, "2025/07/07");
+
+    // 以星期一为今天时，星期二的标签页对应上周二
+    let monday = NaiveDate::from_ymd_opt(2025, 7, 14).unwrap();
+    let result = iqiyi::process_json_value(&json, monday);
+    assert_eq!(result["2025/07/14"][0].title, "斗罗大陆2绝世唐门");
+    assert_eq!(result["2025/07/08"][0].title, "师兄啊师兄");
+}
+
+#[test]
+fn test_parse_mikanani_home() {
+    let base_url = Url::parse("https://mikanani.me").unwrap();
+    let result = parse_mikanani_html(
+        include_str!("fixtures/mikanani_home.html"),
+        &base_url,
+        fixture_day(),
+    );
+
+    let items = result.get("2025/07/13").expect("缺少今日数据");
+    // 缺少 num-node 的条目会被过滤
+    assert_eq!(items.len(), 2);
+
+    let first = &items[0];
+    assert_eq!(first.title, "琉璃的宝石");
+    assert_eq!(first.update_count, "");
+    assert_eq!(first.update_info, "2025/07/13 更新");
+    assert_eq!(
+        first.image_url,
+        "https://mikanani.me/images/Bangumi/202507/18470785.jpg?width=400&height=400&format=webp"
+    );
+    assert_eq!(first.detail_url, "https://mikanani.me/Home/Bangumi/3663");
+    assert_eq!(first.update_time, "2025/07/13");
+    assert_eq!(first.platform, "mikanani");
+    assert_eq!(first.external_id, "3663");
+    assert_eq!(items[1].title, "名侦探柯南");
+
+    // 七天内其它日期的更新按各自日期分组
+    assert_eq!(result["2025/07/10"][0].title, "魔女守护者");
+    // 超出七天的更新不返回
+    let later = NaiveDate::from_ymd_opt(2025, 7, 17).unwrap();
+    let result = parse_mikanani_html(
+        include_str!("fixtures/mikanani_home.html"),
+        &base_url,
+        later,
+    );
+    assert!(!result.contains_key("2025/07/10"));
+    assert!(result["2025/07/17"].is_empty());
+}
+
+#[test]
+fn test_parse_tencent_cartoon() {
+    let html = include_str!("fixtures/tencent_cartoon.html");
+
+    let data = extract_vikor_json(html).unwrap();
+    let pinia = data.get("_piniaState").and_then(Value::as_object).unwrap();
+    let daily = find_daily_card(pinia).expect("未找到每日更新模块");
+    assert_eq!(daily["selectedTabId"], "tab_7");
+
+    let result = parse_tencent_html(html, fixture_day()).unwrap();
+    let items = result.get("2025/07/13").expect("缺少今日数据");
+    // 没有 uniImgTag 的预告条目会被过滤
+    assert_eq!(items.len(), 2);
+
+    let first = &items[0];
+    assert_eq!(first.title, "斗罗大陆2绝世唐门");
+    assert_eq!(first.update_count, "108");
+    assert_eq!(first.update_info, "更新至108集 周日10点更新");
+    assert_eq!(
+        first.detail_url,
+        "https://v.qq.com/x/cover/mzc00200xf3n4dn.html"
+    );
+    assert_eq!(first.update_time, "2025/07/13");
+    assert_eq!(first.platform, "tencent");
+    assert_eq!(first.external_id, "mzc00200xf3n4dn");
+    assert_eq!(items[1].update_info, "更新至220集 ");
+
+    // 页面中已加载的“周六”标签页对应昨天
+    let saturday = result.get("2025/07/12").expect("缺少周六数据");
+    assert!(!saturday.is_empty());
+    assert!(saturday.iter().all(|i| i.update_time == "2025/07/12"));
+}
+
+#[test]
+fn test_parse_tencent_drama() {
+    let html = include_str!("fixtures/tencent_drama.html");
+
+    // 电视剧频道的模块标题为“追剧日历”
+    let data = extract_vikor_json(html).unwrap();
+    let pinia = data.get("_piniaState").and_then(Value::as_object).unwrap();
+    let daily = find_daily_card(pinia).expect("未找到追剧日历模块");
+    assert_eq!(daily["moduleId"], "m_5");
+
+    let result = parse_tencent_html(html, fixture_day()).unwrap();
+    let items = result.get("2025/07/13").expect("缺少今日数据");
+    // 副标题和角标中都没有集数的花絮条目会被过滤
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].title, "庆余年第二季");
+    assert_eq!(items[0].update_count, "24");
+    assert_eq!(items[0].update_info, "更新至24集 周日20点更新");
+    assert_eq!(items[1].update_count, "39");
+    assert_eq!(result["2025/07/12"][0].update_count, "30");
+    assert!(items.iter().all(|i| i.platform == "tencent"));
+}
+
+#[test]
+fn test_parse_tencent_without_context() {
+    let html = "<html><body><script>var a = 1;</script></body></html>";
+    assert!(extract_vikor_json(html).is_err());
+    assert!(parse_tencent_html(html, fixture_day()).is_err());
+}
+
+#[test]
+fn test_parse_youku_webcomic() {
+    let html = include_str!("fixtures/youku_webcomic.html");
+
+    let data = extract_initial_data(html).unwrap();
+    let modules = data["moduleList"].as_array().unwrap();
+    let week = process_module_list(modules, fixture_day()).unwrap();
+    // 七天都会返回，同一天的重复标题只保留一条，其它模块不参与解析
+    assert_eq!(week.len(), 7);
+    assert_eq!(week["2025/07/13"].len(), 3);
+    assert_eq!(week["2025/07/07"][0].title, "周一番剧");
+    assert_eq!(week["2025/07/07"][0].update_time, "2025/07/07");
+
+    let result = parse_youku_html(html, fixture_day()).unwrap();
+    let items = result.get("2025/07/13").expect("缺少今日数据");
+    assert_eq!(items.len(), 3);
+
+    let first = &items[0];
+    assert_eq!(first.title, "沧元图");
+    assert_eq!(first.update_count, "52");
+    assert_eq!(first.update_info, "更新至52集");
+    assert_eq!(
+        first.image_url,
+        "https://liangcang-material.alicdn.com/prod/upload/cangyuantu.jpg"
+    );
+    assert_eq!(
+        first.detail_url,
+        "https://v.youku.com/video?s=cc003de1c52f4c9e9aa7&scm=20140719.manual.4441.show_cc003de1c52f4c9e9aa7&scg_id=22828527"
+    );
+    assert_eq!(first.update_time, "2025/07/13");
+    assert_eq!(first.platform, "youku");
+    assert_eq!(first.external_id, "cc003de1c52f4c9e9aa7");
+
+    // lbTexts 为数组时拼接，缺少 updateCount 时从文本中提取
+    assert_eq!(items[1].update_info, "更新至 26集");
+    assert_eq!(items[1].update_count, "26");
+    // updateCount 为数字，且缺少 action_value 时使用默认链接
+    assert_eq!(items[2].update_count, "8");
+    assert_eq!(items[2].detail_url, "https://www.youku.com/ku/webcomic");
+}
+
+#[test]
+fn test_parse_youku_without_initial_data() {
+    let html = "<html><body><script>var a = 1;</script></body></html>";
+    assert!(parse_youku_html(html, fixture_day()).is_err());
+}
+
+#[test]
+fn test_parse_agedm_update() {
+    let result = parse_agedm_html(include_str!("fixtures/agedm_update.html"), fixture_day());
+
+    let items = result.get("2025/07/13").expect("缺少今日数据");
+    // “今天”区块
+    assert_eq!(items.len(), 3);
+
+    let first = &items[0];
+    assert_eq!(first.title, "琉璃的宝石");
+    assert_eq!(first.update_count, "2");
+    assert_eq!(first.update_info, "第2集");
+    assert_eq!(
+        first.image_url,
+        "https://cdn.aqdstatic.com:966/age/20250096.jpg"
+    );
+    assert_eq!(first.detail_url, "https://www.agedm.tv/play/20250096/1/2");
+    assert_eq!(first.update_time, "2025/07/13");
+    assert_eq!(first.platform, "agedm");
+    assert_eq!(first.external_id, "20250096");
+    assert_eq!(items[2].update_count, "1167");
+
+    // “昨天”区块按昨天的日期返回
+    let yesterday = result.get("2025/07/12").expect("缺少昨天数据");
+    assert!(!yesterday.is_empty());
+    assert!(yesterday.iter().all(|i| i.update_time == "2025/07/12"));
+}
+
+#[test]
+fn test_parse_agedm_without_today() {
+    let html = "<html><body><div class=\"video_list_box recent_update\"></div></body></html>";
+    assert!(parse_agedm_html(html, fixture_day()).is_empty());
+}
@@ -1,11 +1,20 @@
 import {useFavoriteAni} from "@/hooks/useFavoriteAni.ts";
 
+// 数据源分类的显示名称
+const CATEGORY_NAMES: Record<string, string> = {
+    anime: '动漫',
+    drama: '电视剧',
+};
+
 interface Props {
     showFavorite: boolean;
     onFilterChange: (filter: 'all' | 'favorites') => void;
+    categories: string[];
+    category: string;               // 'all' 表示全部分类
+    onCategoryChange: (category: string) => void;
 }
 
-export default function AniFilter({ showFavorite, onFilterChange }: Props) {
+export default function AniFilter({ showFavorite, onFilterChange, categories, category, onCategoryChange }: Props) {
     // —— 收藏番剧相关操作 ——
     const { favoriteAniItems } = useFavoriteAni();
 
@@ -20,6 +29,27 @@ export default function AniFilter({ showFavorite, onFilterChange }: Props) {
                     marginTop: 8,
                     flexShrink: 0
                 }}>
+                    {/* 只有一个分类时不显示分类筛选 */}
+                    {categories.length > 1 && (
+                        <select
+                            value={category}
+                            onChange={(e) => onCategoryChange(e.target.value)}
+                            style={{
+                                padding: '6px 8px',
+                                borderRadius: 6,
+                                border: '1px solid #ddd',
+                                background: '#fff',
+                                color: '#666',
+                                fontSize: '0.9rem',
+                                cursor: 'pointer',
+                            }}
+                        >
+                            <option value="all">全部分类</option>
+                            {categories.map(c => (
+                                <option key={c} value={c}>{CATEGORY_NAMES[c] ?? c}</option>
+                            ))}
+                        </select>
+                    )}
                     <button
                         onClick={() => onFilterChange('all')}
                         style={{
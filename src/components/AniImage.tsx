@@ -8,10 +8,10 @@ interface Props {
     style?: React.CSSProperties;
 }
 
-// 判断所属平台（返回后端 command 名）
-const getImageCommand = (url: string): string | null => {
-    if (url.includes('hdslb.com')) return 'fetch_bilibili_image';
-    if (url.includes('iqiyipic.com')) return 'fetch_iqiyi_image';
+// 判断所属平台（返回后端平台标识）
+const getImagePlatform = (url: string): string | null => {
+    if (url.includes('hdslb.com')) return 'bilibili';
+    if (url.includes('iqiyipic.com')) return 'iqiyi';
     return null;
 };
 
@@ -19,21 +19,21 @@ const AniImage: React.FC<Props> = ({ url, alt = '', className }) => {
     const [src, setSrc] = useState<string>(url);
 
     useEffect(() => {
-        const command = getImageCommand(url);
+        const platform = getImagePlatform(url);
         let cancelled = false;
 
         // 如果不需要转 base64，直接使用原图
-        if (!command) {
+        if (!platform) {
             setSrc(url);
             return;
         }
 
         const fetchImage = async () => {
             try {
-                const dataUrl = await invoke<string>(command, { url });
+                const dataUrl = await invoke<string>('fetch_ani_image', { platform, url });
                 if (!cancelled) setSrc(dataUrl);
             } catch (e) {
-                console.error(`fetch_image failed (${command})`, e);
+                console.error(`fetch_ani_image failed (${platform})`, e);
                 if (!cancelled) setSrc(url); // 回退
             }
         };
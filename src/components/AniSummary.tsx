@@ -6,9 +6,12 @@ interface Props {
     total: number;
     showFavorite: boolean;
     onFilterChange: (filter: 'all' | 'favorites') => void;
+    categories: string[];
+    category: string;
+    onCategoryChange: (category: string) => void;
 }
 
-export default function AniSummary({ weekday, total, showFavorite, onFilterChange }: Props) {
+export default function AniSummary({ weekday, total, showFavorite, onFilterChange, categories, category, onCategoryChange }: Props) {
 
     return (
         <div className="ani-summary" style={{
@@ -29,6 +32,9 @@ export default function AniSummary({ weekday, total, showFavorite, onFilterChang
             <AniFilter
                 showFavorite={showFavorite}
                 onFilterChange={onFilterChange}
+                categories={categories}
+                category={category}
+                onCategoryChange={onCategoryChange}
             />
         </div>
     );
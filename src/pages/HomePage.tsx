@@ -16,6 +16,7 @@ export default function HomePage({ searchQuery }: HomePageProps) {
     const { favoriteAniItems, isLoaded } = useFavoriteAni();
 
     const [showFavorite, setShowFavorite] = useState(false);
+    const [category, setCategory] = useState('all');
     const [initialized, setInitialized] = useState(false);
 
     useEffect(() => {
@@ -68,7 +69,9 @@ export default function HomePage({ searchQuery }: HomePageProps) {
 
     const today = Object.keys(data)[0];
     const aniList = data[today] as Ani[];
-    const filteredAniList = fuzzySearch(aniList, searchQuery, ['title', 'platform'])
+    const categories = [...new Set(aniList.map(ani => ani.category))].sort();
+    const categoryList = category === 'all' ? aniList : aniList.filter(ani => ani.category === category);
+    const filteredAniList = fuzzySearch(categoryList, searchQuery, ['title', 'platform'])
     const favoriteList = filteredAniList.filter(ani => favoriteAniItems.has(ani.id)); // 过滤出收藏的动画并匹配搜索查询
 
     return (
@@ -84,6 +87,9 @@ export default function HomePage({ searchQuery }: HomePageProps) {
                 total={aniList.length}
                 onFilterChange={handleFilterChange}
                 showFavorite={showFavorite}
+                categories={categories}
+                category={category}
+                onCategoryChange={setCategory}
             />
             {/*<RefreshButton loading={loading} onClick={refresh} />*/}
             <div className="App" style={{
@@ -1,4 +1,5 @@
 import {invoke as _invoke} from "@tauri-apps/api/core";
+import {listen, UnlistenFn} from "@tauri-apps/api/event";
 
 
 /** 后端返回的统一响应格式 */
@@ -19,6 +20,8 @@ export interface Ani {
     update_time: number;
     update_time_str: string;
     platform: string;
+    series_id: number | null;   // 所属番剧，跨平台关联同一部番剧
+    category: string;           // 数据源分类：anime / drama 等
 }
 
 // 动漫历史信息
@@ -44,28 +47,130 @@ export interface AniWatchHistory {
     watched_time: string,
 }
 
-// 定义所有 抓取数据的命令的类型
-export type FetchCmd =
-    | 'fetch_bilibili_ani_data'
-    | 'fetch_iqiyi_ani_data'
-    | 'fetch_qq_ani_data'
-    | 'fetch_youku_ani_data'
-    | 'fetch_agedm_ani_data'
+// 关注番剧的分集观看进度
+export interface SeriesProgress {
+    series_id: number;
+    title: string;
+    image_url: string;
+    last_watched_episode: number;   // 看过的最大集数，未看过为 0
+    watched_count: number;
+    latest_episode: number;         // 已播出的最新集数
+    behind: number;                 // 落后的集数
+}
 
+// 任务执行进度事件（task-progress）
+export interface TaskEvent {
+    taskName: string;
+    status: 'started' | 'success' | 'failed';
+    manual: boolean;                // 是否为手动触发
+    count: number | null;           // 成功时为抓取到的更新数
+    message: string | null;         // 失败原因
+    attempts: number;               // 已尝试次数（含重试）
+    warnings: string[];             // 解析告警
+    startedTime: number;
+    time: number;
+}
 
-/** 抓取命令的签名映射 */
-type FetchCommandsMap = {
-    [K in FetchCmd]: {
-        args: { url: string }
-        result: Record<string, Ani[]>
-    }
+// 任务执行记录
+export interface TaskRun {
+    id: number;
+    taskName: string;
+    manual: boolean;
+    status: 'success' | 'failed';
+    attempts: number;
+    itemCount: number | null;
+    error: string | null;
+    warnings: string | null;        // 每行一条
+    startedTime: number;
+    finishedTime: number;
+}
+
+// 数据源健康状况
+export interface TaskHealth {
+    taskName: string;
+    status: 'ok' | 'degraded' | 'failing' | 'unknown';
+    lastRunTime: number | null;
+    lastSuccessTime: number | null;
+    consecutiveFailures: number;    // 最近连续失败次数
+    avgItems: number | null;        // 最近成功执行的平均更新数
+    lastItemCount: number | null;
+    lastError: string | null;
+    lastWarnings: string[];
+}
+
+// 配置检查结果
+export interface ConfigDiagnostic {
+    level: 'error' | 'warning';     // error 的数据源不会被调度
+    path: string;                   // 出错的配置项，如 datasource.anime[0].cron_expr
+    source: string | null;          // 出错的数据源名称
+    message: string;
+}
+
+// 配置加载状态
+export interface ConfigStatus {
+    configDir: string;
+    diagnostics: ConfigDiagnostic[];    // 最近一次加载配置的检查结果，为空表示配置正常
+}
+
+// 数据源（保存在数据库中，启动时由 config.yaml 初始化）
+export interface DataSourceRecord {
+    id: number;
+    name: string;                   // 同时也是定时任务名
+    category: string;
+    url: string;
+    cmd: string;
+    cronExpr: string;
+    retryTimes: number;
+    enabled: boolean;
+    managed: boolean;               // 是否跟随配置文件更新，在应用中修改过后为 false
+    createdTime: number;
+    updatedTime: number;
+}
+
+// 新增、修改数据源时提交的内容，字段与 config.yaml 中的数据源一致
+export interface DataSourceForm {
+    name: string;
+    url: string;
+    cmd: string;
+    cron_expr: string;
+    retry_times: number;
+    enabled?: boolean;
+}
+
+// 定时任务的调度信息
+export interface TaskInfo {
+    name: string;
+    cronExpr: string;
+    nextRun: number | null;         // 下次执行时间（毫秒时间戳）
+    paused: boolean;                // 是否单独暂停
 }
 
+// 通知投递记录
+export interface NotifyDelivery {
+    id: number;
+    channel: string;
+    aniItemId: number | null;       // 合并通知时为空
+    title: string;
+    status: 'ok' | 'failed';
+    attempts: number;
+    error: string | null;
+    deliveredTime: number;
+}
+
+// 定义所有 后端已注册平台的标识
+export type PlatformId =
+    | 'bilibili'
+    | 'iqiyi'
+    | 'mikanani'
+    | 'tencent'
+    | 'youku'
+    | 'agedm'
+
 // 数据源描述
 type DataSource = {
     name: string;
-    url?: string;
-    cmd: FetchCmd;
+    url: string;
+    platform: PlatformId;
 };
 
 /**
@@ -75,37 +180,37 @@ export const dataSources: DataSource[] = [
     {
         name: '哔哩哔哩国创',
         url: 'https://api.bilibili.com/pgc/web/timeline?types=4&before=6&after=6',
-        cmd: 'fetch_bilibili_ani_data',
+        platform: 'bilibili',
     },
     {
         name: '哔哩哔哩番剧',
         url: 'https://api.bilibili.com/pgc/web/timeline?types=1&before=6&after=6',
-        cmd: 'fetch_bilibili_ani_data',
+        platform: 'bilibili',
     },
     {
         name: '爱奇艺动漫',
         url: 'https://mesh.if.iqiyi.com/portal/lw/v7/channel/cartoon',
-        cmd: 'fetch_iqiyi_ani_data',
+        platform: 'iqiyi',
     },
     /*                {
                         name: '蜜柑计划',
                         url: 'https://mikanani.me',
-                        cmd: 'fetch_mikanani_ani_data',
+                        platform: 'mikanani',
                     },*/
     {
         name: '腾讯视频',
         url: 'https://v.qq.com/channel/cartoon',
-        cmd: 'fetch_qq_ani_data',
+        platform: 'tencent',
     },
     {
         name: '优酷视频',
         url: 'https://www.youku.com/ku/webcomic',
-        cmd: 'fetch_youku_ani_data',
+        platform: 'youku',
     },
     {
         name: 'AGE动漫',
         url: 'https://www.agedm.vip/update',
-        cmd: 'fetch_agedm_ani_data'
+        platform: 'agedm'
     },
     // ...其他接口
 ];
@@ -116,11 +221,23 @@ export const dataSources: DataSource[] = [
  * args：前端传参类型；
  * result：命令返回的类型
  */
-export type ApiCommands = FetchCommandsMap & {
+export type ApiCommands = {
+    fetch_ani_data: {
+        args: { platform: PlatformId; url: string }
+        result: Record<string, Ani[]>
+    }
     query_today_update_ani_list: {
         args: undefined
         result: Record<string, Ani[]>
     }
+    query_week_schedule_ani_list: {
+        args: undefined
+        result: Record<string, Ani[]>   // 键为更新日期 YYYY/MM/DD
+    }
+    query_upcoming_ani_list: {
+        args: undefined
+        result: Ani[]
+    }
     save_ani_item_data: {
         args: { aniData?: Record<string, Ani[]> }
         result: { message: string }
@@ -133,6 +250,86 @@ export type ApiCommands = FetchCommandsMap & {
         args: { aniId: number }
         result: { message: string }
     }
+    watch_ani_episodes: {
+        args: { seriesId: number; fromEpisode: number; toEpisode: number }
+        result: { added: number }
+    }
+    unwatch_ani_episodes: {
+        args: { seriesId: number; fromEpisode: number; toEpisode: number }
+        result: { removed: number }
+    }
+    query_series_progress_list: {
+        args: undefined
+        result: SeriesProgress[]
+    }
+    export_followed_calendar: {
+        args: { filePath: string }
+        result: { path: string }
+    }
+    mute_series_notification: {
+        args: { seriesId: number; muted: boolean }
+        result: { seriesId: number; muted: boolean }
+    }
+    query_notify_delivery_list: {
+        args: { limit: number }
+        result: NotifyDelivery[]
+    }
+    refresh_ani_data: {
+        args: { taskName: string | null }
+        result: { started: number }
+    }
+    query_task_list: {
+        args: undefined
+        result: TaskInfo[]
+    }
+    pause_task: {
+        args: { taskName: string }
+        result: { taskName: string }
+    }
+    resume_task: {
+        args: { taskName: string }
+        result: { taskName: string }
+    }
+    reschedule_task: {
+        args: { taskName: string; cronExpr: string }
+        result: { taskName: string; cronExpr: string }
+    }
+    query_task_run_list: {
+        args: { taskName: string | null; limit: number }
+        result: TaskRun[]
+    }
+    query_task_health_list: {
+        args: undefined
+        result: TaskHealth[]
+    }
+    query_config_status: {
+        args: undefined
+        result: ConfigStatus
+    }
+    check_config: {
+        args: { content: string | null }
+        result: ConfigDiagnostic[]
+    }
+    query_data_source_list: {
+        args: undefined
+        result: DataSourceRecord[]
+    }
+    add_data_source: {
+        args: { category: string; source: DataSourceForm }
+        result: { id: number }
+    }
+    update_data_source: {
+        args: { id: number; category: string; source: DataSourceForm }
+        result: { id: number }
+    }
+    delete_data_source: {
+        args: { id: number }
+        result: { id: number }
+    }
+    set_data_source_enabled: {
+        args: { id: number; enabled: boolean }
+        result: { id: number; enabled: boolean }
+    }
     query_favorite_ani_update_list: {
         args: undefined
         result: Ani[]
@@ -145,6 +342,10 @@ export type ApiCommands = FetchCommandsMap & {
         args: { aniId: number; aniTitle: string }
         result: { message: string }
     }
+    reload_title_alias_rules: {
+        args: undefined
+        result: { rules: number; merged: number }
+    }
     query_ani_history_list: {
         args: {
             page: number;       // 当前页
@@ -182,16 +383,26 @@ export async function invokeApi<K extends keyof ApiCommands>(
 export const api = {
     /**
      * 动态抓取任意数据源
-     * @param cmd 必须是 FetchCmd 之一
+     * @param platform 必须是 PlatformId 之一
      * @param url 该源对应的请求 URL
      */
-    fetchAniData: (cmd: FetchCmd, url: string) =>
-        invokeApi(cmd, { url }),
+    fetchAniData: (platform: PlatformId, url: string) =>
+        invokeApi('fetch_ani_data', { platform, url }),
     /**
      * 获取今日更新的动漫列表
      * */
     queryTodayUpdateAniList: () =>
         invokeApi('query_today_update_ani_list', undefined),
+    /**
+     * 获取本周（周一到周日）的更新日历，包含即将更新的剧集
+     * */
+    queryWeekScheduleAniList: () =>
+        invokeApi('query_week_schedule_ani_list', undefined),
+    /**
+     * 获取未来七天内即将更新的动漫列表
+     * */
+    queryUpcomingAniList: () =>
+        invokeApi('query_upcoming_ani_list', undefined),
     /**
      * 保存动画数据
      * */
@@ -207,6 +418,116 @@ export const api = {
      * */
     clearAni: (aniId: number) =>
         invokeApi('watch_ani_item', {aniId}),
+    /**
+     * 把番剧第 fromEpisode ~ toEpisode 集标记为已看
+     * */
+    watchEpisodes: (seriesId: number, fromEpisode: number, toEpisode: number) =>
+        invokeApi('watch_ani_episodes', {seriesId, fromEpisode, toEpisode}),
+    /**
+     * 取消番剧第 fromEpisode ~ toEpisode 集的已看标记
+     * */
+    unwatchEpisodes: (seriesId: number, fromEpisode: number, toEpisode: number) =>
+        invokeApi('unwatch_ani_episodes', {seriesId, fromEpisode, toEpisode}),
+    /**
+     * 查询关注番剧的观看进度
+     * */
+    querySeriesProgressList: () =>
+        invokeApi('query_series_progress_list', undefined),
+    /**
+     * 把关注番剧的更新日历导出为 .ics 文件
+     * */
+    exportFollowedCalendar: (filePath: string) =>
+        invokeApi('export_followed_calendar', {filePath}),
+    /**
+     * 开启或关闭番剧的更新通知
+     * */
+    muteSeriesNotification: (seriesId: number, muted: boolean) =>
+        invokeApi('mute_series_notification', {seriesId, muted}),
+    /**
+     * 查询最近的通知投递记录
+     * */
+    queryNotifyDeliveryList: (limit = 50) =>
+        invokeApi('query_notify_delivery_list', {limit}),
+    /**
+     * 立即刷新指定数据源（为空时刷新全部），进度通过 onTaskProgress 获取
+     * */
+    refreshAniData: (taskName: string | null = null) =>
+        invokeApi('refresh_ani_data', {taskName}),
+    /**
+     * 监听任务执行进度，返回取消监听的函数
+     * */
+    onTaskProgress: (handler: (event: TaskEvent) => void): Promise<UnlistenFn> =>
+        listen<TaskEvent>('task-progress', (e) => handler(e.payload)),
+    /**
+     * 查询定时任务列表及下次执行时间
+     * */
+    queryTaskList: () =>
+        invokeApi('query_task_list', undefined),
+    /**
+     * 暂停任务的定时执行
+     * */
+    pauseTask: (taskName: string) =>
+        invokeApi('pause_task', {taskName}),
+    /**
+     * 恢复任务的定时执行
+     * */
+    resumeTask: (taskName: string) =>
+        invokeApi('resume_task', {taskName}),
+    /**
+     * 修改任务的 cron 表达式，保存到数据源，之后不再跟随配置文件更新
+     * */
+    rescheduleTask: (taskName: string, cronExpr: string) =>
+        invokeApi('reschedule_task', {taskName, cronExpr}),
+    /**
+     * 查询最近的任务执行记录，taskName 为空时查询所有任务
+     * */
+    queryTaskRunList: (taskName: string | null = null, limit = 50) =>
+        invokeApi('query_task_run_list', {taskName, limit}),
+    /**
+     * 查询各数据源的健康状况
+     * */
+    queryTaskHealthList: () =>
+        invokeApi('query_task_health_list', undefined),
+    /**
+     * 查询配置目录和配置检查结果
+     * */
+    queryConfigStatus: () =>
+        invokeApi('query_config_status', undefined),
+    /**
+     * 检查配置，content 为准备保存的 config.yaml 内容（为空时检查当前配置），保存前调用
+     * */
+    checkConfig: (content: string | null = null) =>
+        invokeApi('check_config', {content}),
+    /**
+     * 监听配置文件修改后的重新加载结果（没有 error 级别的检查结果表示已生效），返回取消监听的函数
+     * */
+    onConfigReloaded: (handler: (status: Pick<ConfigStatus, 'diagnostics'>) => void): Promise<UnlistenFn> =>
+        listen<Pick<ConfigStatus, 'diagnostics'>>('config-reloaded', (e) => handler(e.payload)),
+    /**
+     * 查询所有数据源
+     * */
+    queryDataSourceList: () =>
+        invokeApi('query_data_source_list', undefined),
+    /**
+     * 新增数据源，立即创建定时任务并执行一次
+     * */
+    addDataSource: (category: string, source: DataSourceForm) =>
+        invokeApi('add_data_source', {category, source}),
+    /**
+     * 修改数据源，修改后不再跟随配置文件更新
+     * */
+    updateDataSource: (id: number, category: string, source: DataSourceForm) =>
+        invokeApi('update_data_source', {id, category, source}),
+    /**
+     * 删除数据源，配置文件中的同名数据源也不会再添加
+     * */
+    deleteDataSource: (id: number) =>
+        invokeApi('delete_data_source', {id}),
+    /**
+     * 启用或停用数据源
+     * */
+    setDataSourceEnabled: (id: number, enabled: boolean) =>
+        invokeApi('set_data_source_enabled', {id, enabled}),
     /**
      * 查询关注动漫今日更新的动画列表
      * */
@@ -223,6 +544,12 @@ export const api = {
     cancelCollectAni: (aniId: number, aniTitle: string) =>
         invokeApi('cancel_collect_ani_item', {aniId, aniTitle}),
 
+    /**
+     * 重新加载标题别名规则（title_aliases.yaml），并按新规则重新匹配番剧
+     */
+    reloadTitleAliasRules: () =>
+        invokeApi('reload_title_alias_rules', undefined),
+
     /**
      * 查询动漫历史列表
      */